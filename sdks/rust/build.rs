@@ -2,6 +2,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .build_server(false)
         .build_client(true)
-        .compile_protos(&["../../proto/agent_platform.proto"], &["../../proto"])?;
+        .compile_protos(
+            &[
+                "../../proto/agent_platform.proto",
+                "proto/reflection.proto",
+                "proto/health.proto",
+            ],
+            &["../../proto", "proto"],
+        )?;
     Ok(())
 }
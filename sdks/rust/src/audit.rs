@@ -0,0 +1,202 @@
+//! Verification of signed audit log exports.
+//!
+//! Each exported entry carries `SHA-256(previous hash || payload)`, starting
+//! from the export's anchor hash, and the server signs the last hash in the
+//! chain. The signature covers only that end of the chain, so [`verify`]
+//! also takes the anchor the caller expects: [`LOG_START`] for an export
+//! from the start of the log, or the last hash of the export before it.
+//! With both ends pinned, any edited, dropped, or reordered entry is
+//! detected.
+
+use crate::client::audit_from_proto;
+use crate::error::{Result, SdkError};
+use crate::models::SignedAuditExport;
+use crate::proto::AuditEntryProto;
+use crate::sha256;
+use prost::Message;
+
+/// The anchor hash of an export that starts at the beginning of the log.
+pub const LOG_START: [u8; 32] = [0; 32];
+
+/// Checks a signature made with the control plane's audit signing key.
+///
+/// The SDK has no crypto dependency, so implement this with the library and
+/// public key your deployment uses (e.g. Ed25519).
+pub trait SignatureVerifier {
+    /// Returns whether `signature` is valid for `message`.
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool;
+}
+
+impl<F: Fn(&[u8], &[u8]) -> bool> SignatureVerifier for F {
+    fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        self(message, signature)
+    }
+}
+
+/// Validates the hash chain and signature of `export`, returning
+/// [`SdkError::IntegrityViolation`] at the first inconsistency.
+///
+/// The chain must start from `expected_anchor`, so an export with leading
+/// entries cut off is rejected even though its signature still matches.
+/// Each entry's decoded form must match its signed payload.
+pub fn verify(
+    export: &SignedAuditExport,
+    expected_anchor: &[u8],
+    public_key: &dyn SignatureVerifier,
+) -> Result<()> {
+    if export.anchor_hash != expected_anchor {
+        return Err(SdkError::IntegrityViolation(
+            "export does not start at the expected anchor hash".into(),
+        ));
+    }
+    let mut previous = export.anchor_hash.as_slice();
+    for (i, e) in export.entries.iter().enumerate() {
+        let expected = sha256::digest(&[previous, &e.payload]);
+        if e.hash != expected {
+            return Err(SdkError::IntegrityViolation(format!(
+                "hash mismatch at entry {i} ({})",
+                e.entry.entry_id
+            )));
+        }
+        let signed = AuditEntryProto::decode(e.payload.as_slice())
+            .map(audit_from_proto)
+            .map_err(|err| {
+                SdkError::IntegrityViolation(format!("entry {i} payload does not decode: {err}"))
+            })?;
+        if signed != e.entry {
+            return Err(SdkError::IntegrityViolation(format!(
+                "entry {i} ({}) differs from its signed payload",
+                e.entry.entry_id
+            )));
+        }
+        previous = &e.hash;
+    }
+    if !public_key.verify(previous, &export.signature) {
+        return Err(SdkError::IntegrityViolation(format!(
+            "signature does not match key {}",
+            export.key_id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::SignedAuditEntry;
+
+    /// Stands in for a real signature: a digest with a secret prefix.
+    fn sign(message: &[u8]) -> Vec<u8> {
+        sha256::digest(&[b"test-key", message]).to_vec()
+    }
+
+    fn signer(message: &[u8], signature: &[u8]) -> bool {
+        sign(message) == signature
+    }
+
+    /// A signed export of entries `ids`, chained from `anchor`.
+    fn export(anchor: &[u8], ids: &[&str]) -> SignedAuditExport {
+        let mut previous = anchor.to_vec();
+        let entries = ids
+            .iter()
+            .map(|id| {
+                let payload = AuditEntryProto {
+                    entry_id: id.to_string(),
+                    org_id: "org-1".into(),
+                    action: "tool_call".into(),
+                    ..Default::default()
+                }
+                .encode_to_vec();
+                let hash = sha256::digest(&[&previous, &payload]).to_vec();
+                previous.clone_from(&hash);
+                SignedAuditEntry {
+                    entry: audit_from_proto(AuditEntryProto::decode(payload.as_slice()).unwrap()),
+                    payload,
+                    hash,
+                }
+            })
+            .collect();
+        SignedAuditExport {
+            entries,
+            anchor_hash: anchor.to_vec(),
+            signature: sign(&previous),
+            key_id: "key-1".into(),
+        }
+    }
+
+    fn rejected(export: &SignedAuditExport, anchor: &[u8]) -> String {
+        match verify(export, anchor, &signer) {
+            Err(SdkError::IntegrityViolation(m)) => m,
+            other => panic!("expected an integrity violation, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_chains_verify() {
+        let first = export(&LOG_START, &["e1", "e2", "e3"]);
+        verify(&first, &LOG_START, &signer).unwrap();
+        // The next export picks up from the last hash of the first.
+        let last = first.entries.last().unwrap().hash.clone();
+        verify(&export(&last, &["e4"]), &last, &signer).unwrap();
+        verify(&export(&LOG_START, &[]), &LOG_START, &signer).unwrap();
+    }
+
+    #[test]
+    fn edited_payloads_and_entries_are_rejected() {
+        let mut edited = export(&LOG_START, &["e1", "e2"]);
+        edited.entries[1].payload.push(0);
+        assert_eq!(
+            rejected(&edited, &LOG_START),
+            "hash mismatch at entry 1 (e2)"
+        );
+
+        let mut relabelled = export(&LOG_START, &["e1", "e2"]);
+        relabelled.entries[0].entry.action = "nothing_happened".into();
+        assert_eq!(
+            rejected(&relabelled, &LOG_START),
+            "entry 0 (e1) differs from its signed payload"
+        );
+    }
+
+    #[test]
+    fn reordered_entries_are_rejected() {
+        let mut reordered = export(&LOG_START, &["e1", "e2", "e3"]);
+        reordered.entries.swap(1, 2);
+        assert_eq!(
+            rejected(&reordered, &LOG_START),
+            "hash mismatch at entry 1 (e3)"
+        );
+    }
+
+    #[test]
+    fn trimmed_prefixes_are_rejected() {
+        let full = export(&LOG_START, &["e1", "e2", "e3"]);
+        let mut trimmed = full.clone();
+        trimmed.anchor_hash = trimmed.entries.remove(0).hash;
+        // Internally consistent and still signed, but not from the expected start.
+        let anchor = trimmed.anchor_hash.clone();
+        verify(&trimmed, &anchor, &signer).unwrap();
+        assert_eq!(
+            rejected(&trimmed, &LOG_START),
+            "export does not start at the expected anchor hash"
+        );
+    }
+
+    #[test]
+    fn bad_signatures_are_rejected() {
+        let mut forged = export(&LOG_START, &["e1"]);
+        forged.signature = sign(b"something else");
+        assert_eq!(
+            rejected(&forged, &LOG_START),
+            "signature does not match key key-1"
+        );
+
+        // Dropping the last entry moves the end of the chain off the signed hash.
+        let mut truncated = export(&LOG_START, &["e1", "e2"]);
+        truncated.entries.pop();
+        assert_eq!(
+            rejected(&truncated, &LOG_START),
+            "signature does not match key key-1"
+        );
+    }
+}
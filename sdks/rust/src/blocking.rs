@@ -0,0 +1,648 @@
+//! A synchronous client for code that doesn't run inside a tokio runtime,
+//! such as CLI tools. Enabled by the `blocking` feature.
+//!
+//! [`AgentPlatformClient`] mirrors the async
+//! [`AgentPlatformClient`](crate::AgentPlatformClient) method for method and
+//! returns the same types; each call blocks on a runtime owned by the client.
+//! Streams become [`BlockingStream`]s, which are iterators.
+//!
+//! Calling these methods from within an async runtime panics. Async code
+//! should use the async client directly.
+//!
+//! # Example
+//! ```no_run
+//! use agent_platform_sdk::blocking::AgentPlatformClient;
+//!
+//! let mut client = AgentPlatformClient::connect("http://localhost:50051")?;
+//! let org = client.create_org("acme")?;
+//! let agent = client.register_agent(&org.org_id, "planner", "worker", None)?;
+//! let decision = client.evaluate_policy(&org.org_id, &agent.agent.agent_id, "search", 500)?;
+//! println!("allowed: {}", decision.allowed);
+//! # Ok::<(), agent_platform_sdk::SdkError>(())
+//! ```
+
+use crate::error::{Result, SdkError};
+use crate::models::*;
+use crate::policy::PolicyBuilder;
+use crate::profile::ClientProfile;
+use crate::retry::RetryPolicy;
+use crate::stream::{EventStream, StreamConfig};
+use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Range;
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::runtime::Runtime;
+use tokio_stream::StreamExt;
+
+/// Blocking counterpart of [`crate::AgentPlatformClient`]. Clones share the
+/// channel and the runtime, so each thread can hold its own handle.
+#[derive(Clone)]
+pub struct AgentPlatformClient {
+    inner: crate::AgentPlatformClient,
+    rt: Arc<Runtime>,
+}
+
+/// Events from a streaming call, delivered by a blocking iterator. Dropping
+/// it cancels the underlying RPC.
+pub struct BlockingStream<T> {
+    inner: EventStream<T>,
+    rt: Arc<Runtime>,
+}
+
+impl<T> Iterator for BlockingStream<T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.rt.block_on(self.inner.next())
+    }
+}
+
+/// Blocking counterpart of [`crate::guard::Guard`].
+#[must_use = "a guard reports usage only when finished"]
+pub struct Guard {
+    inner: crate::guard::Guard,
+    rt: Arc<Runtime>,
+}
+
+impl Guard {
+    pub fn execution_id(&self) -> &str {
+        self.inner.execution_id()
+    }
+
+    pub fn decision(&self) -> &PolicyDecision {
+        self.inner.decision()
+    }
+
+    pub fn budget(&self) -> &BudgetCheck {
+        self.inner.budget()
+    }
+
+    /// See [`finish`](crate::guard::Guard::finish).
+    pub fn finish(
+        self,
+        actual_tokens: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+    ) -> Result<i64> {
+        self.rt.block_on(
+            self.inner
+                .finish(actual_tokens, tool_invocations, duration_ms),
+        )
+    }
+}
+
+/// Wraps async methods that return a plain result.
+macro_rules! unary {
+    ($(fn $name:ident(&mut self $(, $arg:ident: $ty:ty)*) -> $ret:ty;)*) => {
+        $(
+            #[doc = concat!(
+                "Blocking form of [`", stringify!($name),
+                "`](crate::AgentPlatformClient::", stringify!($name), ")."
+            )]
+            #[allow(clippy::too_many_arguments)]
+            pub fn $name(&mut self $(, $arg: $ty)*) -> $ret {
+                self.rt.block_on(self.inner.$name($($arg),*))
+            }
+        )*
+    };
+}
+
+/// Wraps async methods that return an [`EventStream`].
+macro_rules! streaming {
+    ($(fn $name:ident(&mut self $(, $arg:ident: $ty:ty)*) -> $item:ty;)*) => {
+        $(
+            #[doc = concat!(
+                "Blocking form of [`", stringify!($name),
+                "`](crate::AgentPlatformClient::", stringify!($name), ")."
+            )]
+            pub fn $name(&mut self $(, $arg: $ty)*) -> Result<BlockingStream<$item>> {
+                let inner = self.rt.block_on(self.inner.$name($($arg),*))?;
+                Ok(BlockingStream { inner, rt: Arc::clone(&self.rt) })
+            }
+        )*
+    };
+}
+
+/// Wraps builder methods that configure the client by value.
+macro_rules! builder {
+    ($(fn $name:ident(self $(, $arg:ident: $ty:ty)*) -> Self;)*) => {
+        $(
+            #[doc = concat!(
+                "See [`", stringify!($name),
+                "`](crate::AgentPlatformClient::", stringify!($name), ")."
+            )]
+            pub fn $name(self $(, $arg: $ty)*) -> Self {
+                Self { inner: self.inner.$name($($arg),*), rt: self.rt }
+            }
+        )*
+    };
+}
+
+impl AgentPlatformClient {
+    pub fn connect(addr: &str) -> Result<Self> {
+        Self::start(|| crate::AgentPlatformClient::connect(addr))
+    }
+
+    /// See [`connect_with_failover`](crate::AgentPlatformClient::connect_with_failover).
+    pub fn connect_with_failover(primary: &str, secondaries: Vec<String>) -> Result<Self> {
+        Self::start(|| crate::AgentPlatformClient::connect_with_failover(primary, secondaries))
+    }
+
+    /// See [`from_profile`](crate::AgentPlatformClient::from_profile).
+    #[cfg(feature = "serde")]
+    pub fn from_profile(path: impl AsRef<Path>, profile_name: &str) -> Result<Self> {
+        Self::start(|| crate::AgentPlatformClient::from_profile(path, profile_name))
+    }
+
+    /// See [`connect_profile`](crate::AgentPlatformClient::connect_profile).
+    pub fn connect_profile(profile: &ClientProfile) -> Result<Self> {
+        Self::start(|| crate::AgentPlatformClient::connect_profile(profile))
+    }
+
+    /// Wraps an already configured async client, running its calls on a new
+    /// runtime. The client's channels must not be tied to another runtime
+    /// that may shut down first.
+    pub fn from_async(inner: crate::AgentPlatformClient) -> Result<Self> {
+        Ok(Self {
+            inner,
+            rt: Arc::new(runtime()?),
+        })
+    }
+
+    fn start<F, Fut>(connect: F) -> Result<Self>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<crate::AgentPlatformClient>>,
+    {
+        let rt = runtime()?;
+        let inner = rt.block_on(connect())?;
+        Ok(Self {
+            inner,
+            rt: Arc::new(rt),
+        })
+    }
+
+    builder! {
+        fn policy_cache(self, default_ttl: Duration) -> Self;
+        fn read_only(self) -> Self;
+        fn with_timeout(self, timeout: Duration) -> Self;
+        fn retry_policy(self, policy: RetryPolicy) -> Self;
+    }
+
+    /// See [`on_warning`](crate::AgentPlatformClient::on_warning).
+    pub fn on_warning(self, handler: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        Self {
+            inner: self.inner.on_warning(handler),
+            rt: self.rt,
+        }
+    }
+
+    /// See [`auth_token_provider`](crate::AgentPlatformClient::auth_token_provider).
+    pub fn auth_token_provider(
+        self,
+        provider: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: self.inner.auth_token_provider(provider),
+            rt: self.rt,
+        }
+    }
+
+    /// See [`credential_refresher`](crate::AgentPlatformClient::credential_refresher).
+    /// The returned future runs on the client's runtime.
+    pub fn credential_refresher<F, Fut>(self, refresher: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = String> + Send + 'static,
+    {
+        Self {
+            inner: self.inner.credential_refresher(refresher),
+            rt: self.rt,
+        }
+    }
+
+    /// See [`instance_id`](crate::AgentPlatformClient::instance_id).
+    pub fn instance_id(self, id: &str) -> Result<Self> {
+        Ok(Self {
+            inner: self.inner.instance_id(id)?,
+            rt: self.rt,
+        })
+    }
+
+    /// See [`with_metadata`](crate::AgentPlatformClient::with_metadata).
+    pub fn with_metadata(self, key: &str, value: &str) -> Result<Self> {
+        Ok(Self {
+            inner: self.inner.with_metadata(key, value)?,
+            rt: self.rt,
+        })
+    }
+
+    /// See [`metadata_provider`](crate::AgentPlatformClient::metadata_provider).
+    pub fn metadata_provider(
+        self,
+        provider: impl Fn() -> Vec<(String, String)> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner: self.inner.metadata_provider(provider),
+            rt: self.rt,
+        }
+    }
+
+    /// See [`auth_token`](crate::AgentPlatformClient::auth_token).
+    pub fn auth_token(self, token: &str) -> Result<Self> {
+        Ok(Self {
+            inner: self.inner.auth_token(token)?,
+            rt: self.rt,
+        })
+    }
+
+    /// See [`pool_weighted`](crate::AgentPlatformClient::pool_weighted).
+    pub fn pool_weighted(self, endpoints: Vec<(String, u32)>) -> Result<Self> {
+        // Lazy channels spawn their workers on the current runtime.
+        let _guard = self.rt.enter();
+        Ok(Self {
+            inner: self.inner.pool_weighted(endpoints)?,
+            rt: Arc::clone(&self.rt),
+        })
+    }
+
+    #[cfg(feature = "testing")]
+    /// See [`fault_injection`](crate::AgentPlatformClient::fault_injection).
+    pub fn fault_injection(self, config: crate::fault::FaultConfig) -> Self {
+        Self {
+            inner: self.inner.fault_injection(config),
+            rt: self.rt,
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    /// See [`latency_snapshot`](crate::AgentPlatformClient::latency_snapshot).
+    pub fn latency_snapshot(&self) -> HashMap<String, crate::metrics::HistogramSnapshot> {
+        self.inner.latency_snapshot()
+    }
+
+    #[cfg(feature = "metrics")]
+    /// See [`render_metrics`](crate::AgentPlatformClient::render_metrics).
+    pub fn render_metrics(&self) -> String {
+        self.inner.render_metrics()
+    }
+
+    /// See [`clear_policy_cache`](crate::AgentPlatformClient::clear_policy_cache).
+    pub fn clear_policy_cache(&mut self) {
+        self.inner.clear_policy_cache();
+    }
+
+    /// The async client underneath, for calls this wrapper doesn't cover.
+    pub fn get_mut(&mut self) -> &mut crate::AgentPlatformClient {
+        &mut self.inner
+    }
+
+    unary! {
+        fn warm_up(&mut self) -> Result<()>;
+
+        // --- Organizations ---
+        fn create_org(&mut self, name: &str) -> Result<Org>;
+        fn create_org_with_metadata(&mut self, name: &str, metadata: &HashMap<String, String>) -> Result<Org>;
+        fn create_orgs(&mut self, specs: Vec<OrgSpec>) -> Result<BatchResult<Org>>;
+        fn create_org_with_admin(
+            &mut self,
+            name: &str,
+            admin_name: &str,
+            admin_role: impl Into<Role>,
+            claims: &HashMap<String, String>
+        ) -> Result<(Org, RegisteredAgent)>;
+        fn get_org(&mut self, org_id: &str) -> Result<Org>;
+        fn get_org_with_fields(&mut self, org_id: &str, fields: &[&str]) -> Result<Org>;
+        fn list_orgs(&mut self) -> Result<Vec<Org>>;
+        fn list_orgs_with_fields(&mut self, fields: &[&str]) -> Result<Vec<Org>>;
+        fn list_orgs_with_tombstones(&mut self) -> Result<Vec<Org>>;
+        fn list_orgs_by_metadata(&mut self, key: &str, value: &str) -> Result<Vec<Org>>;
+        fn list_orgs_paged(&mut self, page_size: i32, page_token: Option<&str>) -> Result<Page<Org>>;
+        fn list_all_orgs(&mut self) -> Result<Vec<Org>>;
+        fn update_org(&mut self, org_id: &str, name: &str) -> Result<Org>;
+        fn update_org_with_metadata(
+            &mut self,
+            org_id: &str,
+            name: &str,
+            metadata: &HashMap<String, String>
+        ) -> Result<Org>;
+        fn delete_org(&mut self, org_id: &str) -> Result<bool>;
+        fn delete_org_with_options(&mut self, org_id: &str, cascade: bool) -> Result<bool>;
+        fn delete_org_dry_run(&mut self, org_id: &str) -> Result<DeleteImpact>;
+
+        // --- Agents ---
+        fn register_agent(
+            &mut self,
+            org_id: &str,
+            name: &str,
+            role: impl Into<Role>,
+            delegated_user_id: Option<&str>
+        ) -> Result<RegisteredAgent>;
+        fn register_agent_with_claims(
+            &mut self,
+            org_id: &str,
+            name: &str,
+            role: impl Into<Role>,
+            delegated_user_id: Option<&str>,
+            claims: HashMap<String, String>
+        ) -> Result<RegisteredAgent>;
+        fn register_agents(&mut self, org_id: &str, specs: &[AgentSpec]) -> Result<Vec<RegisteredAgent>>;
+        fn get_agent(&mut self, org_id: &str, agent_id: &str) -> Result<Agent>;
+        fn effective_user(&mut self, org_id: &str, agent_id: &str) -> Result<Option<String>>;
+        fn get_agent_card(&mut self, org_id: &str, agent_id: &str) -> Result<AgentCard>;
+        fn get_agent_with_fields(&mut self, org_id: &str, agent_id: &str, fields: &[&str]) -> Result<Agent>;
+        fn list_agents(&mut self, org_id: &str) -> Result<Vec<Agent>>;
+        fn list_agents_into(&mut self, org_id: &str, buf: &mut Vec<Agent>) -> Result<()>;
+        fn list_agents_with_fields(&mut self, org_id: &str, fields: &[&str]) -> Result<Vec<Agent>>;
+        fn list_agents_with_tombstones(&mut self, org_id: &str) -> Result<Vec<Agent>>;
+        fn list_agents_paged(
+            &mut self,
+            org_id: &str,
+            page_size: i32,
+            page_token: Option<&str>
+        ) -> Result<Page<Agent>>;
+        fn list_all_agents(&mut self, org_id: &str) -> Result<Vec<Agent>>;
+        fn list_agents_with_tool(&mut self, org_id: &str, tool_name: &str) -> Result<Vec<Agent>>;
+        fn reconcile_agents(&mut self, org_id: &str, local: Vec<Agent>) -> Result<ReconcileReport>;
+        fn validate_credential(&mut self, agent_id: &str, token: &str) -> Result<CredentialStatus>;
+        fn deactivate_agent(&mut self, org_id: &str, agent_id: &str, reason: Option<&str>) -> Result<bool>;
+        fn update_agent(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            name: Option<&str>,
+            role: Option<Role>,
+            delegated_user_id: Option<Option<&str>>
+        ) -> Result<Agent>;
+        fn reactivate_agent(&mut self, org_id: &str, agent_id: &str) -> Result<bool>;
+        fn offboard_user(&mut self, org_id: &str, user_id: &str) -> Result<Vec<String>>;
+        fn get_agent_config(&mut self, org_id: &str, agent_id: &str) -> Result<AgentConfig>;
+        fn get_scheduling_info(&mut self, org_id: &str, agent_ids: Vec<String>) -> Result<Vec<SchedulingInfo>>;
+
+        // --- Policy ---
+        fn set_policy(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            allowed_tools: &[&str],
+            denied_tools: &[&str],
+            token_limit: i64,
+            timeout_seconds: i32
+        ) -> Result<String>;
+        fn set_policy_detailed(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            tools: &[ToolPermission],
+            token_limit: i64,
+            timeout_seconds: i32,
+            mode: PolicyMode
+        ) -> Result<String>;
+        fn patch_policy(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            add: Vec<ToolPermission>,
+            remove: Vec<String>
+        ) -> Result<Policy>;
+        fn set_policy_from(&mut self, policy: PolicyBuilder) -> Result<String>;
+        fn get_policy_at(&mut self, org_id: &str, agent_id: Option<&str>, at: SystemTime) -> Result<Option<Policy>>;
+        fn set_default_agent_policy(
+            &mut self,
+            org_id: &str,
+            permissions: &[ToolPermission],
+            token_limit: i64,
+            timeout_seconds: i32
+        ) -> Result<String>;
+        fn get_default_agent_policy(&mut self, org_id: &str) -> Result<Option<Policy>>;
+        fn get_policy(&mut self, org_id: &str, policy_id: &str) -> Result<Policy>;
+        fn list_policies(&mut self, org_id: &str) -> Result<Vec<Policy>>;
+        fn lint_policies(&mut self, org_id: &str) -> Result<Vec<PolicyLintFinding>>;
+        fn delete_policy(&mut self, org_id: &str, policy_id: &str) -> Result<bool>;
+        fn get_policy_hash(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<String>;
+        fn evaluate_policy(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            tool_name: &str,
+            estimated_tokens: i64
+        ) -> Result<PolicyDecision>;
+        fn evaluate_policy_with_context(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            tool_name: &str,
+            estimated_tokens: i64,
+            context: serde_json::Map<String, serde_json::Value>
+        ) -> Result<PolicyDecision>;
+        fn evaluate_policy_with_timeout(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            tool_name: &str,
+            estimated_tokens: i64,
+            timeout: Duration
+        ) -> Result<PolicyDecision>;
+        fn evaluate_policies(&mut self, org_id: &str, agent_id: &str, tools: &[(&str, i64)]) -> Result<Vec<PolicyDecision>>;
+        fn evaluate_matrix(
+            &mut self,
+            org_id: &str,
+            agent_ids: Vec<String>,
+            tool_names: Vec<String>
+        ) -> Result<Vec<Vec<PolicyDecision>>>;
+        fn find_policies_by_tool(&mut self, org_id: &str, tool_name: &str) -> Result<Vec<Policy>>;
+        fn get_effective_policy(&mut self, org_id: &str, agent_id: &str) -> Result<EffectivePolicy>;
+        fn get_effective_policies(&mut self, org_id: &str) -> Result<Vec<(String, EffectivePolicy)>>;
+        fn probe_policy_latency(&mut self, org_id: &str, agent_id: &str) -> Result<Duration>;
+        fn deprecate_tool(&mut self, org_id: &str, tool_name: &str, message: &str) -> Result<bool>;
+        fn schedule_policy(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            permissions: &[ToolPermission],
+            activate_at: SystemTime
+        ) -> Result<String>;
+        fn list_scheduled_changes(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<Vec<ScheduledPolicyChange>>;
+        fn cancel_scheduled_change(&mut self, org_id: &str, change_id: &str) -> Result<bool>;
+
+        // --- Budget ---
+        fn set_budget(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            token_limit: i64,
+            reset_period_days: i32
+        ) -> Result<BudgetInfo>;
+        fn set_budget_with_warning(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            token_limit: i64,
+            reset_period_days: i32,
+            warning_threshold_percent: Option<u8>
+        ) -> Result<BudgetInfo>;
+        fn get_budget(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<BudgetInfo>;
+        fn reset_budget(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<BudgetInfo>;
+        fn set_budgets(&mut self, org_id: &str, budgets: Vec<(String, i64, i32)>) -> Result<BatchResult<BudgetInfo>>;
+        fn check_budget(&mut self, org_id: &str, agent_id: &str, estimated_tokens: i64) -> Result<BudgetCheck>;
+        fn set_rate_limit(&mut self, org_id: &str, agent_id: Option<&str>, max_per_minute: i32) -> Result<RateLimit>;
+        fn check_rate_limit(&mut self, org_id: &str, agent_id: &str) -> Result<RateLimitCheck>;
+        fn export_org_config(&mut self, org_id: &str) -> Result<OrgConfig>;
+        fn import_org_config(
+            &mut self,
+            org_id: &str,
+            config: &OrgConfig,
+            create_agents: bool
+        ) -> Result<Vec<RegisteredAgent>>;
+        fn check_budget_with_reservation(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            estimated_tokens: i64
+        ) -> Result<BudgetCheck>;
+        fn reserve_budget(&mut self, org_id: &str, agent_id: &str, estimated_tokens: i64) -> Result<Reservation>;
+        fn commit_reservation(
+            &mut self,
+            reservation: Reservation,
+            execution_id: &str,
+            actual_tokens: i64
+        ) -> Result<i64>;
+        fn release_reservation(&mut self, reservation: Reservation) -> Result<bool>;
+        fn consume_budget(&mut self, org_id: &str, agent_id: &str, tokens: i64) -> Result<BudgetCheck>;
+        fn transfer_budget(
+            &mut self,
+            org_id: &str,
+            from_agent: &str,
+            to_agent: &str,
+            tokens: i64
+        ) -> Result<(BudgetInfo, BudgetInfo)>;
+        fn check_budget_allocation(&mut self, org_id: &str) -> Result<AllocationReport>;
+        fn recommend_budget(&mut self, org_id: &str, agent_id: &str, target_percentile: f64) -> Result<i64>;
+        fn list_exhausted_agents(&mut self, org_id: &str) -> Result<Vec<(Agent, BudgetInfo)>>;
+        fn list_open_reservations(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<Vec<Reservation>>;
+
+        // --- Usage ---
+        fn report_usage(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            execution_id: &str,
+            tokens_used: i64,
+            tool_invocations: i32,
+            duration_ms: i64
+        ) -> Result<i64>;
+        fn report_usage_with_metadata(
+            &mut self,
+            org_id: &str,
+            agent_id: &str,
+            execution_id: &str,
+            tokens_used: i64,
+            tool_invocations: i32,
+            duration_ms: i64,
+            metadata: HashMap<String, String>
+        ) -> Result<i64>;
+        fn report_usage_record(&mut self, org_id: &str, agent_id: &str, record: UsageRecord) -> Result<i64>;
+        fn report_execution(&mut self, org_id: &str, agent_id: &str, execution: Execution) -> Result<i64>;
+        fn list_usage_reports(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<Vec<UsageReport>>;
+        fn list_executions(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            filter: &ExecutionFilter,
+            page_size: i32,
+            page_token: Option<&str>
+        ) -> Result<Page<UsageReport>>;
+        fn get_usage_summary(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            window: Option<Range<SystemTime>>
+        ) -> Result<UsageSummary>;
+        fn get_usage_by_user(&mut self, org_id: &str) -> Result<Vec<(String, UsageSummary)>>;
+        fn get_usage_by_model(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<Vec<(String, UsageSummary)>>;
+        fn list_used_tools(&mut self, org_id: &str, since: Option<SystemTime>) -> Result<Vec<(String, u64)>>;
+        fn forecast_cost(&mut self, org_id: &str, pricing: &PricingModel, until: SystemTime) -> Result<CostForecast>;
+
+        // --- Audit and server ---
+        fn export_audit_log_signed(&mut self, org_id: &str, range: Range<SystemTime>) -> Result<SignedAuditExport>;
+        fn get_agent_health(&mut self, org_id: &str, agent_id: &str) -> Result<AgentHealth>;
+        fn health_check(&mut self) -> Result<bool>;
+        fn list_server_methods(&mut self) -> Result<Vec<String>>;
+        fn ping_all(&mut self) -> Result<Vec<(String, Result<Duration>)>>;
+        fn server_time(&mut self) -> Result<SystemTime>;
+    }
+
+    /// See [`guard`](crate::AgentPlatformClient::guard).
+    pub fn guard(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> Result<Guard> {
+        let inner =
+            self.rt.block_on(
+                self.inner
+                    .guard(org_id, agent_id, tool_name, estimated_tokens),
+            )?;
+        Ok(Guard {
+            inner,
+            rt: Arc::clone(&self.rt),
+        })
+    }
+
+    /// Blocking form of
+    /// [`report_usage_stream`](crate::AgentPlatformClient::report_usage_stream),
+    /// taking the records up front.
+    pub fn report_usage_stream(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        records: impl IntoIterator<Item = UsageRecord>,
+    ) -> Result<i64> {
+        let records: Vec<UsageRecord> = records.into_iter().collect();
+        self.rt.block_on(self.inner.report_usage_stream(
+            org_id,
+            agent_id,
+            tokio_stream::iter(records),
+        ))
+    }
+
+    streaming! {
+        fn backtest_policy_stream(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            permissions: &[ToolPermission],
+            range: Range<SystemTime>,
+            config: StreamConfig
+        ) -> BacktestEvent;
+        fn watch_budget_resets(&mut self, org_id: &str, config: StreamConfig) -> BudgetResetEvent;
+        fn tail_audit_log(&mut self, org_id: &str, config: StreamConfig) -> AuditEntry;
+        fn tail_audit_log_from(&mut self, org_id: &str, cursor: u64, config: StreamConfig) -> AuditEntry;
+        fn watch_activity(&mut self, org_id: &str, agent_id: Option<&str>, config: StreamConfig) -> ActivityEvent;
+        fn watch_activity_from(
+            &mut self,
+            org_id: &str,
+            agent_id: Option<&str>,
+            cursor: u64,
+            config: StreamConfig
+        ) -> ActivityEvent;
+        fn watch_changes(&mut self, org_id: &str, config: StreamConfig) -> ChangeEvent;
+        fn watch_changes_from(&mut self, org_id: &str, cursor: u64, config: StreamConfig) -> ChangeEvent;
+        fn sample_policy_decisions(&mut self, org_id: &str, rate: f64, config: StreamConfig) -> PolicyDecisionEvent;
+    }
+}
+
+/// Multi-threaded so that background work, such as stream pumps and lazy
+/// channel connections, keeps running between blocking calls.
+fn runtime() -> Result<Runtime> {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .enable_all()
+        .build()
+        .map_err(|e| SdkError::Config(format!("failed to start runtime: {e}")))
+}
@@ -0,0 +1,224 @@
+use crate::client::AgentPlatformClient;
+use crate::error::{Result, SdkError};
+use crate::retry::RetryPolicy;
+use std::time::Duration;
+use tonic::transport::Endpoint;
+
+/// Keep-alive ping interval unless configured. Shorter than the idle
+/// timeouts of common load balancers and NAT gateways (60s and up).
+const DEFAULT_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30);
+/// How long to wait for a keep-alive ack before dropping the connection,
+/// unless configured.
+const DEFAULT_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configures and connects an [`AgentPlatformClient`]; created by
+/// [`AgentPlatformClient::builder`].
+///
+/// # Example
+/// ```no_run
+/// use agent_platform_sdk::AgentPlatformClient;
+/// use std::time::Duration;
+///
+/// # async fn run() -> agent_platform_sdk::error::Result<()> {
+/// let client = AgentPlatformClient::builder()
+///     .endpoint("http://localhost:50051")
+///     .timeout(Duration::from_secs(10))
+///     .keep_alive_interval(Duration::from_secs(20))
+///     .auth_token("secret")
+///     .connect()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ClientBuilder {
+    endpoint: Option<String>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    auth_token: Option<String>,
+    retry: Option<RetryPolicy>,
+    metadata: Vec<(String, String)>,
+    keep_alive_interval: Option<Duration>,
+    keep_alive_timeout: Option<Duration>,
+    keep_alive_while_idle: Option<bool>,
+    tcp_nodelay: Option<bool>,
+}
+
+impl std::fmt::Debug for ClientBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientBuilder")
+            .field("endpoint", &self.endpoint)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("timeout", &self.timeout)
+            .field(
+                "auth_token",
+                &self.auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .field("retry", &self.retry)
+            .field("metadata", &self.metadata)
+            .field("keep_alive_interval", &self.keep_alive_interval)
+            .field("keep_alive_timeout", &self.keep_alive_timeout)
+            .field("keep_alive_while_idle", &self.keep_alive_while_idle)
+            .field("tcp_nodelay", &self.tcp_nodelay)
+            .finish()
+    }
+}
+
+impl ClientBuilder {
+    /// Control plane address, e.g. `http://localhost:50051`. Required.
+    pub fn endpoint(mut self, addr: &str) -> Self {
+        self.endpoint = Some(addr.to_string());
+        self
+    }
+
+    /// Limit on establishing the connection, separate from the per-call
+    /// [`timeout`](Self::timeout).
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Default deadline for every call; see
+    /// [`AgentPlatformClient::with_timeout`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Bearer token sent with every call; see
+    /// [`AgentPlatformClient::auth_token`].
+    pub fn auth_token(mut self, token: &str) -> Self {
+        self.auth_token = Some(token.to_string());
+        self
+    }
+
+    /// Header sent with every call; see
+    /// [`AgentPlatformClient::with_metadata`].
+    pub fn metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// How often to send HTTP/2 keep-alive pings; 30s by default. Pings
+    /// stop proxies from dropping quiet connections and detect dead ones
+    /// early, at the cost of a little traffic; servers may close
+    /// connections that ping more often than they allow. `Duration::ZERO`
+    /// turns pings off.
+    pub fn keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.keep_alive_interval = Some(interval);
+        self
+    }
+
+    /// How long to wait for a ping to be acknowledged before treating the
+    /// connection as dead; 10s by default.
+    pub fn keep_alive_timeout(mut self, timeout: Duration) -> Self {
+        self.keep_alive_timeout = Some(timeout);
+        self
+    }
+
+    /// Whether to ping while no call is in flight; on by default, since
+    /// idle connections are the ones proxies drop. Turn it off to let
+    /// idle connections close and be redialed on the next call.
+    pub fn keep_alive_while_idle(mut self, enabled: bool) -> Self {
+        self.keep_alive_while_idle = Some(enabled);
+        self
+    }
+
+    /// Whether to disable Nagle's algorithm; on by default, which favors
+    /// latency of small calls over packing them into fewer segments.
+    pub fn tcp_nodelay(mut self, enabled: bool) -> Self {
+        self.tcp_nodelay = Some(enabled);
+        self
+    }
+
+    /// Connects eagerly with the configured settings. Fails with
+    /// [`SdkError::Config`] when no endpoint was set or the token or a
+    /// metadata pair is not a valid header value.
+    pub async fn connect(self) -> Result<AgentPlatformClient> {
+        let addr = self
+            .endpoint
+            .ok_or_else(|| SdkError::Config("no endpoint set".into()))?;
+        let mut endpoint = Endpoint::from_shared(addr)?;
+        if let Some(timeout) = self.connect_timeout {
+            endpoint = endpoint.connect_timeout(timeout);
+        }
+        let interval = self
+            .keep_alive_interval
+            .unwrap_or(DEFAULT_KEEP_ALIVE_INTERVAL);
+        if !interval.is_zero() {
+            endpoint = endpoint
+                .http2_keep_alive_interval(interval)
+                .keep_alive_timeout(
+                    self.keep_alive_timeout
+                        .unwrap_or(DEFAULT_KEEP_ALIVE_TIMEOUT),
+                )
+                .keep_alive_while_idle(self.keep_alive_while_idle.unwrap_or(true));
+        }
+        endpoint = endpoint.tcp_nodelay(self.tcp_nodelay.unwrap_or(true));
+        let channel = endpoint.connect().await?;
+        let mut client = AgentPlatformClient::from_channel(channel, Some(endpoint));
+        if let Some(token) = &self.auth_token {
+            client = client.auth_token(token)?;
+        }
+        for (key, value) in &self.metadata {
+            client = client.with_metadata(key, value)?;
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.with_timeout(timeout);
+        }
+        if let Some(policy) = self.retry {
+            client = client.retry_policy(policy);
+        }
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proto::OrganizationProto;
+    use crate::test_server::{reply, TestServer};
+
+    #[test]
+    fn debug_redacts_the_auth_token() {
+        let builder = ClientBuilder::default().auth_token("s3cret-token");
+        let debug = format!("{builder:?}");
+        assert!(!debug.contains("s3cret-token"), "{debug}");
+        assert!(
+            debug.contains(r#"auth_token: Some("<redacted>")"#),
+            "{debug}"
+        );
+        assert!(format!("{:?}", ClientBuilder::default()).contains("auth_token: None"));
+    }
+
+    #[tokio::test]
+    async fn connection_options_are_accepted() {
+        let server = TestServer::start(|_| {
+            reply(&OrganizationProto {
+                org_id: "org-1".into(),
+                ..Default::default()
+            })
+        })
+        .await;
+        for builder in [
+            ClientBuilder::default()
+                .keep_alive_interval(Duration::from_secs(5))
+                .keep_alive_timeout(Duration::from_secs(1))
+                .keep_alive_while_idle(false)
+                .tcp_nodelay(false),
+            // Pings off, which skips the other keep-alive settings.
+            ClientBuilder::default()
+                .keep_alive_interval(Duration::ZERO)
+                .keep_alive_timeout(Duration::from_secs(1)),
+        ] {
+            let mut client = builder.endpoint(server.addr()).connect().await.unwrap();
+            assert_eq!(client.get_org("org-1").await.unwrap().org_id, "org-1");
+        }
+        assert_eq!(server.methods(), ["GetOrganization", "GetOrganization"]);
+    }
+}
@@ -0,0 +1,55 @@
+use crate::models::PolicyDecision;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Entries kept before expired ones are swept on insert.
+const MAX_ENTRIES: usize = 10_000;
+
+type Key = (String, String, String, i64);
+
+/// Client-side cache of policy decisions, keyed by org, agent, tool, and
+/// estimated tokens.
+#[derive(Clone)]
+pub(crate) struct PolicyCache {
+    default_ttl: Duration,
+    entries: HashMap<Key, (PolicyDecision, Instant)>,
+}
+
+impl PolicyCache {
+    pub(crate) fn new(default_ttl: Duration) -> Self {
+        Self {
+            default_ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &Key) -> Option<&PolicyDecision> {
+        self.entries
+            .get(key)
+            .filter(|(_, expires)| Instant::now() < *expires)
+            .map(|(d, _)| d)
+    }
+
+    /// Caches `decision` for its server-provided `cache_ttl`, falling back to
+    /// the default. A zero TTL means the decision must not be cached.
+    pub(crate) fn put(&mut self, key: Key, decision: &PolicyDecision) {
+        let ttl = decision.cache_ttl.unwrap_or(self.default_ttl);
+        if ttl.is_zero() {
+            self.entries.remove(&key);
+            return;
+        }
+        if self.entries.len() >= MAX_ENTRIES {
+            let now = Instant::now();
+            self.entries.retain(|_, (_, expires)| now < *expires);
+            if self.entries.len() >= MAX_ENTRIES {
+                self.entries.clear();
+            }
+        }
+        self.entries
+            .insert(key, (decision.clone(), Instant::now() + ttl));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -1,11 +1,46 @@
+use crate::builder::ClientBuilder;
+use crate::cache::PolicyCache;
+use crate::deadline;
 use crate::error::{Result, SdkError};
+use crate::failover::Failover;
+#[cfg(feature = "testing")]
+use crate::fault::FaultConfig;
+use crate::guard::Guard;
+use crate::health;
+use crate::idempotency;
+#[cfg(feature = "metrics")]
+use crate::metrics::{HistogramSnapshot, Metrics};
 use crate::models::*;
+use crate::policy::PolicyBuilder;
+use crate::pool::Pool;
+use crate::profile::ClientProfile;
 use crate::proto::control_plane_client::ControlPlaneClient;
 use crate::proto::*;
-use tonic::transport::Channel;
+use crate::reflection;
+use crate::retry::RetryPolicy;
+use crate::stream::{EventStream, StreamConfig};
+use crate::trace::{CallSpan, Scoped};
+use prost::Message;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::ops::Range;
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio_stream::{Stream, StreamExt};
+use tonic::metadata::{Ascii, MetadataKey, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
 
 /// Unified client for the Agent Platform control plane.
 ///
+/// Cloning is cheap and gives each task its own handle: clones share the
+/// underlying channel, so their calls are multiplexed over the same
+/// connection, and they share metrics. Settings, failover state, pool
+/// rotation, and the policy cache are copied, so changing them on one clone
+/// leaves the others alone.
+///
 /// # Example
 /// ```no_run
 /// use agent_platform_sdk::AgentPlatformClient;
@@ -17,283 +52,5745 @@ use tonic::transport::Channel;
 ///     let agent = client.register_agent(&org.org_id, "assistant", "executor", None).await.unwrap();
 /// }
 /// ```
+#[derive(Clone)]
 pub struct AgentPlatformClient {
     inner: ControlPlaneClient<Channel>,
+    channel: Channel,
+    /// Where `channel` is connected, redialed with the same settings after
+    /// the connection drops. `None` for clients from `from_inner`.
+    endpoint: Option<Endpoint>,
+    on_warning: Option<WarningHandler>,
+    read_only: bool,
+    instance_id: Option<MetadataValue<Ascii>>,
+    auth: Option<TokenProvider>,
+    headers: Vec<(MetadataKey<Ascii>, MetadataValue<Ascii>)>,
+    metadata_provider: Option<MetadataProvider>,
+    refresher: Option<CredentialRefresher>,
+    timeout: Option<Duration>,
+    retry: Option<RetryPolicy>,
+    failover: Option<Failover>,
+    pool: Option<Pool>,
+    policy_cache: Option<PolicyCache>,
+    #[cfg(feature = "metrics")]
+    metrics: Arc<Metrics>,
+    #[cfg(feature = "testing")]
+    faults: Option<FaultConfig>,
+}
+
+/// Tool name sent by [`AgentPlatformClient::probe_policy_latency`].
+const PROBE_TOOL: &str = "__latency_probe__";
+
+/// Page size used by `list_all_agents` and `list_all_orgs`.
+const LIST_ALL_PAGE_SIZE: i32 = 500;
+
+/// Metadata key carrying [`AgentPlatformClient::instance_id`].
+const INSTANCE_ID_HEADER: &str = "x-client-instance";
+
+/// Metadata key carrying a mutation's key; see [`crate::idempotency`].
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Per-call settings for [`AgentPlatformClient::call_with`].
+struct CallOptions {
+    timeout: Option<Duration>,
+    /// Whether the call can safely be sent again after a failure.
+    idempotent: bool,
+}
+
+/// RPCs that only read state, going by their name, and so are safe to retry.
+fn is_idempotent(method: &str) -> bool {
+    const READ_PREFIXES: &[&str] = &[
+        "Get",
+        "List",
+        "Find",
+        "Check",
+        "Validate",
+        "Sample",
+        "Evaluate",
+        "Recommend",
+        "Probe",
+        "Watch",
+        "Tail",
+        "Backtest",
+        "Export",
+        "Preview",
+    ];
+    READ_PREFIXES.iter().any(|p| method.starts_with(p))
 }
 
+/// Returns the bearer token to send with a call; see
+/// [`AgentPlatformClient::auth_token_provider`].
+pub type TokenProvider = Arc<dyn Fn() -> String + Send + Sync>;
+
+/// Fetches a replacement token; see
+/// [`AgentPlatformClient::credential_refresher`].
+pub type CredentialRefresher =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = String> + Send>> + Send + Sync>;
+
+/// Produces extra headers for each call; see
+/// [`AgentPlatformClient::metadata_provider`].
+pub type MetadataProvider = Arc<dyn Fn() -> Vec<(String, String)> + Send + Sync>;
+
+/// Callback invoked with each warning the control plane attaches to a response.
+pub type WarningHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
 impl AgentPlatformClient {
+    /// Connects to `addr` with default settings. Use
+    /// [`builder`](Self::builder) to configure the connection.
     pub async fn connect(addr: &str) -> Result<Self> {
-        let inner = ControlPlaneClient::connect(addr.to_string()).await?;
-        Ok(Self { inner })
+        Self::builder().endpoint(addr).connect().await
+    }
+
+    pub fn builder() -> ClientBuilder {
+        ClientBuilder::default()
+    }
+
+    pub(crate) fn from_channel(channel: Channel, endpoint: Option<Endpoint>) -> Self {
+        Self {
+            inner: ControlPlaneClient::new(channel.clone()),
+            channel,
+            endpoint,
+            on_warning: None,
+            read_only: false,
+            instance_id: None,
+            auth: None,
+            headers: Vec::new(),
+            metadata_provider: None,
+            refresher: None,
+            timeout: None,
+            retry: None,
+            failover: None,
+            pool: None,
+            policy_cache: None,
+            #[cfg(feature = "metrics")]
+            metrics: Arc::default(),
+            #[cfg(feature = "testing")]
+            faults: None,
+        }
+    }
+
+    /// Wraps a generated client built on `channel`, for callers who set the
+    /// channel up themselves. `channel` also carries health checks,
+    /// reflection, and pings. The endpoint is unknown, so a dropped
+    /// connection is not redialed.
+    ///
+    /// Like [`into_inner`](Self::into_inner) and [`channel`](Self::channel),
+    /// this is an escape hatch into the generated code, which has no
+    /// stability guarantee across SDK versions.
+    pub fn from_inner(inner: ControlPlaneClient<Channel>, channel: Channel) -> Self {
+        Self {
+            inner,
+            ..Self::from_channel(channel, None)
+        }
+    }
+
+    /// The generated client underneath, for RPCs this SDK doesn't wrap yet.
+    /// Headers, timeouts, retries, and failover configured on this client
+    /// don't apply to calls made through it.
+    pub fn into_inner(self) -> ControlPlaneClient<Channel> {
+        self.inner
+    }
+
+    /// The channel in use, for building generated clients with your own
+    /// interceptors (`ControlPlaneClient::with_interceptor`). With failover
+    /// this is the active endpoint's channel.
+    pub fn channel(&self) -> Channel {
+        match &self.failover {
+            Some(f) => f.channel(),
+            None => self.channel.clone(),
+        }
+    }
+
+    /// Connects with regional failover. Calls go to `primary` until it fails
+    /// repeatedly with `Unavailable` or `DeadlineExceeded`, then shift to the
+    /// secondaries in order. While failed over, a call is periodically routed
+    /// back to the primary and traffic returns once it succeeds.
+    ///
+    /// Channels connect lazily, so this succeeds even if the primary is down.
+    pub async fn connect_with_failover(primary: &str, secondaries: Vec<String>) -> Result<Self> {
+        let mut endpoints = Vec::with_capacity(secondaries.len() + 1);
+        for addr in std::iter::once(primary.to_string()).chain(secondaries) {
+            endpoints.push(Endpoint::from_shared(addr)?);
+        }
+        Ok(Self::from_failover(Failover::new(endpoints)))
+    }
+
+    fn from_failover(failover: Failover) -> Self {
+        let mut client = Self::from_channel(failover.channel(), Some(failover.endpoint()));
+        client.failover = Some(failover);
+        client
+    }
+
+    /// Connects using the named profile from a JSON profile file. See
+    /// [`ClientProfile`] for the format.
+    #[cfg(feature = "serde")]
+    pub async fn from_profile(path: impl AsRef<Path>, profile_name: &str) -> Result<Self> {
+        Self::connect_profile(&ClientProfile::load(path, profile_name)?).await
+    }
+
+    /// Connects with every setting in `profile`. Profiles with secondaries
+    /// connect with failover, lazily, like
+    /// [`connect_with_failover`](Self::connect_with_failover).
+    pub async fn connect_profile(profile: &ClientProfile) -> Result<Self> {
+        let mut endpoints = profile.endpoints()?;
+        let mut client = if endpoints.len() > 1 {
+            Self::from_failover(Failover::new(endpoints))
+        } else {
+            let endpoint = endpoints.remove(0);
+            Self::from_channel(endpoint.connect().await?, Some(endpoint))
+        };
+        client.read_only = profile.read_only;
+        client.timeout = profile.request_timeout_secs.map(Duration::from_secs);
+        if let Some(secs) = profile.policy_cache_ttl_secs {
+            client = client.policy_cache(Duration::from_secs(secs));
+        }
+        Ok(client)
+    }
+
+    /// Establishes the connection up front so the first RPC doesn't pay for
+    /// the TCP, TLS, and HTTP/2 handshakes.
+    ///
+    /// [`connect`](Self::connect) already connects eagerly, so this is a no-op
+    /// for such clients and for any connection that is already established.
+    /// With [`connect_with_failover`](Self::connect_with_failover) it connects
+    /// the currently active endpoint.
+    pub async fn warm_up(&mut self) -> Result<()> {
+        if let Some(failover) = &mut self.failover {
+            failover.warm_up().await?;
+        }
+        Ok(())
+    }
+
+    /// Spreads calls over `endpoints` in proportion to their weights, e.g.
+    /// `[("http://big:50051", 3), ("http://small:50051", 1)]` for replicas of
+    /// uneven capacity. Channels connect lazily. Cannot be combined with
+    /// [`connect_with_failover`](Self::connect_with_failover).
+    pub fn pool_weighted(mut self, endpoints: Vec<(String, u32)>) -> Result<Self> {
+        if self.failover.is_some() {
+            return Err(SdkError::Config(
+                "a weighted pool cannot be combined with failover".into(),
+            ));
+        }
+        if !endpoints.iter().any(|(_, weight)| *weight > 0) {
+            return Err(SdkError::Config(
+                "a weighted pool needs an endpoint with non-zero weight".into(),
+            ));
+        }
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(addr, weight)| Ok((Endpoint::from_shared(addr)?, weight)))
+            .collect::<Result<Vec<_>>>()?;
+        self.pool = Some(Pool::new(endpoints));
+        Ok(self)
+    }
+
+    /// Caches `evaluate_policy` decisions. Each decision is kept for the
+    /// `cache_ttl` the server attaches to it, or `default_ttl` when the server
+    /// gives none; a zero TTL from the server means the decision is never
+    /// cached. Policy changes made through this client clear the cache.
+    pub fn policy_cache(mut self, default_ttl: Duration) -> Self {
+        self.policy_cache = Some(PolicyCache::new(default_ttl));
+        self
+    }
+
+    /// Drops every cached policy decision.
+    pub fn clear_policy_cache(&mut self) {
+        if let Some(cache) = &mut self.policy_cache {
+            cache.clear();
+        }
+    }
+
+    /// Registers a callback for server warnings, such as use of a deprecated tool.
+    pub fn on_warning(mut self, handler: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_warning = Some(Arc::new(handler));
+        self
+    }
+
+    /// Rejects every mutating call with [`SdkError::ReadOnly`] before it is
+    /// sent, for services that must never change platform state.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Fails any call that takes longer than `timeout` with
+    /// [`SdkError::Timeout`]. Applies to every RPC the client makes; for
+    /// streaming calls it bounds opening the stream, not its lifetime.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Retries calls that fail with a transient error, as described by
+    /// `policy`. Without this, failures are returned immediately.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Sends `id` in the `x-client-instance` header of every call, so the
+    /// platform can tell instances of a deployment apart in its logs (e.g.
+    /// set it to the pod name). Fails if `id` is not a valid header value.
+    pub fn instance_id(mut self, id: &str) -> Result<Self> {
+        let value = id
+            .parse()
+            .map_err(|_| SdkError::Config(format!("invalid instance id {id:?}")))?;
+        self.instance_id = Some(value);
+        Ok(self)
+    }
+
+    /// Sends `authorization: Bearer <token>` with every call. Fails if
+    /// `token` is not a valid header value.
+    pub fn auth_token(self, token: &str) -> Result<Self> {
+        bearer(token)?;
+        let token = token.to_string();
+        Ok(self.auth_token_provider(move || token.clone()))
+    }
+
+    /// Like [`auth_token`](Self::auth_token), but asks `provider` for the
+    /// token before every call, so it can hand out refreshed tokens. A
+    /// token that is not a valid header value fails the call with
+    /// [`SdkError::Config`].
+    pub fn auth_token_provider(
+        mut self,
+        provider: impl Fn() -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.auth = Some(Arc::new(provider));
+        self
+    }
+
+    /// Sends the header `key: value` with every call, e.g. a tenant id. Keys
+    /// are lowercased; fails with [`SdkError::Config`] if either part is not
+    /// valid in gRPC metadata. `authorization` and the instance id header are
+    /// always set by the client and override these.
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Result<Self> {
+        self.headers.push(metadata_pair(key, value)?);
+        Ok(self)
+    }
+
+    /// Calls `provider` before every call and sends the pairs it returns as
+    /// headers, after any set with [`with_metadata`](Self::with_metadata) and
+    /// replacing them on the same key. Suited to per-call values such as an
+    /// `x-request-id` taken from the caller's current trace. An invalid pair
+    /// fails the call with [`SdkError::Config`].
+    pub fn metadata_provider(
+        mut self,
+        provider: impl Fn() -> Vec<(String, String)> + Send + Sync + 'static,
+    ) -> Self {
+        self.metadata_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// When a call fails with `Unauthenticated`, awaits `refresher` for a new
+    /// token, uses it for this and all later calls in place of the
+    /// [`auth_token`](Self::auth_token), and retries the call once. A second
+    /// rejection is returned as [`SdkError::Unauthenticated`].
+    pub fn credential_refresher<F, Fut>(mut self, refresher: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = String> + Send + 'static,
+    {
+        self.refresher = Some(Arc::new(move || Box::pin(refresher())));
+        self
+    }
+
+    /// Injects synthetic errors and latency into matching calls, for chaos
+    /// testing code built on this client. See [`FaultConfig`].
+    #[cfg(feature = "testing")]
+    pub fn fault_injection(mut self, config: FaultConfig) -> Self {
+        self.faults = Some(config);
+        self
+    }
+
+    /// Per-RPC latency histograms recorded since the client was created,
+    /// keyed by RPC name (e.g. `"EvaluatePolicy"`).
+    #[cfg(feature = "metrics")]
+    pub fn latency_snapshot(&self) -> HashMap<String, HistogramSnapshot> {
+        self.metrics.snapshot()
+    }
+
+    /// Latency histograms in the Prometheus text exposition format, for
+    /// serving from a `/metrics` endpoint.
+    #[cfg(feature = "metrics")]
+    pub fn render_metrics(&self) -> String {
+        self.metrics.render_prometheus()
+    }
+
+    fn ensure_writable(&self, method: &str) -> Result<()> {
+        if self.read_only {
+            return Err(SdkError::ReadOnly(method.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn call<Req, Resp, F, Fut>(
+        &mut self,
+        method: &'static str,
+        req: Req,
+        rpc: F,
+    ) -> Result<Resp>
+    where
+        Req: Clone + Scoped + prost::Message,
+        F: Fn(ControlPlaneClient<Channel>, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        let opts = CallOptions {
+            timeout: self.timeout,
+            idempotent: is_idempotent(method),
+        };
+        self.call_with(method, req, opts, rpc).await
+    }
+
+    /// [`call`](Self::call) with explicit options instead of the client
+    /// default deadline and the idempotency implied by `method`.
+    async fn call_with<Req, Resp, F, Fut>(
+        &mut self,
+        method: &'static str,
+        req: Req,
+        opts: CallOptions,
+        rpc: F,
+    ) -> Result<Resp>
+    where
+        Req: Clone + Scoped + prost::Message,
+        F: Fn(ControlPlaneClient<Channel>, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        CallSpan::new(method, req.org_id(), req.agent_id())
+            .run(self.call_retrying(method, req, opts, rpc))
+            .await
+    }
+
+    /// Retry and credential-refresh loop behind [`call_with`](Self::call_with).
+    async fn call_retrying<Req, Resp, F, Fut>(
+        &mut self,
+        method: &'static str,
+        req: Req,
+        opts: CallOptions,
+        rpc: F,
+    ) -> Result<Resp>
+    where
+        Req: Clone + prost::Message,
+        F: Fn(ControlPlaneClient<Channel>, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        let max_attempts = match &self.retry {
+            Some(p) if opts.idempotent || p.retry_mutations => p.max_attempts.max(1),
+            _ => 1,
+        };
+        // Chosen once, so every resend of a mutation carries the same key.
+        let idempotency_key = if opts.idempotent {
+            None
+        } else {
+            idempotency::scoped_key(method, &req)
+                .or_else(|| self.retry.is_some().then(idempotency::unique_id))
+        };
+        let mut attempt = 1;
+        let mut refreshed = false;
+        let mut delay = Duration::ZERO;
+        loop {
+            let result = self
+                .attempt(
+                    method,
+                    req.clone(),
+                    opts.timeout,
+                    idempotency_key.as_deref(),
+                    &rpc,
+                )
+                .await;
+            if let Err(SdkError::Status(status)) = &result {
+                if is_connection_failure(status) {
+                    self.reconnect();
+                }
+            }
+            // Classified before deciding, so the loop retries exactly what
+            // `SdkError::retriable` reports as retriable.
+            let result = result.map_err(classify);
+            match (&result, &self.retry) {
+                // A rejected credential means the server did no work, so even
+                // mutations can be resent once with a fresh token.
+                (Err(SdkError::Unauthenticated(_)), _)
+                    if !refreshed && self.refresher.is_some() =>
+                {
+                    refreshed = true;
+                    self.refresh_credential().await?;
+                }
+                (Err(e), Some(policy))
+                    if attempt < max_attempts
+                        && e.retriable()
+                        && !deadline::remaining().is_some_and(|left| left.is_zero()) =>
+                {
+                    delay = policy.delay(attempt, delay);
+                    // Never back off past the caller's deadline; the next
+                    // attempt then fails fast instead of being sent late.
+                    let wait = deadline::remaining().map_or(delay, |left| delay.min(left));
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// Replaces a dead single connection with a fresh one to the stored
+    /// endpoint. The new channel dials on first use, so the retry (or the
+    /// next call) connects once the server is back. Failover and pool
+    /// clients manage their own channels and are left alone, as are clients
+    /// from `from_inner`, which have no endpoint to dial.
+    fn reconnect(&mut self) {
+        if self.failover.is_some() || self.pool.is_some() {
+            return;
+        }
+        if let Some(endpoint) = &self.endpoint {
+            self.channel = endpoint.connect_lazy();
+            self.inner = ControlPlaneClient::new(self.channel.clone());
+        }
+    }
+
+    /// Wraps `req` with the client's headers and `timeout` as its deadline.
+    fn prepare<Req>(&self, req: Req, timeout: Option<Duration>) -> Result<tonic::Request<Req>> {
+        let mut request = tonic::Request::new(req);
+        for (key, value) in &self.headers {
+            request.metadata_mut().insert(key.clone(), value.clone());
+        }
+        if let Some(provider) = &self.metadata_provider {
+            for (key, value) in provider() {
+                let (key, value) = metadata_pair(&key, &value)?;
+                request.metadata_mut().insert(key, value);
+            }
+        }
+        if let Some(id) = &self.instance_id {
+            request
+                .metadata_mut()
+                .insert(INSTANCE_ID_HEADER, id.clone());
+        }
+        if let Some(provider) = &self.auth {
+            request
+                .metadata_mut()
+                .insert("authorization", bearer(&provider())?);
+        }
+        if let Some(limit) = timeout {
+            // Lets the server abandon work the client has stopped waiting for.
+            request.set_timeout(limit);
+        }
+        Ok(request)
+    }
+
+    /// Replaces the auth token with one from the credential refresher.
+    async fn refresh_credential(&mut self) -> Result<()> {
+        if let Some(refresher) = &self.refresher {
+            let token = refresher().await;
+            bearer(&token)?;
+            self.auth = Some(Arc::new(move || token.clone()));
+        }
+        Ok(())
+    }
+
+    /// Sends `req` once, recording the outcome for metrics and failover.
+    async fn attempt<Req, Resp, F, Fut>(
+        &mut self,
+        method: &'static str,
+        req: Req,
+        timeout: Option<Duration>,
+        idempotency_key: Option<&str>,
+        rpc: &F,
+    ) -> Result<Resp>
+    where
+        F: Fn(ControlPlaneClient<Channel>, tonic::Request<Req>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<Resp>, tonic::Status>>,
+    {
+        let timeout = deadline::clamp(method, timeout)?;
+        let client = match (&mut self.failover, &mut self.pool) {
+            (Some(f), _) => f.select(),
+            (None, Some(p)) => p.select(),
+            (None, None) => self.inner.clone(),
+        };
+        let mut request = self.prepare(req, timeout)?;
+        if let Some(key) = idempotency_key {
+            let value = key.parse().map_err(|_| {
+                SdkError::InvalidInput(format!("idempotency key {key:?} is not valid metadata"))
+            })?;
+            request.metadata_mut().insert(IDEMPOTENCY_KEY_HEADER, value);
+        }
+        #[cfg(feature = "metrics")]
+        let started = std::time::Instant::now();
+        #[cfg(feature = "testing")]
+        let injected = match &self.faults {
+            Some(faults) => faults.inject(method).await,
+            None => None,
+        };
+        #[cfg(not(feature = "testing"))]
+        let injected: Option<tonic::Status> = None;
+        let mut timed_out = false;
+        let result = match (injected, timeout) {
+            (Some(status), _) => Err(status),
+            (None, Some(limit)) => match tokio::time::timeout(limit, rpc(client, request)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    timed_out = true;
+                    Err(tonic::Status::deadline_exceeded(method))
+                }
+            },
+            (None, None) => rpc(client, request).await,
+        };
+        #[cfg(feature = "metrics")]
+        self.metrics.observe(method, started.elapsed());
+        if let Some(f) = &mut self.failover {
+            f.record(result.as_ref().err().map(|e| e.code()));
+        }
+        match result {
+            Ok(resp) => Ok(resp.into_inner()),
+            Err(_) if timed_out => Err(SdkError::Timeout(format!(
+                "{method} after {:?}",
+                timeout.unwrap_or_default()
+            ))),
+            Err(status) => Err(status.into()),
+        }
+    }
+
+    fn emit_warnings(&self, warnings: &[String]) {
+        if let Some(handler) = &self.on_warning {
+            for w in warnings {
+                handler(w);
+            }
+        }
     }
 
     // --- Organizations ---
 
+    /// Fails with [`SdkError::InvalidInput`] if `name` is empty.
     pub async fn create_org(&mut self, name: &str) -> Result<Org> {
+        self.create_org_with_metadata(name, &HashMap::new()).await
+    }
+
+    /// Creates an org with `metadata` attached, returned as [`Org::metadata`].
+    pub async fn create_org_with_metadata(
+        &mut self,
+        name: &str,
+        metadata: &HashMap<String, String>,
+    ) -> Result<Org> {
+        self.ensure_writable("create_org")?;
+        require("name", name)?;
         let resp = self
-            .inner
-            .create_organization(CreateOrgRequest {
-                name: name.to_string(),
-                metadata: None,
-            })
-            .await?
-            .into_inner();
-        Ok(Org {
-            org_id: resp.org_id,
-            name: resp.name,
+            .call(
+                "CreateOrganization",
+                CreateOrgRequest {
+                    name: name.to_string(),
+                    metadata: string_map_to_struct(metadata),
+                },
+                |mut c, r| async move { c.create_organization(r).await },
+            )
+            .await?;
+        Ok(org_from_proto(resp))
+    }
+
+    /// Creates several orgs in one call, e.g. a reseller's sub-tenants. Each
+    /// org succeeds or fails on its own; see [`BatchResult`].
+    pub async fn create_orgs(&mut self, specs: Vec<OrgSpec>) -> Result<BatchResult<Org>> {
+        self.ensure_writable("create_orgs")?;
+        let count = specs.len();
+        let resp = self
+            .call(
+                "CreateOrgs",
+                CreateOrgsRequest {
+                    orgs: specs
+                        .into_iter()
+                        .map(|spec| CreateOrgRequest {
+                            metadata: string_map_to_struct(&spec.metadata),
+                            name: spec.name,
+                        })
+                        .collect(),
+                },
+                |mut c, r| async move { c.create_orgs(r).await },
+            )
+            .await?;
+        if resp.results.len() != count {
+            return Err(SdkError::InvalidResponse(format!(
+                "expected {count} org results, got {}",
+                resp.results.len()
+            )));
+        }
+        Ok(BatchResult {
+            results: resp
+                .results
+                .into_iter()
+                .map(|r| {
+                    if !r.error.is_empty() {
+                        Err(r.error)
+                    } else {
+                        r.organization
+                            .map(org_from_proto)
+                            .ok_or_else(|| "missing organization in result".to_string())
+                    }
+                })
+                .collect(),
         })
     }
 
+    /// Creates an org together with its first admin agent in a single server
+    /// transaction, so a failed agent registration never leaves an empty org.
+    pub async fn create_org_with_admin(
+        &mut self,
+        name: &str,
+        admin_name: &str,
+        admin_role: impl Into<Role>,
+        claims: &HashMap<String, String>,
+    ) -> Result<(Org, RegisteredAgent)> {
+        self.ensure_writable("create_org_with_admin")?;
+        let admin_role = admin_role.into();
+        require("name", name)?;
+        require("admin_name", admin_name)?;
+        require("admin_role", admin_role.as_str())?;
+        let resp = self
+            .call(
+                "CreateOrgWithAdmin",
+                CreateOrgWithAdminRequest {
+                    name: name.to_string(),
+                    metadata: None,
+                    admin_name: admin_name.to_string(),
+                    admin_role: admin_role.into(),
+                    admin_token_claims: string_map_to_struct(claims),
+                },
+                |mut c, r| async move { c.create_org_with_admin(r).await },
+            )
+            .await?;
+        match (resp.organization, resp.admin) {
+            (Some(org), Some(admin)) => Ok((org_from_proto(org), registered_from_proto(admin))),
+            _ => Err(SdkError::InvalidResponse(
+                "missing organization or admin agent".to_string(),
+            )),
+        }
+    }
+
     pub async fn get_org(&mut self, org_id: &str) -> Result<Org> {
+        self.get_org_with_fields(org_id, &[]).await
+    }
+
+    /// [`get_org`](Self::get_org) returning only `fields`, named as in the
+    /// model (e.g. `"name"`); the rest are left at their defaults. An empty
+    /// slice returns every field.
+    pub async fn get_org_with_fields(&mut self, org_id: &str, fields: &[&str]) -> Result<Org> {
         let resp = self
-            .inner
-            .get_organization(GetOrgRequest {
-                org_id: org_id.to_string(),
-            })
-            .await?
-            .into_inner();
-        Ok(Org {
-            org_id: resp.org_id,
-            name: resp.name,
-        })
+            .call(
+                "GetOrganization",
+                GetOrgRequest {
+                    org_id: org_id.to_string(),
+                    read_mask: field_mask(fields),
+                },
+                |mut c, r| async move { c.get_organization(r).await },
+            )
+            .await?;
+        Ok(org_from_proto(resp))
     }
 
     pub async fn list_orgs(&mut self) -> Result<Vec<Org>> {
+        self.list_orgs_inner(ListOrgsRequest::default()).await
+    }
+
+    /// [`list_orgs`](Self::list_orgs) returning only `fields` of each org;
+    /// see [`get_org_with_fields`](Self::get_org_with_fields).
+    pub async fn list_orgs_with_fields(&mut self, fields: &[&str]) -> Result<Vec<Org>> {
+        self.list_orgs_inner(ListOrgsRequest {
+            read_mask: field_mask(fields),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// [`list_orgs`](Self::list_orgs) plus tombstones for recently deleted
+    /// orgs, marked with [`Org::deleted`], so a polling cache can tell a
+    /// deletion from a transient omission.
+    pub async fn list_orgs_with_tombstones(&mut self) -> Result<Vec<Org>> {
+        self.list_orgs_inner(ListOrgsRequest {
+            include_tombstones: true,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Orgs whose metadata has `key` set to `value`, filtered server-side.
+    pub async fn list_orgs_by_metadata(&mut self, key: &str, value: &str) -> Result<Vec<Org>> {
+        self.list_orgs_inner(ListOrgsRequest {
+            metadata_key: key.to_string(),
+            metadata_value: value.to_string(),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// One page of at most `page_size` orgs, starting at `page_token` (or
+    /// the first page when `None`).
+    pub async fn list_orgs_paged(
+        &mut self,
+        page_size: i32,
+        page_token: Option<&str>,
+    ) -> Result<Page<Org>> {
+        self.list_orgs_page(ListOrgsRequest {
+            page_size,
+            page_token: page_token.unwrap_or("").to_string(),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Every org, fetched page by page with
+    /// [`list_orgs_paged`](Self::list_orgs_paged). Fails with
+    /// [`SdkError::InvalidResponse`] if the server hands back a page token
+    /// it already gave, rather than paging forever.
+    pub async fn list_all_orgs(&mut self) -> Result<Vec<Org>> {
+        let mut orgs = Vec::new();
+        let mut token = None;
+        let mut seen = HashSet::new();
+        loop {
+            let page = self
+                .list_orgs_paged(LIST_ALL_PAGE_SIZE, token.as_deref())
+                .await?;
+            orgs.extend(page.items);
+            match page.next_page_token {
+                Some(next) if !seen.insert(next.clone()) => {
+                    return Err(repeated_page_token("ListOrganizations", &next))
+                }
+                Some(next) => token = Some(next),
+                None => return Ok(orgs),
+            }
+        }
+    }
+
+    async fn list_orgs_inner(&mut self, request: ListOrgsRequest) -> Result<Vec<Org>> {
+        Ok(self.list_orgs_page(request).await?.items)
+    }
+
+    async fn list_orgs_page(&mut self, request: ListOrgsRequest) -> Result<Page<Org>> {
         let resp = self
-            .inner
-            .list_organizations(ListOrgsRequest {})
-            .await?
-            .into_inner();
-        Ok(resp
-            .organizations
-            .into_iter()
-            .map(|o| Org {
-                org_id: o.org_id,
-                name: o.name,
+            .call("ListOrganizations", request, |mut c, r| async move {
+                c.list_organizations(r).await
             })
-            .collect())
+            .await?;
+        Ok(Page {
+            items: resp.organizations.into_iter().map(org_from_proto).collect(),
+            next_page_token: opt(resp.next_page_token),
+        })
+    }
+
+    /// Renames an org and returns it. Fails with [`SdkError::NotFound`] if
+    /// the org doesn't exist.
+    pub async fn update_org(&mut self, org_id: &str, name: &str) -> Result<Org> {
+        self.update_org_inner(org_id, name, None).await
     }
 
+    /// Renames an org and replaces all of its metadata in one call.
+    pub async fn update_org_with_metadata(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        metadata: &HashMap<String, String>,
+    ) -> Result<Org> {
+        self.update_org_inner(org_id, name, Some(metadata)).await
+    }
+
+    async fn update_org_inner(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        metadata: Option<&HashMap<String, String>>,
+    ) -> Result<Org> {
+        self.ensure_writable("update_org")?;
+        let mut fields = vec!["name"];
+        if metadata.is_some() {
+            fields.push("metadata");
+        }
+        let resp = self
+            .call(
+                "UpdateOrganization",
+                UpdateOrgRequest {
+                    org_id: org_id.to_string(),
+                    name: name.to_string(),
+                    metadata: metadata.and_then(string_map_to_struct),
+                    update_mask: field_mask(&fields),
+                },
+                |mut c, r| async move { c.update_organization(r).await },
+            )
+            .await?;
+        Ok(org_from_proto(resp))
+    }
+
+    /// Deletes an org that has no active agents; see
+    /// [`delete_org_with_options`](Self::delete_org_with_options).
     pub async fn delete_org(&mut self, org_id: &str) -> Result<bool> {
+        self.delete_org_with_options(org_id, false).await
+    }
+
+    /// With `cascade`, deletes the org along with all its agents, policies,
+    /// and budgets. Without it, fails with [`SdkError::OrgNotEmpty`] while
+    /// the org still has active agents; deactivate or delete them first.
+    /// [`delete_org_dry_run`](Self::delete_org_dry_run) shows what a cascade
+    /// would remove.
+    ///
+    /// The active-agent check is made here, with
+    /// [`list_all_agents`](Self::list_all_agents), so it holds against
+    /// servers that ignore `cascade`. An agent registered between the check
+    /// and the delete is left to the server.
+    pub async fn delete_org_with_options(&mut self, org_id: &str, cascade: bool) -> Result<bool> {
+        self.ensure_writable("delete_org")?;
+        if !cascade {
+            let active = self
+                .list_all_agents(org_id)
+                .await?
+                .iter()
+                .filter(|a| a.active)
+                .count();
+            if active > 0 {
+                return Err(SdkError::OrgNotEmpty(format!(
+                    "{org_id}: {active} active agents"
+                )));
+            }
+        }
         let resp = self
-            .inner
-            .delete_organization(DeleteOrgRequest {
-                org_id: org_id.to_string(),
-            })
-            .await?
-            .into_inner();
+            .call(
+                "DeleteOrganization",
+                DeleteOrgRequest {
+                    org_id: org_id.to_string(),
+                    cascade,
+                },
+                |mut c, r| async move { c.delete_organization(r).await },
+            )
+            .await?;
         Ok(resp.success)
     }
 
+    /// Reports what a cascading [`delete_org`](Self::delete_org_with_options)
+    /// would remove, without deleting anything. Uses the read-only
+    /// `PreviewDeleteOrg` RPC, never `DeleteOrganization`, so it is allowed on
+    /// read-only clients and a server without preview support answers
+    /// `Unimplemented` rather than deleting.
+    pub async fn delete_org_dry_run(&mut self, org_id: &str) -> Result<DeleteImpact> {
+        let impact = self
+            .call(
+                "PreviewDeleteOrg",
+                PreviewDeleteOrgRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.preview_delete_org(r).await },
+            )
+            .await?;
+        Ok(DeleteImpact {
+            agent_count: impact.agent_count,
+            policy_count: impact.policy_count,
+            budget_count: impact.budget_count,
+            usage_record_count: impact.usage_record_count,
+        })
+    }
+
     // --- Agents ---
 
+    /// Registers an agent. The returned
+    /// [`initial_credential`](RegisteredAgent::initial_credential) is the only
+    /// chance to read the agent's bootstrap token. Fails with
+    /// [`SdkError::InvalidInput`] if `org_id`, `name`, or `role` is empty.
     pub async fn register_agent(
         &mut self,
         org_id: &str,
         name: &str,
-        role: &str,
+        role: impl Into<Role>,
         delegated_user_id: Option<&str>,
-    ) -> Result<Agent> {
-        let resp = self
-            .inner
-            .register_agent(RegisterAgentRequest {
-                org_id: org_id.to_string(),
-                name: name.to_string(),
-                role: role.to_string(),
-                delegated_user_id: delegated_user_id.unwrap_or("").to_string(),
-                token_claims: None,
-            })
-            .await?
-            .into_inner();
-        Ok(Agent {
-            agent_id: resp.agent_id,
-            org_id: resp.org_id,
-            name: resp.name,
-            role: resp.role,
-            active: resp.active,
-            delegated_user_id: if resp.delegated_user_id.is_empty() {
-                None
-            } else {
-                Some(resp.delegated_user_id)
-            },
-        })
+    ) -> Result<RegisteredAgent> {
+        let role = role.into();
+        self.ensure_writable("register_agent")?;
+        self.register_agent_inner(org_id, name, role, delegated_user_id, HashMap::new())
+            .await
     }
 
-    pub async fn list_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
-        let resp = self
-            .inner
-            .list_agents(ListAgentsRequest {
-                org_id: org_id.to_string(),
-            })
-            .await?
-            .into_inner();
-        Ok(resp
-            .agents
-            .into_iter()
-            .map(|a| Agent {
-                agent_id: a.agent_id,
-                org_id: a.org_id,
-                name: a.name,
-                role: a.role,
-                active: a.active,
-                delegated_user_id: None,
-            })
-            .collect())
+    /// [`register_agent`](Self::register_agent) with token claims (scopes,
+    /// audience, custom attributes) for delegated identity. The control plane
+    /// stores claims verbatim and returns them as [`Agent::token_claims`]; it
+    /// does not interpret any keys itself, so their meaning is up to the
+    /// services that consume the agent's identity.
+    pub async fn register_agent_with_claims(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        role: impl Into<Role>,
+        delegated_user_id: Option<&str>,
+        claims: HashMap<String, String>,
+    ) -> Result<RegisteredAgent> {
+        let role = role.into();
+        self.ensure_writable("register_agent_with_claims")?;
+        self.register_agent_inner(org_id, name, role, delegated_user_id, claims)
+            .await
     }
 
-    pub async fn deactivate_agent(&mut self, org_id: &str, agent_id: &str) -> Result<bool> {
+    async fn register_agent_inner(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        role: Role,
+        delegated_user_id: Option<&str>,
+        claims: HashMap<String, String>,
+    ) -> Result<RegisteredAgent> {
+        require("org_id", org_id)?;
+        require("name", name)?;
+        require("role", role.as_str())?;
         let resp = self
-            .inner
-            .deactivate_agent(DeactivateAgentRequest {
-                org_id: org_id.to_string(),
-                agent_id: agent_id.to_string(),
-            })
-            .await?
-            .into_inner();
-        Ok(resp.success)
+            .call(
+                "RegisterAgent",
+                RegisterAgentRequest {
+                    org_id: org_id.to_string(),
+                    name: name.to_string(),
+                    role: role.into(),
+                    delegated_user_id: delegated_user_id.unwrap_or("").to_string(),
+                    token_claims: string_map_to_struct(&claims),
+                },
+                |mut c, r| async move { c.register_agent(r).await },
+            )
+            .await?;
+        Ok(registered_from_proto(resp))
     }
 
-    // --- Policy ---
-
-    pub async fn set_policy(
+    /// Registers several agents in one call, returning them with their ids
+    /// and bootstrap credentials in input order.
+    ///
+    /// The batch is all-or-nothing: if any spec is rejected, no agent is
+    /// registered and the error's message names the index of the first
+    /// failure.
+    pub async fn register_agents(
         &mut self,
         org_id: &str,
-        agent_id: Option<&str>,
-        allowed_tools: &[&str],
-        denied_tools: &[&str],
-        token_limit: i64,
-        timeout_seconds: i32,
-    ) -> Result<String> {
-        let mut tools = Vec::new();
-        for t in allowed_tools {
-            tools.push(ToolPermissionProto {
-                tool_name: t.to_string(),
-                effect: "allow".to_string(),
-                parameters_constraint: None,
-            });
+        specs: &[AgentSpec],
+    ) -> Result<Vec<RegisteredAgent>> {
+        self.ensure_writable("register_agents")?;
+        require("org_id", org_id)?;
+        for (i, spec) in specs.iter().enumerate() {
+            let check =
+                require("name", &spec.name).and_then(|_| require("role", spec.role.as_str()));
+            indexed("agents", i, check)?;
         }
-        for t in denied_tools {
-            tools.push(ToolPermissionProto {
-                tool_name: t.to_string(),
-                effect: "deny".to_string(),
-                parameters_constraint: None,
-            });
+        let resp = self
+            .call(
+                "RegisterAgents",
+                RegisterAgentsRequest {
+                    org_id: org_id.to_string(),
+                    agents: specs
+                        .iter()
+                        .map(|spec| RegisterAgentRequest {
+                            org_id: String::new(),
+                            name: spec.name.clone(),
+                            role: spec.role.to_string(),
+                            delegated_user_id: spec.delegated_user_id.clone().unwrap_or_default(),
+                            token_claims: string_map_to_struct(&spec.claims),
+                        })
+                        .collect(),
+                },
+                |mut c, r| async move { c.register_agents(r).await },
+            )
+            .await?;
+        if resp.agents.len() != specs.len() {
+            return Err(SdkError::InvalidResponse(format!(
+                "registered {} agents for {} specs",
+                resp.agents.len(),
+                specs.len()
+            )));
+        }
+        Ok(resp.agents.into_iter().map(registered_from_proto).collect())
+    }
+
+    /// Fetches one agent by id. Fails with [`SdkError::NotFound`] if the org
+    /// has no such agent.
+    pub async fn get_agent(&mut self, org_id: &str, agent_id: &str) -> Result<Agent> {
+        self.get_agent_with_fields(org_id, agent_id, &[]).await
+    }
+
+    /// The human an agent ultimately acts for, or `None` if it isn't
+    /// delegated to one. An agent may be delegated to another agent in the
+    /// same org, marked by [`Agent::delegate_is_agent`]; the chain is
+    /// followed until it reaches a user. A delegate agent that no longer
+    /// exists fails with [`SdkError::NotFound`], and a chain that loops back
+    /// on itself with [`SdkError::DelegationCycle`].
+    pub async fn effective_user(&mut self, org_id: &str, agent_id: &str) -> Result<Option<String>> {
+        let mut chain = vec![agent_id.to_string()];
+        let mut seen = HashSet::from([agent_id.to_string()]);
+        let mut current = self.get_agent(org_id, agent_id).await?;
+        loop {
+            let Some(delegate) = current.delegated_user_id else {
+                return Ok(None);
+            };
+            if !current.delegate_is_agent {
+                return Ok(Some(delegate));
+            }
+            chain.push(delegate.clone());
+            if !seen.insert(delegate.clone()) {
+                return Err(SdkError::DelegationCycle(chain));
+            }
+            current = self.get_agent(org_id, &delegate).await?;
         }
+    }
+
+    /// The public-facing [`AgentCard`] for an agent, safe to show outside
+    /// the org.
+    pub async fn get_agent_card(&mut self, org_id: &str, agent_id: &str) -> Result<AgentCard> {
         let resp = self
-            .inner
-            .set_policy(SetPolicyRequest {
-                org_id: org_id.to_string(),
-                agent_id: agent_id.unwrap_or("").to_string(),
-                tools,
-                token_limit,
-                execution_timeout_seconds: timeout_seconds,
-            })
-            .await?
-            .into_inner();
-        Ok(resp.policy_id)
+            .call(
+                "GetAgentCard",
+                GetAgentCardRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                },
+                |mut c, r| async move { c.get_agent_card(r).await },
+            )
+            .await?;
+        Ok(AgentCard {
+            agent_id: resp.agent_id,
+            name: resp.name,
+            role: resp.role.into(),
+            capabilities: resp.capabilities,
+        })
     }
 
-    pub async fn evaluate_policy(
+    /// [`get_agent`](Self::get_agent) returning only `fields`, named as in
+    /// the model (e.g. `"agent_id"`, `"name"`); the rest are left at their
+    /// defaults. An empty slice returns every field.
+    pub async fn get_agent_with_fields(
         &mut self,
         org_id: &str,
         agent_id: &str,
-        tool_name: &str,
-        estimated_tokens: i64,
-    ) -> Result<PolicyDecision> {
+        fields: &[&str],
+    ) -> Result<Agent> {
         let resp = self
-            .inner
-            .evaluate_policy(EvaluatePolicyRequest {
-                org_id: org_id.to_string(),
-                agent_id: agent_id.to_string(),
-                tool_name: tool_name.to_string(),
-                estimated_tokens,
-                context: None,
-            })
-            .await?
-            .into_inner();
-        Ok(PolicyDecision {
-            allowed: resp.allowed,
-            reason: resp.reason,
-            policy_id: if resp.matched_policy_id.is_empty() {
-                None
-            } else {
-                Some(resp.matched_policy_id)
-            },
-        })
+            .call(
+                "GetAgent",
+                GetAgentRequest {
+                    agent_id: agent_id.to_string(),
+                    org_id: org_id.to_string(),
+                    read_mask: field_mask(fields),
+                },
+                |mut c, r| async move { c.get_agent(r).await },
+            )
+            .await?;
+        Ok(agent_from_proto(resp))
     }
 
-    // --- Budget ---
+    pub async fn list_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        self.list_agents_with_fields(org_id, &[]).await
+    }
 
-    pub async fn set_budget(
+    /// [`list_agents`](Self::list_agents) into `buf`, replacing its contents
+    /// and keeping its allocation, for hot refresh loops. Strings are moved
+    /// out of the decoded response, not copied.
+    pub async fn list_agents_into(&mut self, org_id: &str, buf: &mut Vec<Agent>) -> Result<()> {
+        let resp = self
+            .call(
+                "ListAgents",
+                ListAgentsRequest {
+                    org_id: org_id.to_string(),
+                    ..Default::default()
+                },
+                |mut c, r| async move { c.list_agents(r).await },
+            )
+            .await?;
+        buf.clear();
+        buf.extend(resp.agents.into_iter().map(agent_from_proto));
+        Ok(())
+    }
+
+    /// [`list_agents`](Self::list_agents) returning only `fields` of each
+    /// agent; see [`get_agent_with_fields`](Self::get_agent_with_fields).
+    pub async fn list_agents_with_fields(
         &mut self,
         org_id: &str,
-        agent_id: Option<&str>,
-        token_limit: i64,
-        reset_period_days: i32,
-    ) -> Result<BudgetInfo> {
+        fields: &[&str],
+    ) -> Result<Vec<Agent>> {
+        self.list_agents_inner(ListAgentsRequest {
+            org_id: org_id.to_string(),
+            read_mask: field_mask(fields),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// [`list_agents`](Self::list_agents) plus tombstones for recently
+    /// deleted agents, marked with [`Agent::deleted`], so a polling cache can
+    /// tell a deletion from a transient omission.
+    pub async fn list_agents_with_tombstones(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        self.list_agents_inner(ListAgentsRequest {
+            org_id: org_id.to_string(),
+            include_tombstones: true,
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// One page of at most `page_size` agents in an org, starting at
+    /// `page_token` (or the first page when `None`).
+    pub async fn list_agents_paged(
+        &mut self,
+        org_id: &str,
+        page_size: i32,
+        page_token: Option<&str>,
+    ) -> Result<Page<Agent>> {
+        self.list_agents_page(ListAgentsRequest {
+            org_id: org_id.to_string(),
+            page_size,
+            page_token: page_token.unwrap_or("").to_string(),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// Every agent in an org, fetched page by page with
+    /// [`list_agents_paged`](Self::list_agents_paged). Like
+    /// [`list_all_orgs`](Self::list_all_orgs), fails if a page token
+    /// repeats.
+    pub async fn list_all_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        let mut agents = Vec::new();
+        let mut token = None;
+        let mut seen = HashSet::new();
+        loop {
+            let page = self
+                .list_agents_paged(org_id, LIST_ALL_PAGE_SIZE, token.as_deref())
+                .await?;
+            agents.extend(page.items);
+            match page.next_page_token {
+                Some(next) if !seen.insert(next.clone()) => {
+                    return Err(repeated_page_token("ListAgents", &next))
+                }
+                Some(next) => token = Some(next),
+                None => return Ok(agents),
+            }
+        }
+    }
+
+    async fn list_agents_inner(&mut self, request: ListAgentsRequest) -> Result<Vec<Agent>> {
+        Ok(self.list_agents_page(request).await?.items)
+    }
+
+    async fn list_agents_page(&mut self, request: ListAgentsRequest) -> Result<Page<Agent>> {
         let resp = self
-            .inner
-            .set_budget(SetBudgetRequest {
-                org_id: org_id.to_string(),
-                agent_id: agent_id.unwrap_or("").to_string(),
-                token_limit,
-                reset_period_days,
+            .call("ListAgents", request, |mut c, r| async move {
+                c.list_agents(r).await
             })
-            .await?
-            .into_inner();
-        Ok(BudgetInfo {
-            budget_id: resp.budget_id,
-            token_limit: resp.token_limit,
-            tokens_used: resp.tokens_used,
-            tokens_remaining: resp.tokens_remaining,
-            tool_invocations: resp.tool_invocations,
+            .await?;
+        Ok(Page {
+            items: resp.agents.into_iter().map(agent_from_proto).collect(),
+            next_page_token: opt(resp.next_page_token),
         })
     }
 
-    pub async fn check_budget(
+    /// Agents whose effective policy allows `tool_name`, resolved by the
+    /// server, e.g. to assess the blast radius of an incident with a tool.
+    pub async fn list_agents_with_tool(
+        &mut self,
+        org_id: &str,
+        tool_name: &str,
+    ) -> Result<Vec<Agent>> {
+        let resp = self
+            .call(
+                "ListAgentsWithTool",
+                ListAgentsWithToolRequest {
+                    org_id: org_id.to_string(),
+                    tool_name: tool_name.to_string(),
+                },
+                |mut c, r| async move { c.list_agents_with_tool(r).await },
+            )
+            .await?;
+        Ok(resp.agents.into_iter().map(agent_from_proto).collect())
+    }
+
+    /// Compares a locally cached set of agents against the org's current
+    /// agents, so a cache can apply only the deltas.
+    pub async fn reconcile_agents(
         &mut self,
         org_id: &str,
+        local: Vec<Agent>,
+    ) -> Result<ReconcileReport> {
+        let mut local: HashMap<String, Agent> =
+            local.into_iter().map(|a| (a.agent_id.clone(), a)).collect();
+        let mut report = ReconcileReport::default();
+        for agent in self.list_agents(org_id).await? {
+            match local.remove(&agent.agent_id) {
+                None => report.new.push(agent),
+                Some(cached) if cached != agent => report.changed.push(agent),
+                Some(_) => {}
+            }
+        }
+        report.deleted = local.into_keys().collect();
+        report.deleted.sort();
+        Ok(report)
+    }
+
+    /// Checks whether `token` is still a valid credential for `agent_id`, so
+    /// a long-running agent can refresh it before calls start failing with
+    /// [`SdkError::Unauthenticated`].
+    pub async fn validate_credential(
+        &mut self,
         agent_id: &str,
-        estimated_tokens: i64,
-    ) -> Result<BudgetCheck> {
+        token: &str,
+    ) -> Result<CredentialStatus> {
         let resp = self
-            .inner
-            .check_budget(CheckBudgetRequest {
-                org_id: org_id.to_string(),
-                agent_id: agent_id.to_string(),
-                estimated_tokens,
-            })
-            .await?
-            .into_inner();
-        Ok(BudgetCheck {
-            allowed: resp.allowed,
-            tokens_remaining: resp.tokens_remaining,
-            reason: resp.reason,
+            .call(
+                "ValidateCredential",
+                ValidateCredentialRequest {
+                    agent_id: agent_id.to_string(),
+                    token: token.to_string(),
+                },
+                |mut c, r| async move { c.validate_credential(r).await },
+            )
+            .await?;
+        Ok(CredentialStatus {
+            valid: resp.valid,
+            expires_at: time(resp.expires_at),
+            reason: opt(resp.reason),
         })
     }
 
-    pub async fn report_usage(
+    /// Deactivates an agent. `reason` is stored on the agent and returned as
+    /// [`Agent::deactivation_reason`].
+    pub async fn deactivate_agent(
         &mut self,
         org_id: &str,
         agent_id: &str,
-        execution_id: &str,
-        tokens_used: i64,
-        tool_invocations: i32,
-        duration_ms: i64,
-    ) -> Result<i64> {
+        reason: Option<&str>,
+    ) -> Result<bool> {
+        self.ensure_writable("deactivate_agent")?;
+        let resp = self
+            .call(
+                "DeactivateAgent",
+                DeactivateAgentRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    reason: reason.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.deactivate_agent(r).await },
+            )
+            .await?;
+        Ok(resp.success)
+    }
+
+    /// Changes an agent's name, role, or delegation in place and returns the
+    /// updated agent. `None` leaves a field unchanged; for delegation,
+    /// `Some(None)` clears it and `Some(Some(user))` sets it.
+    pub async fn update_agent(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        name: Option<&str>,
+        role: Option<Role>,
+        delegated_user_id: Option<Option<&str>>,
+    ) -> Result<Agent> {
+        self.ensure_writable("update_agent")?;
+        let mut fields = Vec::new();
+        if name.is_some() {
+            fields.push("name");
+        }
+        if role.is_some() {
+            fields.push("role");
+        }
+        if delegated_user_id.is_some() {
+            fields.push("delegated_user_id");
+        }
+        let resp = self
+            .call(
+                "UpdateAgent",
+                UpdateAgentRequest {
+                    agent_id: agent_id.to_string(),
+                    org_id: org_id.to_string(),
+                    name: name.unwrap_or("").to_string(),
+                    role: role.map(String::from).unwrap_or_default(),
+                    delegated_user_id: delegated_user_id.flatten().unwrap_or("").to_string(),
+                    // Always sent, so an update that changes nothing is not
+                    // mistaken for a full replace.
+                    update_mask: Some(prost_types::FieldMask {
+                        paths: fields.into_iter().map(String::from).collect(),
+                    }),
+                },
+                |mut c, r| async move { c.update_agent(r).await },
+            )
+            .await?;
+        Ok(agent_from_proto(resp))
+    }
+
+    /// Brings a deactivated agent back under its existing id, clearing its
+    /// deactivation reason. Reactivating an active agent also returns
+    /// `true`. Fails with [`SdkError::NotFound`] for an unknown agent.
+    pub async fn reactivate_agent(&mut self, org_id: &str, agent_id: &str) -> Result<bool> {
+        self.ensure_writable("reactivate_agent")?;
+        let resp = self
+            .call(
+                "ReactivateAgent",
+                ReactivateAgentRequest {
+                    agent_id: agent_id.to_string(),
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.reactivate_agent(r).await },
+            )
+            .await?;
+        Ok(resp.success)
+    }
+
+    /// Deactivates every active agent acting on behalf of `user_id` in one
+    /// server-side operation and returns their ids.
+    pub async fn offboard_user(&mut self, org_id: &str, user_id: &str) -> Result<Vec<String>> {
+        self.ensure_writable("offboard_user")?;
+        let resp = self
+            .call(
+                "OffboardUser",
+                OffboardUserRequest {
+                    org_id: org_id.to_string(),
+                    user_id: user_id.to_string(),
+                },
+                |mut c, r| async move { c.offboard_user(r).await },
+            )
+            .await?;
+        Ok(resp.agent_ids)
+    }
+
+    /// Fetches an agent together with its effective policy, budget, and rate
+    /// limit in a single round trip.
+    pub async fn get_agent_config(&mut self, org_id: &str, agent_id: &str) -> Result<AgentConfig> {
+        let resp = self
+            .call(
+                "GetAgentConfig",
+                GetAgentConfigRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                },
+                |mut c, r| async move { c.get_agent_config(r).await },
+            )
+            .await?;
+        let agent = resp
+            .agent
+            .ok_or_else(|| SdkError::InvalidResponse("missing agent in config".into()))?;
+        Ok(AgentConfig {
+            agent: agent_from_proto(agent),
+            effective_policy: resp.effective_policy.map(effective_policy_from_proto),
+            budget: resp.budget.map(budget_from_proto),
+            rate_limit: resp.rate_limit.map(|r| RateLimit {
+                max_per_minute: r.max_per_minute,
+            }),
+        })
+    }
+
+    /// Effective policy, budget, and rate limit for many agents in one call,
+    /// in the order requested. Agents the server doesn't know are left out.
+    pub async fn get_scheduling_info(
+        &mut self,
+        org_id: &str,
+        agent_ids: Vec<String>,
+    ) -> Result<Vec<SchedulingInfo>> {
+        let resp = self
+            .call(
+                "GetSchedulingInfo",
+                GetSchedulingInfoRequest {
+                    org_id: org_id.to_string(),
+                    agent_ids,
+                },
+                |mut c, r| async move { c.get_scheduling_info(r).await },
+            )
+            .await?;
+        Ok(resp
+            .infos
+            .into_iter()
+            .map(|i| SchedulingInfo {
+                agent_id: i.agent_id,
+                effective_policy: i.effective_policy.map(effective_policy_from_proto),
+                budget: i.budget.map(budget_from_proto),
+                rate_limit: i.rate_limit.map(|r| RateLimit {
+                    max_per_minute: r.max_per_minute,
+                }),
+            })
+            .collect())
+    }
+
+    // --- Policy ---
+
+    /// Prefer [`set_policy_from`](Self::set_policy_from) with a
+    /// [`PolicyBuilder`], which names each setting and supports parameter
+    /// constraints.
+    ///
+    /// A `token_limit` or `timeout_seconds` of 0 selects the server default
+    /// (100,000 tokens, 300 seconds). Negative values, or an empty `org_id`,
+    /// fail with [`SdkError::InvalidInput`].
+    pub async fn set_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        allowed_tools: &[&str],
+        denied_tools: &[&str],
+        token_limit: i64,
+        timeout_seconds: i32,
+    ) -> Result<String> {
+        let mut tools = Vec::new();
+        for t in allowed_tools {
+            tools.push(ToolPermission {
+                tool_name: t.to_string(),
+                effect: PolicyEffect::Allow,
+                parameters_constraint: None,
+            });
+        }
+        for t in denied_tools {
+            tools.push(ToolPermission {
+                tool_name: t.to_string(),
+                effect: PolicyEffect::Deny,
+                parameters_constraint: None,
+            });
+        }
+        self.set_policy_detailed(
+            org_id,
+            agent_id,
+            &tools,
+            token_limit,
+            timeout_seconds,
+            PolicyMode::Enforce,
+        )
+        .await
+    }
+
+    /// Like [`set_policy`](Self::set_policy), with explicit tool permissions
+    /// and a [`PolicyMode`]. Use `PolicyMode::Shadow` to trial a policy: its
+    /// would-be denies are reported via `PolicyDecision::shadow_would_deny`
+    /// but not enforced.
+    pub async fn set_policy_detailed(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        tools: &[ToolPermission],
+        token_limit: i64,
+        timeout_seconds: i32,
+        mode: PolicyMode,
+    ) -> Result<String> {
+        self.ensure_writable("set_policy")?;
+        require("org_id", org_id)?;
+        non_negative("token_limit", token_limit)?;
+        non_negative("timeout_seconds", timeout_seconds.into())?;
+        self.clear_policy_cache();
+        let tools = tools.iter().map(tool_to_proto).collect();
+        let resp = self
+            .call(
+                "SetPolicy",
+                SetPolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    tools,
+                    token_limit,
+                    execution_timeout_seconds: timeout_seconds,
+                    mode: mode.as_str().to_string(),
+                },
+                |mut c, r| async move { c.set_policy(r).await },
+            )
+            .await?;
+        Ok(resp.policy_id)
+    }
+
+    /// Adds and removes tool permissions on the live policy server-side,
+    /// leaving the rest untouched, and returns the resulting policy. Safer
+    /// than a full [`set_policy`](Self::set_policy) when others edit the same
+    /// policy concurrently. An `add` entry replaces any permission for the
+    /// same tool; `remove` names tools.
+    pub async fn patch_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        add: Vec<ToolPermission>,
+        remove: Vec<String>,
+    ) -> Result<Policy> {
+        self.ensure_writable("patch_policy")?;
+        self.clear_policy_cache();
+        let resp = self
+            .call(
+                "PatchPolicy",
+                PatchPolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    add: add.iter().map(tool_to_proto).collect(),
+                    remove,
+                },
+                |mut c, r| async move { c.patch_policy(r).await },
+            )
+            .await?;
+        Ok(policy_from_proto(resp))
+    }
+
+    /// Sets the policy described by `policy`, returning its id.
+    pub async fn set_policy_from(&mut self, policy: PolicyBuilder) -> Result<String> {
+        self.set_policy_detailed(
+            &policy.org_id,
+            policy.agent_id.as_deref(),
+            &policy.tools,
+            policy.token_limit,
+            policy.timeout_seconds,
+            policy.mode,
+        )
+        .await
+    }
+
+    /// The policy version that was live at `at`, for investigating past
+    /// decisions. `None` if no policy existed then.
+    pub async fn get_policy_at(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        at: SystemTime,
+    ) -> Result<Option<Policy>> {
+        let resp = self
+            .call(
+                "GetPolicyAt",
+                GetPolicyAtRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    at: Some(at.into()),
+                },
+                |mut c, r| async move { c.get_policy_at(r).await },
+            )
+            .await?;
+        Ok(resp.policy.map(policy_from_proto))
+    }
+
+    /// Sets the policy the server applies to every agent registered in the
+    /// org from now on. Existing agents are unaffected. Returns the policy id.
+    pub async fn set_default_agent_policy(
+        &mut self,
+        org_id: &str,
+        permissions: &[ToolPermission],
+        token_limit: i64,
+        timeout_seconds: i32,
+    ) -> Result<String> {
+        self.ensure_writable("set_default_agent_policy")?;
+        require("org_id", org_id)?;
+        non_negative("token_limit", token_limit)?;
+        non_negative("timeout_seconds", timeout_seconds.into())?;
+        let resp = self
+            .call(
+                "SetDefaultAgentPolicy",
+                SetDefaultAgentPolicyRequest {
+                    org_id: org_id.to_string(),
+                    tools: permissions.iter().map(tool_to_proto).collect(),
+                    token_limit,
+                    execution_timeout_seconds: timeout_seconds,
+                },
+                |mut c, r| async move { c.set_default_agent_policy(r).await },
+            )
+            .await?;
+        Ok(resp.policy_id)
+    }
+
+    /// The org's default policy for new agents, if one is configured.
+    pub async fn get_default_agent_policy(&mut self, org_id: &str) -> Result<Option<Policy>> {
+        let resp = self
+            .call(
+                "GetDefaultAgentPolicy",
+                GetDefaultAgentPolicyRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.get_default_agent_policy(r).await },
+            )
+            .await?;
+        Ok(resp.policy.map(policy_from_proto))
+    }
+
+    /// Fetches a policy by id. Fails with [`SdkError::NotFound`] if the org
+    /// has no such policy.
+    pub async fn get_policy(&mut self, org_id: &str, policy_id: &str) -> Result<Policy> {
+        let resp = self
+            .call(
+                "GetPolicy",
+                GetPolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: String::new(),
+                    policy_id: policy_id.to_string(),
+                },
+                |mut c, r| async move { c.get_policy(r).await },
+            )
+            .await?;
+        Ok(policy_from_proto(resp))
+    }
+
+    /// Every policy in an org, org-level and agent-level.
+    pub async fn list_policies(&mut self, org_id: &str) -> Result<Vec<Policy>> {
+        let resp = self
+            .call(
+                "ListPolicies",
+                ListPoliciesRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.list_policies(r).await },
+            )
+            .await?;
+        Ok(resp.policies.into_iter().map(policy_from_proto).collect())
+    }
+
+    /// Fetches the org's policies and reports rules that conflict or can
+    /// never apply; see [`policy::lint_policies`](crate::policy::lint_policies),
+    /// which also lints policies that haven't been uploaded.
+    pub async fn lint_policies(&mut self, org_id: &str) -> Result<Vec<PolicyLintFinding>> {
+        let policies = self.list_policies(org_id).await?;
+        Ok(crate::policy::lint_policies(&policies))
+    }
+
+    /// Deletes a policy. Fails with [`SdkError::NotFound`] if the org has no
+    /// such policy.
+    pub async fn delete_policy(&mut self, org_id: &str, policy_id: &str) -> Result<bool> {
+        self.ensure_writable("delete_policy")?;
+        self.clear_policy_cache();
+        let resp = self
+            .call(
+                "DeletePolicy",
+                DeletePolicyRequest {
+                    org_id: org_id.to_string(),
+                    policy_id: policy_id.to_string(),
+                },
+                |mut c, r| async move { c.delete_policy(r).await },
+            )
+            .await?;
+        Ok(resp.success)
+    }
+
+    /// Fetches the live policy and returns its [`Policy::content_hash`].
+    pub async fn get_policy_hash(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<String> {
+        let resp = self
+            .call(
+                "GetPolicy",
+                GetPolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    policy_id: String::new(),
+                },
+                |mut c, r| async move { c.get_policy(r).await },
+            )
+            .await?;
+        Ok(policy_from_proto(resp).content_hash())
+    }
+
+    pub async fn evaluate_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> Result<PolicyDecision> {
+        let timeout = self.timeout;
+        self.evaluate_policy_inner(org_id, agent_id, tool_name, estimated_tokens, timeout, None)
+            .await
+    }
+
+    /// Like [`evaluate_policy`](Self::evaluate_policy), passing details of
+    /// the call, such as its parameters, for the server to check against a
+    /// permission's `parameters_constraint`. Keys are parameter names, as in
+    /// the constraint. These decisions bypass the policy cache.
+    pub async fn evaluate_policy_with_context(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+        context: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<PolicyDecision> {
+        let timeout = self.timeout;
+        let context = Some(json_to_struct(context));
+        self.evaluate_policy_inner(
+            org_id,
+            agent_id,
+            tool_name,
+            estimated_tokens,
+            timeout,
+            context,
+        )
+        .await
+    }
+
+    /// Like [`evaluate_policy`](Self::evaluate_policy), with a deadline for
+    /// this call only in place of the client's
+    /// [`with_timeout`](Self::with_timeout) default.
+    pub async fn evaluate_policy_with_timeout(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+        timeout: Duration,
+    ) -> Result<PolicyDecision> {
+        self.evaluate_policy_inner(
+            org_id,
+            agent_id,
+            tool_name,
+            estimated_tokens,
+            Some(timeout),
+            None,
+        )
+        .await
+    }
+
+    async fn evaluate_policy_inner(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+        timeout: Option<Duration>,
+        context: Option<prost_types::Struct>,
+    ) -> Result<PolicyDecision> {
+        let key = (
+            org_id.to_string(),
+            agent_id.to_string(),
+            tool_name.to_string(),
+            estimated_tokens,
+        );
+        // The cache key has no context, so context-dependent decisions are
+        // neither served from nor stored in it.
+        let cacheable = context.is_none();
+        let cached = self.policy_cache.as_ref().filter(|_| cacheable);
+        if let Some(hit) = cached.and_then(|c| c.get(&key)) {
+            let decision = hit.clone();
+            self.emit_warnings(&decision.warnings);
+            return Ok(decision);
+        }
+        let resp = self
+            .call_with(
+                "EvaluatePolicy",
+                EvaluatePolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    tool_name: tool_name.to_string(),
+                    estimated_tokens,
+                    context,
+                    probe: false,
+                },
+                CallOptions {
+                    timeout,
+                    idempotent: true,
+                },
+                |mut c, r| async move { c.evaluate_policy(r).await },
+            )
+            .await?;
+        let decision = decision_from_proto(resp);
+        self.emit_warnings(&decision.warnings);
+        if let Some(cache) = self.policy_cache.as_mut().filter(|_| cacheable) {
+            cache.put(key, &decision);
+        }
+        Ok(decision)
+    }
+
+    /// Evaluates several `(tool_name, estimated_tokens)` candidates for one
+    /// agent in a single round trip, returning one decision per tool in
+    /// input order. An empty slice returns an empty vec without a call.
+    /// Decisions bypass the policy cache.
+    pub async fn evaluate_policies(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tools: &[(&str, i64)],
+    ) -> Result<Vec<PolicyDecision>> {
+        if tools.is_empty() {
+            return Ok(Vec::new());
+        }
+        let resp = self
+            .call(
+                "EvaluatePolicies",
+                EvaluatePoliciesRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    tools: tools
+                        .iter()
+                        .map(|(tool_name, estimated_tokens)| ToolEstimateProto {
+                            tool_name: tool_name.to_string(),
+                            estimated_tokens: *estimated_tokens,
+                        })
+                        .collect(),
+                },
+                |mut c, r| async move { c.evaluate_policies(r).await },
+            )
+            .await?;
+        if resp.decisions.len() != tools.len() {
+            return Err(SdkError::InvalidResponse(format!(
+                "expected {} decisions, got {}",
+                tools.len(),
+                resp.decisions.len()
+            )));
+        }
+        let decisions: Vec<PolicyDecision> = resp
+            .decisions
+            .into_iter()
+            .map(decision_from_proto)
+            .collect();
+        for decision in &decisions {
+            self.emit_warnings(&decision.warnings);
+        }
+        Ok(decisions)
+    }
+
+    /// Evaluates every agent × tool combination in one call. The result has
+    /// one row per agent and one decision per tool, in input order. Decisions
+    /// bypass the policy cache.
+    pub async fn evaluate_matrix(
+        &mut self,
+        org_id: &str,
+        agent_ids: Vec<String>,
+        tool_names: Vec<String>,
+    ) -> Result<Vec<Vec<PolicyDecision>>> {
+        let (agents, tools) = (agent_ids.len(), tool_names.len());
+        let resp = self
+            .call(
+                "EvaluateMatrix",
+                EvaluateMatrixRequest {
+                    org_id: org_id.to_string(),
+                    agent_ids,
+                    tool_names,
+                },
+                |mut c, r| async move { c.evaluate_matrix(r).await },
+            )
+            .await?;
+        if resp.rows.len() != agents || resp.rows.iter().any(|r| r.decisions.len() != tools) {
+            return Err(SdkError::InvalidResponse(format!(
+                "expected a {agents}x{tools} decision matrix"
+            )));
+        }
+        Ok(resp
+            .rows
+            .into_iter()
+            .map(|r| r.decisions.into_iter().map(decision_from_proto).collect())
+            .collect())
+    }
+
+    /// Every policy in the org with a permission entry for `tool_name`,
+    /// searched server-side.
+    pub async fn find_policies_by_tool(
+        &mut self,
+        org_id: &str,
+        tool_name: &str,
+    ) -> Result<Vec<Policy>> {
+        let resp = self
+            .call(
+                "FindPoliciesByTool",
+                FindPoliciesByToolRequest {
+                    org_id: org_id.to_string(),
+                    tool_name: tool_name.to_string(),
+                },
+                |mut c, r| async move { c.find_policies_by_tool(r).await },
+            )
+            .await?;
+        Ok(resp.policies.into_iter().map(policy_from_proto).collect())
+    }
+
+    /// The merged org- and agent-level policy that governs `agent_id`, for
+    /// working out why a call was denied. See
+    /// [`EffectivePolicy::precedence`] for which levels took part.
+    pub async fn get_effective_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> Result<EffectivePolicy> {
+        let resp = self
+            .call(
+                "GetEffectivePolicy",
+                GetEffectivePolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                },
+                |mut c, r| async move { c.get_effective_policy(r).await },
+            )
+            .await?;
+        Ok(effective_policy_from_proto(resp))
+    }
+
+    /// Effective policies for every agent in the org, resolved server-side in
+    /// one call. Returns `(agent_id, policy)` pairs.
+    pub async fn get_effective_policies(
+        &mut self,
+        org_id: &str,
+    ) -> Result<Vec<(String, EffectivePolicy)>> {
         let resp = self
-            .inner
-            .report_usage(ReportUsageRequest {
+            .call(
+                "GetEffectivePolicies",
+                GetEffectivePoliciesRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.get_effective_policies(r).await },
+            )
+            .await?;
+        Ok(resp
+            .policies
+            .into_iter()
+            .map(|p| (p.agent_id.clone(), effective_policy_from_proto(p)))
+            .collect())
+    }
+
+    /// Times a representative `evaluate_policy` round trip, for synthetic
+    /// latency monitoring. The request is flagged as a probe, so it estimates
+    /// zero tokens and is neither audited nor counted against any budget; the
+    /// decision itself is discarded.
+    pub async fn probe_policy_latency(&mut self, org_id: &str, agent_id: &str) -> Result<Duration> {
+        let started = Instant::now();
+        self.call(
+            "EvaluatePolicy",
+            EvaluatePolicyRequest {
                 org_id: org_id.to_string(),
                 agent_id: agent_id.to_string(),
-                execution_id: execution_id.to_string(),
-                tokens_used,
-                tool_invocations,
-                execution_duration_ms: duration_ms,
-                tool_name: String::new(),
-            })
-            .await?
-            .into_inner();
-        Ok(resp.tokens_remaining)
+                tool_name: PROBE_TOOL.to_string(),
+                estimated_tokens: 0,
+                context: None,
+                probe: true,
+            },
+            |mut c, r| async move { c.evaluate_policy(r).await },
+        )
+        .await?;
+        Ok(started.elapsed())
+    }
+
+    /// Marks a tool as deprecated for the org. Calls to it remain allowed but
+    /// `evaluate_policy` returns `message` as a warning.
+    pub async fn deprecate_tool(
+        &mut self,
+        org_id: &str,
+        tool_name: &str,
+        message: &str,
+    ) -> Result<bool> {
+        self.ensure_writable("deprecate_tool")?;
+        self.clear_policy_cache();
+        let resp = self
+            .call(
+                "DeprecateTool",
+                DeprecateToolRequest {
+                    org_id: org_id.to_string(),
+                    tool_name: tool_name.to_string(),
+                    message: message.to_string(),
+                },
+                |mut c, r| async move { c.deprecate_tool(r).await },
+            )
+            .await?;
+        Ok(resp.success)
+    }
+
+    /// Replays the history in `range` against candidate `permissions` without
+    /// applying them, streaming each call whose outcome would change as the
+    /// server reaches it. Suited to histories too large to buffer.
+    pub async fn backtest_policy_stream(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        permissions: &[ToolPermission],
+        range: Range<SystemTime>,
+        config: StreamConfig,
+    ) -> Result<EventStream<BacktestEvent>> {
+        let tools = permissions.iter().map(tool_to_proto).collect();
+        let stream = self
+            .call(
+                "BacktestPolicy",
+                BacktestPolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    tools,
+                    start_time: Some(range.start.into()),
+                    end_time: Some(range.end.into()),
+                },
+                |mut c, r| async move { c.backtest_policy(r).await },
+            )
+            .await?;
+        Ok(EventStream::spawn(stream, config, |e| BacktestEvent {
+            entry_id: e.entry_id,
+            agent_id: e.agent_id,
+            tool_name: e.tool_name,
+            timestamp: time(e.timestamp),
+            originally_allowed: e.originally_allowed,
+            would_allow: e.would_allow,
+            reason: e.reason,
+        }))
+    }
+
+    /// Submits tool permissions that the server applies at `activate_at`,
+    /// keeping the token limit, timeout and mode of the policy live at that
+    /// time. Returns the scheduled change id.
+    pub async fn schedule_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        permissions: &[ToolPermission],
+        activate_at: SystemTime,
+    ) -> Result<String> {
+        self.ensure_writable("schedule_policy")?;
+        let tools = permissions.iter().map(tool_to_proto).collect();
+        let resp = self
+            .call(
+                "SchedulePolicy",
+                SchedulePolicyRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    tools,
+                    activate_at: Some(activate_at.into()),
+                },
+                |mut c, r| async move { c.schedule_policy(r).await },
+            )
+            .await?;
+        Ok(resp.change_id)
+    }
+
+    /// Pending scheduled changes for an org, or one agent when `agent_id` is
+    /// set, in activation order.
+    pub async fn list_scheduled_changes(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<Vec<ScheduledPolicyChange>> {
+        let resp = self
+            .call(
+                "ListScheduledChanges",
+                ListScheduledChangesRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.list_scheduled_changes(r).await },
+            )
+            .await?;
+        Ok(resp
+            .changes
+            .into_iter()
+            .map(|c| ScheduledPolicyChange {
+                change_id: c.change_id,
+                org_id: c.org_id,
+                agent_id: opt(c.agent_id),
+                tools: c.tools.into_iter().map(tool_from_proto).collect(),
+                activate_at: time(c.activate_at),
+                created_at: time(c.created_at),
+            })
+            .collect())
+    }
+
+    /// Cancels a change that has not activated yet.
+    pub async fn cancel_scheduled_change(&mut self, org_id: &str, change_id: &str) -> Result<bool> {
+        self.ensure_writable("cancel_scheduled_change")?;
+        let resp = self
+            .call(
+                "CancelScheduledChange",
+                CancelScheduledChangeRequest {
+                    org_id: org_id.to_string(),
+                    change_id: change_id.to_string(),
+                },
+                |mut c, r| async move { c.cancel_scheduled_change(r).await },
+            )
+            .await?;
+        Ok(resp.success)
+    }
+
+    // --- Budget ---
+
+    /// A `token_limit` or `reset_period_days` of 0 selects the server
+    /// default (1,000,000 tokens, 30 days). Negative values, or an empty
+    /// `org_id`, fail with [`SdkError::InvalidInput`].
+    pub async fn set_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+    ) -> Result<BudgetInfo> {
+        self.set_budget_with_warning(org_id, agent_id, token_limit, reset_period_days, None)
+            .await
+    }
+
+    /// [`set_budget`](Self::set_budget) that also makes `check_budget`
+    /// return a [`BudgetCheck::warning`] once utilization reaches
+    /// `warning_threshold_percent`. A threshold above 100 fails with
+    /// [`SdkError::InvalidInput`]; `None` or 0 turns the warning off.
+    pub async fn set_budget_with_warning(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+        warning_threshold_percent: Option<u8>,
+    ) -> Result<BudgetInfo> {
+        self.ensure_writable("set_budget")?;
+        require("org_id", org_id)?;
+        non_negative("token_limit", token_limit)?;
+        non_negative("reset_period_days", reset_period_days.into())?;
+        let warning_threshold_percent = warning_threshold_percent.unwrap_or(0);
+        if warning_threshold_percent > 100 {
+            return Err(SdkError::InvalidInput(format!(
+                "warning_threshold_percent must be at most 100, got {warning_threshold_percent}"
+            )));
+        }
+        let resp = self
+            .call(
+                "SetBudget",
+                SetBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    token_limit,
+                    reset_period_days,
+                    warning_threshold_percent: warning_threshold_percent.into(),
+                },
+                |mut c, r| async move { c.set_budget(r).await },
+            )
+            .await?;
+        Ok(budget_from_proto(resp))
+    }
+
+    /// Current state of a budget, with live `tokens_used` and
+    /// `tokens_remaining`. `agent_id: None` reads the org-level budget.
+    pub async fn get_budget(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<BudgetInfo> {
+        let resp = self
+            .call(
+                "GetBudget",
+                GetBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.get_budget(r).await },
+            )
+            .await?;
+        Ok(budget_from_proto(resp))
+    }
+
+    /// Zeroes a budget's used tokens and invocations without waiting for
+    /// its period to end, returning the refreshed budget.
+    pub async fn reset_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<BudgetInfo> {
+        self.ensure_writable("reset_budget")?;
+        let resp = self
+            .call(
+                "ResetBudget",
+                ResetBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.reset_budget(r).await },
+            )
+            .await?;
+        Ok(budget_from_proto(resp))
+    }
+
+    /// Streams an event each time one of the org's budgets resets, so work
+    /// held back for budget can resume without polling.
+    pub async fn watch_budget_resets(
+        &mut self,
+        org_id: &str,
+        config: StreamConfig,
+    ) -> Result<EventStream<BudgetResetEvent>> {
+        let stream = self
+            .call(
+                "WatchBudgetResets",
+                WatchBudgetResetsRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.watch_budget_resets(r).await },
+            )
+            .await?;
+        Ok(EventStream::spawn(
+            stream,
+            config,
+            |e: BudgetResetEventProto| BudgetResetEvent {
+                org_id: e.org_id,
+                agent_id: opt(e.agent_id),
+                token_limit: e.token_limit,
+                reset_at: time(e.reset_at),
+            },
+        ))
+    }
+
+    /// Applies several agent budgets in one call. Each entry is
+    /// `(agent_id, token_limit, reset_period_days)`; results are in input order.
+    pub async fn set_budgets(
+        &mut self,
+        org_id: &str,
+        budgets: Vec<(String, i64, i32)>,
+    ) -> Result<BatchResult<BudgetInfo>> {
+        self.ensure_writable("set_budgets")?;
+        require("org_id", org_id)?;
+        for (i, (_, token_limit, reset_period_days)) in budgets.iter().enumerate() {
+            let check = non_negative("token_limit", *token_limit)
+                .and_then(|_| non_negative("reset_period_days", (*reset_period_days).into()));
+            indexed("budgets", i, check)?;
+        }
+        let count = budgets.len();
+        let resp = self
+            .call(
+                "SetBudgets",
+                SetBudgetsRequest {
+                    org_id: org_id.to_string(),
+                    budgets: budgets
+                        .into_iter()
+                        .map(
+                            |(agent_id, token_limit, reset_period_days)| SetBudgetRequest {
+                                org_id: org_id.to_string(),
+                                agent_id,
+                                token_limit,
+                                reset_period_days,
+                                ..Default::default()
+                            },
+                        )
+                        .collect(),
+                },
+                |mut c, r| async move { c.set_budgets(r).await },
+            )
+            .await?;
+        if resp.results.len() != count {
+            return Err(SdkError::InvalidResponse(format!(
+                "expected {count} budget results, got {}",
+                resp.results.len()
+            )));
+        }
+        Ok(BatchResult {
+            results: resp
+                .results
+                .into_iter()
+                .map(|r| {
+                    if !r.error.is_empty() {
+                        Err(r.error)
+                    } else {
+                        r.budget
+                            .map(budget_from_proto)
+                            .ok_or_else(|| "missing budget in result".to_string())
+                    }
+                })
+                .collect(),
+        })
+    }
+
+    pub async fn check_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+    ) -> Result<BudgetCheck> {
+        self.check_budget_inner(org_id, agent_id, estimated_tokens, false)
+            .await
+    }
+
+    /// Like [`check_budget`](Self::check_budget), but when allowed also holds
+    /// `estimated_tokens` and returns a `reservation_id` to settle via
+    /// [`report_usage_record`](Self::report_usage_record). Unsettled holds
+    /// show up in [`list_open_reservations`](Self::list_open_reservations).
+    pub async fn check_budget_with_reservation(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+    ) -> Result<BudgetCheck> {
+        self.ensure_writable("check_budget_with_reservation")?;
+        self.check_budget_inner(org_id, agent_id, estimated_tokens, true)
+            .await
+    }
+
+    async fn check_budget_inner(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+        reserve: bool,
+    ) -> Result<BudgetCheck> {
+        // A reserving check holds tokens, so resending it could hold them twice.
+        let opts = CallOptions {
+            timeout: self.timeout,
+            idempotent: !reserve,
+        };
+        let resp = self
+            .call_with(
+                "CheckBudget",
+                CheckBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    estimated_tokens,
+                    reserve,
+                },
+                opts,
+                |mut c, r| async move { c.check_budget(r).await },
+            )
+            .await?;
+        Ok(BudgetCheck {
+            allowed: resp.allowed,
+            tokens_remaining: resp.tokens_remaining,
+            reason: resp.reason,
+            reservation_id: opt(resp.reservation_id),
+            utilization: resp.utilization,
+            warning: opt(resp.warning),
+        })
+    }
+
+    /// Atomically holds `estimated_tokens` of the agent's budget so that
+    /// concurrent agents cannot spend them before this one reports. Settle
+    /// the hold with [`commit_reservation`](Self::commit_reservation) or
+    /// [`release_reservation`](Self::release_reservation); otherwise the
+    /// server releases it at [`Reservation::expires_at`]. Fails with
+    /// [`SdkError::BudgetExhausted`] if the budget cannot cover the hold.
+    pub async fn reserve_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+    ) -> Result<Reservation> {
+        self.ensure_writable("reserve_budget")?;
+        // A mutation despite the read-like RPC: a resent reservation could
+        // hold the tokens twice, so it is only resent under `retry_mutations`,
+        // with an idempotency key.
+        let opts = CallOptions {
+            timeout: self.timeout,
+            idempotent: false,
+        };
+        let resp = self
+            .call_with(
+                "CheckBudget",
+                CheckBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    estimated_tokens,
+                    reserve: true,
+                },
+                opts,
+                |mut c, r| async move { c.check_budget(r).await },
+            )
+            .await?;
+        if !resp.allowed {
+            return Err(SdkError::BudgetExhausted {
+                tokens_remaining: Some(resp.tokens_remaining),
+                reason: resp.reason,
+            });
+        }
+        resp.reservation
+            .map(reservation_from_proto)
+            .ok_or_else(|| SdkError::InvalidResponse("missing reservation".into()))
+    }
+
+    /// Settles a reservation by reporting what the execution actually used;
+    /// the unused part of the hold returns to the budget. Returns the tokens
+    /// remaining afterwards.
+    pub async fn commit_reservation(
+        &mut self,
+        reservation: Reservation,
+        execution_id: &str,
+        actual_tokens: i64,
+    ) -> Result<i64> {
+        let record = UsageRecord {
+            execution_id: execution_id.to_string(),
+            tokens_used: actual_tokens,
+            reservation_id: Some(reservation.reservation_id),
+            ..UsageRecord::default()
+        };
+        self.report_usage_record(&reservation.org_id, &reservation.agent_id, record)
+            .await
+    }
+
+    /// Returns a reservation's tokens to the budget without using any, e.g.
+    /// when the execution was cancelled. Returns `false` if the hold had
+    /// already expired or been settled.
+    pub async fn release_reservation(&mut self, reservation: Reservation) -> Result<bool> {
+        self.ensure_writable("release_reservation")?;
+        let resp = self
+            .call(
+                "ReleaseReservation",
+                ReleaseReservationRequest {
+                    org_id: reservation.org_id,
+                    reservation_id: reservation.reservation_id,
+                },
+                |mut c, r| async move { c.release_reservation(r).await },
+            )
+            .await?;
+        Ok(resp.released)
+    }
+
+    /// Checks and deducts `tokens` in a single server-side operation, for
+    /// callers that know the exact cost up front. Nothing is deducted if the
+    /// budget cannot cover it; that case returns [`SdkError::BudgetExhausted`].
+    /// A [`BudgetCheck::warning`] is also passed to the
+    /// [`on_warning`](Self::on_warning) handler.
+    pub async fn consume_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tokens: i64,
+    ) -> Result<BudgetCheck> {
+        self.ensure_writable("consume_budget")?;
+        let resp = self
+            .call(
+                "ConsumeBudget",
+                ConsumeBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    tokens,
+                },
+                |mut c, r| async move { c.consume_budget(r).await },
+            )
+            .await?;
+        if !resp.allowed {
+            return Err(SdkError::BudgetExhausted {
+                tokens_remaining: Some(resp.tokens_remaining),
+                reason: resp.reason,
+            });
+        }
+        let warning = opt(resp.warning);
+        if let Some(w) = &warning {
+            self.emit_warnings(std::slice::from_ref(w));
+        }
+        Ok(BudgetCheck {
+            allowed: true,
+            tokens_remaining: resp.tokens_remaining,
+            reason: resp.reason,
+            reservation_id: None,
+            utilization: resp.utilization,
+            warning,
+        })
+    }
+
+    /// Moves `tokens` of budget limit from one agent to another in a single
+    /// server transaction, preserving the total. Returns the updated
+    /// `(from, to)` budgets.
+    pub async fn transfer_budget(
+        &mut self,
+        org_id: &str,
+        from_agent: &str,
+        to_agent: &str,
+        tokens: i64,
+    ) -> Result<(BudgetInfo, BudgetInfo)> {
+        self.ensure_writable("transfer_budget")?;
+        let resp = self
+            .call(
+                "TransferBudget",
+                TransferBudgetRequest {
+                    org_id: org_id.to_string(),
+                    from_agent_id: from_agent.to_string(),
+                    to_agent_id: to_agent.to_string(),
+                    tokens,
+                },
+                |mut c, r| async move { c.transfer_budget(r).await },
+            )
+            .await?;
+        match (resp.from_budget, resp.to_budget) {
+            (Some(from), Some(to)) => Ok((budget_from_proto(from), budget_from_proto(to))),
+            _ => Err(SdkError::InvalidResponse(
+                "transfer response missing a budget".to_string(),
+            )),
+        }
+    }
+
+    /// Compares the sum of agent budget limits with the org-level budget,
+    /// computed server-side. `overallocated` means the agents together may
+    /// consume more than the org allows.
+    pub async fn check_budget_allocation(&mut self, org_id: &str) -> Result<AllocationReport> {
+        let resp = self
+            .call(
+                "CheckBudgetAllocation",
+                CheckBudgetAllocationRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.check_budget_allocation(r).await },
+            )
+            .await?;
+        Ok(AllocationReport {
+            org_limit: resp.org_limit,
+            allocated: resp.allocated,
+            overallocated: resp.overallocated,
+            per_agent: resp
+                .per_agent
+                .into_iter()
+                .map(|a| AgentAllocation {
+                    agent_id: a.agent_id,
+                    token_limit: a.token_limit,
+                })
+                .collect(),
+        })
+    }
+
+    /// Token limit that would have covered `target_percentile` (0.0 to 1.0)
+    /// of the agent's past reset periods, computed server-side from its
+    /// usage history. Returns 0 when the agent has no history.
+    pub async fn recommend_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        target_percentile: f64,
+    ) -> Result<i64> {
+        let resp = self
+            .call(
+                "RecommendBudget",
+                RecommendBudgetRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    target_percentile,
+                },
+                |mut c, r| async move { c.recommend_budget(r).await },
+            )
+            .await?;
+        Ok(resp.recommended_tokens)
+    }
+
+    /// Every agent in the org with no tokens remaining, alongside its budget.
+    /// Computed server-side in one scan of the org's budgets.
+    pub async fn list_exhausted_agents(
+        &mut self,
+        org_id: &str,
+    ) -> Result<Vec<(Agent, BudgetInfo)>> {
+        let resp = self
+            .call(
+                "ListExhaustedAgents",
+                ListExhaustedAgentsRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.list_exhausted_agents(r).await },
+            )
+            .await?;
+        resp.agents
+            .into_iter()
+            .map(|e| match (e.agent, e.budget) {
+                (Some(agent), Some(budget)) => {
+                    Ok((agent_from_proto(agent), budget_from_proto(budget)))
+                }
+                _ => Err(SdkError::InvalidResponse(
+                    "exhausted agent entry missing agent or budget".to_string(),
+                )),
+            })
+            .collect()
+    }
+
+    /// Reservations from `check_budget_with_reservation` that no usage report
+    /// has settled yet, e.g. from agents that crashed before reporting.
+    pub async fn list_open_reservations(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<Vec<Reservation>> {
+        let resp = self
+            .call(
+                "ListOpenReservations",
+                ListOpenReservationsRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.list_open_reservations(r).await },
+            )
+            .await?;
+        Ok(resp
+            .reservations
+            .into_iter()
+            .map(reservation_from_proto)
+            .collect())
+    }
+
+    pub async fn report_usage(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        execution_id: &str,
+        tokens_used: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+    ) -> Result<i64> {
+        self.report_usage_with_metadata(
+            org_id,
+            agent_id,
+            execution_id,
+            tokens_used,
+            tool_invocations,
+            duration_ms,
+            HashMap::new(),
+        )
+        .await
+    }
+
+    /// Like [`report_usage`](Self::report_usage), tagging the report with
+    /// free-form metadata (prompt category, ...) that is returned
+    /// by [`list_usage_reports`](Self::list_usage_reports).
+    #[allow(clippy::too_many_arguments)]
+    pub async fn report_usage_with_metadata(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        execution_id: &str,
+        tokens_used: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+        metadata: HashMap<String, String>,
+    ) -> Result<i64> {
+        let record = UsageRecord {
+            execution_id: execution_id.to_string(),
+            tokens_used,
+            tool_invocations,
+            duration_ms,
+            metadata,
+            ..Default::default()
+        };
+        self.report_usage_record(org_id, agent_id, record).await
+    }
+
+    /// Reports usage described by a [`UsageRecord`], which carries every
+    /// optional field. Returns the tokens remaining in the budget.
+    pub async fn report_usage_record(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        record: UsageRecord,
+    ) -> Result<i64> {
+        self.ensure_writable("report_usage")?;
+        let resp = self
+            .call(
+                "ReportUsage",
+                usage_request(org_id, agent_id, record),
+                |mut c, r| async move { c.report_usage(r).await },
+            )
+            .await?;
+        Ok(resp.tokens_remaining)
+    }
+
+    /// Reports every record from `records` over a single client-streaming
+    /// call, for agents emitting usage at high frequency. Records are pulled
+    /// only as fast as the connection accepts them. Returns the tokens
+    /// remaining once the stream ends. Never retried, since the records
+    /// cannot be replayed, and sent without an idempotency key.
+    ///
+    /// The client's [`with_timeout`](Self::with_timeout) limit is meant for
+    /// single calls, not for a stream that lasts as long as its source, so
+    /// it does not apply; bound the upload with [`crate::deadline`] instead.
+    pub async fn report_usage_stream(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        records: impl Stream<Item = UsageRecord> + Send + 'static,
+    ) -> Result<i64> {
+        self.ensure_writable("report_usage")?;
+        let (org_id, agent_id) = (org_id.to_string(), agent_id.to_string());
+        let span = CallSpan::new("ReportUsageStream", Some(&org_id), Some(&agent_id));
+        let requests = records.map(move |r| usage_request(&org_id, &agent_id, r));
+        let resp = span
+            .run(async {
+                self.attempt(
+                    "ReportUsageStream",
+                    requests,
+                    None,
+                    None,
+                    &|mut c, r| async move { c.report_usage_stream(r).await },
+                )
+                .await
+                .map_err(classify)
+            })
+            .await?;
+        Ok(resp.tokens_remaining)
+    }
+
+    /// Reports a whole execution in one call. The server charges
+    /// `total_tokens` to the budget and records each tool call alongside the
+    /// rollup. Returns the tokens remaining afterwards.
+    pub async fn report_execution(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        execution: Execution,
+    ) -> Result<i64> {
+        self.ensure_writable("report_execution")?;
+        let resp = self
+            .call(
+                "ReportExecution",
+                ReportExecutionRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                    execution_id: execution.execution_id,
+                    tool_calls: execution
+                        .tool_calls
+                        .into_iter()
+                        .map(|t| ToolUsageProto {
+                            tool_name: t.tool_name,
+                            tokens_used: t.tokens_used,
+                            duration_ms: t.duration_ms,
+                        })
+                        .collect(),
+                    total_tokens: execution.total_tokens,
+                    duration_ms: execution.duration_ms,
+                },
+                |mut c, r| async move { c.report_execution(r).await },
+            )
+            .await?;
+        Ok(resp.tokens_remaining)
+    }
+
+    pub async fn list_usage_reports(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<Vec<UsageReport>> {
+        let page = self
+            .list_executions(org_id, agent_id, &ExecutionFilter::default(), 0, None)
+            .await?;
+        Ok(page.items)
+    }
+
+    /// One page of the usage reports `report_usage` and its variants wrote,
+    /// oldest first, narrowed by `filter`. A `page_size` of 0 returns every
+    /// match in one page. Pass `next_page_token` back to continue.
+    pub async fn list_executions(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        filter: &ExecutionFilter,
+        page_size: i32,
+        page_token: Option<&str>,
+    ) -> Result<Page<UsageReport>> {
+        let (start_time, end_time) = match &filter.window {
+            Some(range) => (Some(range.start.into()), Some(range.end.into())),
+            None => (None, None),
+        };
+        let resp = self
+            .call(
+                "ListUsageReports",
+                ListUsageReportsRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    start_time,
+                    end_time,
+                    tool_name: filter.tool_name.clone().unwrap_or_default(),
+                    page_size,
+                    page_token: page_token.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.list_usage_reports(r).await },
+            )
+            .await?;
+        Ok(Page {
+            items: resp
+                .reports
+                .into_iter()
+                .map(usage_report_from_proto)
+                .collect(),
+            next_page_token: opt(resp.next_page_token),
+        })
+    }
+
+    /// Usage totals for an org, or one agent when `agent_id` is set, within
+    /// `window` when given (e.g. `now - 24h..now`) or over all retained
+    /// history. Fails with [`SdkError::NotFound`] for an unknown agent.
+    pub async fn get_usage_summary(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        window: Option<Range<SystemTime>>,
+    ) -> Result<UsageSummary> {
+        let (start_time, end_time) = match window {
+            Some(range) => (Some(range.start.into()), Some(range.end.into())),
+            None => (None, None),
+        };
+        let resp = self
+            .call(
+                "GetUsage",
+                GetUsageRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    start_time,
+                    end_time,
+                },
+                |mut c, r| async move { c.get_usage(r).await },
+            )
+            .await?;
+        Ok(usage_from_proto(resp))
+    }
+
+    /// Usage summed across agents per `delegated_user_id`, for user-level
+    /// cost attribution. Agents without a delegated user are not included.
+    pub async fn get_usage_by_user(&mut self, org_id: &str) -> Result<Vec<(String, UsageSummary)>> {
+        let resp = self
+            .call(
+                "GetUsageByUser",
+                GetUsageByUserRequest {
+                    org_id: org_id.to_string(),
+                },
+                |mut c, r| async move { c.get_usage_by_user(r).await },
+            )
+            .await?;
+        Ok(resp
+            .users
+            .into_iter()
+            .map(|u| {
+                (
+                    u.delegated_user_id,
+                    u.summary.map(usage_from_proto).unwrap_or_default(),
+                )
+            })
+            .collect())
+    }
+
+    /// Usage for an org, or one agent when `agent_id` is set, grouped by the
+    /// [`UsageRecord::model`] it was reported with. Usage reported without a
+    /// model is grouped under an empty name.
+    pub async fn get_usage_by_model(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> Result<Vec<(String, UsageSummary)>> {
+        let resp = self
+            .call(
+                "GetUsageByModel",
+                GetUsageByModelRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                },
+                |mut c, r| async move { c.get_usage_by_model(r).await },
+            )
+            .await?;
+        Ok(resp
+            .models
+            .into_iter()
+            .map(|m| (m.model, m.summary.map(usage_from_proto).unwrap_or_default()))
+            .collect())
+    }
+
+    /// Each tool name in an org's usage history since `since` (or ever, when
+    /// `None`) with its invocation count, most invoked first. Includes tools
+    /// no policy governs.
+    pub async fn list_used_tools(
+        &mut self,
+        org_id: &str,
+        since: Option<SystemTime>,
+    ) -> Result<Vec<(String, u64)>> {
+        let resp = self
+            .call(
+                "ListUsedTools",
+                ListUsedToolsRequest {
+                    org_id: org_id.to_string(),
+                    since: since.map(Into::into),
+                },
+                |mut c, r| async move { c.list_used_tools(r).await },
+            )
+            .await?;
+        Ok(resp
+            .tools
+            .into_iter()
+            .map(|t| (t.tool_name, t.invocation_count))
+            .collect())
+    }
+
+    /// Projects an org's spend at `until` by carrying its average burn rate
+    /// since its earliest usage report forward. Costs come from `pricing`
+    /// applied to each report's tokens and model. An `until` in the past
+    /// projects no further spend.
+    pub async fn forecast_cost(
+        &mut self,
+        org_id: &str,
+        pricing: &PricingModel,
+        until: SystemTime,
+    ) -> Result<CostForecast> {
+        let reports = self.list_usage_reports(org_id, None).await?;
+        let now = SystemTime::now();
+        let cost_to_date: f64 = reports
+            .iter()
+            .map(|r| pricing.cost(r.model.as_deref(), r.tokens_used))
+            .sum();
+        let elapsed = reports
+            .iter()
+            .filter_map(|r| r.reported_at)
+            .min()
+            .and_then(|first| now.duration_since(first).ok())
+            .unwrap_or_default();
+        let remaining = until.duration_since(now).unwrap_or_default();
+        const DAY_SECS: f64 = 86_400.0;
+        // With too little history to measure a rate, assume no further spend.
+        let daily_burn_rate = if elapsed.is_zero() {
+            0.0
+        } else {
+            cost_to_date / elapsed.as_secs_f64() * DAY_SECS
+        };
+        Ok(CostForecast {
+            cost_to_date,
+            daily_burn_rate,
+            projected_cost: cost_to_date + daily_burn_rate * remaining.as_secs_f64() / DAY_SECS,
+            until,
+        })
+    }
+
+    /// [`ControlPlane::guard`], callable without importing the trait.
+    pub async fn guard(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> Result<Guard> {
+        crate::ControlPlane::guard(self, org_id, agent_id, tool_name, estimated_tokens).await
+    }
+
+    // --- Rate Limits ---
+
+    /// Caps how many calls per minute `agent_id`, or every agent in the org
+    /// when `None`, may make; see
+    /// [`check_rate_limit`](Self::check_rate_limit). A `max_per_minute` of 0
+    /// removes the limit; negative values fail with
+    /// [`SdkError::InvalidInput`].
+    pub async fn set_rate_limit(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        max_per_minute: i32,
+    ) -> Result<RateLimit> {
+        self.ensure_writable("set_rate_limit")?;
+        require("org_id", org_id)?;
+        non_negative("max_per_minute", max_per_minute.into())?;
+        let resp = self
+            .call(
+                "SetRateLimit",
+                SetRateLimitRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    max_per_minute,
+                },
+                |mut c, r| async move { c.set_rate_limit(r).await },
+            )
+            .await?;
+        Ok(RateLimit {
+            max_per_minute: resp.max_per_minute,
+        })
+    }
+
+    /// Counts one call by `agent_id` against its rate limits and the org's.
+    /// A call over the limit comes back with `allowed: false`, not an error;
+    /// use [`RateLimitCheck::require_allowed`] to get
+    /// [`SdkError::RateLimited`] instead. Since each attempt counts, it is
+    /// only resent under
+    /// [`retry_mutations`](crate::retry::RetryPolicy::retry_mutations), with
+    /// an idempotency key.
+    pub async fn check_rate_limit(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> Result<RateLimitCheck> {
+        let opts = CallOptions {
+            timeout: self.timeout,
+            idempotent: false,
+        };
+        let resp = self
+            .call_with(
+                "CheckRateLimit",
+                CheckRateLimitRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                },
+                opts,
+                |mut c, r| async move { c.check_rate_limit(r).await },
+            )
+            .await?;
+        Ok(RateLimitCheck {
+            allowed: resp.allowed,
+            remaining: (resp.remaining >= 0).then_some(resp.remaining),
+            reset_at: time(resp.reset_at),
+            reason: resp.reason,
+        })
+    }
+
+    // --- Config Snapshots ---
+
+    /// [`ControlPlane::export_org_config`](crate::ControlPlane::export_org_config),
+    /// callable without importing the trait.
+    pub async fn export_org_config(&mut self, org_id: &str) -> Result<OrgConfig> {
+        crate::ControlPlane::export_org_config(self, org_id).await
+    }
+
+    /// [`ControlPlane::import_org_config`](crate::ControlPlane::import_org_config),
+    /// callable without importing the trait. Read-only clients fail before
+    /// anything is read.
+    pub async fn import_org_config(
+        &mut self,
+        org_id: &str,
+        config: &OrgConfig,
+        create_agents: bool,
+    ) -> Result<Vec<RegisteredAgent>> {
+        self.ensure_writable("import_org_config")?;
+        crate::ControlPlane::import_org_config(self, org_id, config, create_agents).await
+    }
+
+    // --- Audit ---
+
+    /// Streams audit entries for an org as the server writes them.
+    pub async fn tail_audit_log(
+        &mut self,
+        org_id: &str,
+        config: StreamConfig,
+    ) -> Result<EventStream<AuditEntry>> {
+        self.tail_audit_log_from(org_id, 0, config).await
+    }
+
+    /// Like [`tail_audit_log`](Self::tail_audit_log), but first replays
+    /// buffered entries after `cursor` (an [`AuditEntry::cursor`] from an
+    /// earlier stream), so a consumer can catch up after downtime.
+    pub async fn tail_audit_log_from(
+        &mut self,
+        org_id: &str,
+        cursor: u64,
+        config: StreamConfig,
+    ) -> Result<EventStream<AuditEntry>> {
+        let stream = self
+            .call(
+                "TailAuditLog",
+                TailAuditLogRequest {
+                    org_id: org_id.to_string(),
+                    after_cursor: cursor,
+                },
+                |mut c, r| async move { c.tail_audit_log(r).await },
+            )
+            .await?;
+        Ok(EventStream::spawn(stream, config, audit_from_proto))
+    }
+
+    /// Exports the audit entries in `range` with the server's hash chain and
+    /// signature. Check the result with [`audit::verify`](crate::audit::verify).
+    pub async fn export_audit_log_signed(
+        &mut self,
+        org_id: &str,
+        range: Range<SystemTime>,
+    ) -> Result<SignedAuditExport> {
+        let resp = self
+            .call(
+                "ExportAuditLogSigned",
+                ExportAuditLogSignedRequest {
+                    org_id: org_id.to_string(),
+                    start_time: Some(range.start.into()),
+                    end_time: Some(range.end.into()),
+                },
+                |mut c, r| async move { c.export_audit_log_signed(r).await },
+            )
+            .await?;
+        let mut entries = Vec::with_capacity(resp.entries.len());
+        for e in resp.entries {
+            let entry = AuditEntryProto::decode(e.payload.as_slice())
+                .map_err(|err| SdkError::InvalidResponse(format!("bad audit payload: {err}")))?;
+            entries.push(SignedAuditEntry {
+                entry: audit_from_proto(entry),
+                payload: e.payload,
+                hash: e.hash,
+            });
+        }
+        Ok(SignedAuditExport {
+            entries,
+            anchor_hash: resp.anchor_hash,
+            signature: resp.signature,
+            key_id: resp.key_id,
+        })
+    }
+
+    // --- Activity ---
+
+    /// Streams activity events for an org, or a single agent when `agent_id`
+    /// is set. `config` controls buffering when the consumer falls behind.
+    pub async fn watch_activity(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        config: StreamConfig,
+    ) -> Result<EventStream<ActivityEvent>> {
+        self.watch_activity_from(org_id, agent_id, 0, config).await
+    }
+
+    /// Like [`watch_activity`](Self::watch_activity), but first replays
+    /// buffered events after `cursor` (an [`ActivityEvent::cursor`] from an
+    /// earlier stream), giving at-least-once delivery across reconnects. A
+    /// cursor of 0 delivers live events only.
+    pub async fn watch_activity_from(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        cursor: u64,
+        config: StreamConfig,
+    ) -> Result<EventStream<ActivityEvent>> {
+        let stream = self
+            .call(
+                "WatchActivity",
+                WatchActivityRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.unwrap_or("").to_string(),
+                    after_cursor: cursor,
+                },
+                |mut c, r| async move { c.watch_activity(r).await },
+            )
+            .await?;
+        Ok(EventStream::spawn(stream, config, activity_from_proto))
+    }
+
+    /// Streams policy, budget, and agent changes in an org as admins make
+    /// them, for invalidating local caches. Equivalent to
+    /// [`watch_changes_from`](Self::watch_changes_from) with cursor 0.
+    pub async fn watch_changes(
+        &mut self,
+        org_id: &str,
+        config: StreamConfig,
+    ) -> Result<EventStream<ChangeEvent>> {
+        self.watch_changes_from(org_id, 0, config).await
+    }
+
+    /// Like [`watch_changes`](Self::watch_changes), but first replays
+    /// buffered changes after `cursor` (a [`ChangeEvent::cursor`] from an
+    /// earlier stream). When a stream ends or fails, resubscribe from the
+    /// last cursor seen so no change is missed; if the server no longer
+    /// buffers that far back, drop all cached state for the org instead.
+    pub async fn watch_changes_from(
+        &mut self,
+        org_id: &str,
+        cursor: u64,
+        config: StreamConfig,
+    ) -> Result<EventStream<ChangeEvent>> {
+        let stream = self
+            .call(
+                "WatchChanges",
+                WatchChangesRequest {
+                    org_id: org_id.to_string(),
+                    after_cursor: cursor,
+                },
+                |mut c, r| async move { c.watch_changes(r).await },
+            )
+            .await?;
+        Ok(EventStream::spawn(stream, config, change_from_proto))
+    }
+
+    /// Streams a random sample of an org's policy decisions as they are made,
+    /// picked server-side with probability `rate` (e.g. 0.01 for 1%), for
+    /// analytics that don't need every event.
+    pub async fn sample_policy_decisions(
+        &mut self,
+        org_id: &str,
+        rate: f64,
+        config: StreamConfig,
+    ) -> Result<EventStream<PolicyDecisionEvent>> {
+        let stream = self
+            .call(
+                "SamplePolicyDecisions",
+                SamplePolicyDecisionsRequest {
+                    org_id: org_id.to_string(),
+                    rate,
+                },
+                |mut c, r| async move { c.sample_policy_decisions(r).await },
+            )
+            .await?;
+        Ok(EventStream::spawn(
+            stream,
+            config,
+            |e: PolicyDecisionEventProto| PolicyDecisionEvent {
+                org_id: e.org_id,
+                agent_id: e.agent_id,
+                tool_name: e.tool_name,
+                decision: decision_from_proto(e.decision.unwrap_or_default()),
+                timestamp: time(e.timestamp),
+            },
+        ))
+    }
+
+    /// Denial rate, budget exhaustions and errors for an agent, computed by
+    /// the server from its recent activity history.
+    pub async fn get_agent_health(&mut self, org_id: &str, agent_id: &str) -> Result<AgentHealth> {
+        let resp = self
+            .call(
+                "GetAgentHealth",
+                GetAgentHealthRequest {
+                    org_id: org_id.to_string(),
+                    agent_id: agent_id.to_string(),
+                },
+                |mut c, r| async move { c.get_agent_health(r).await },
+            )
+            .await?;
+        Ok(AgentHealth {
+            deny_rate: resp.deny_rate,
+            exhaustion_count: resp.exhaustion_count,
+            error_count: resp.error_count,
+            last_error: opt(resp.last_error),
+        })
+    }
+
+    // --- Server ---
+
+    /// Whether the control plane is ready to serve, using the standard gRPC
+    /// health service on the active endpoint. Returns `Ok(false)` when the
+    /// server is reachable but reports itself not serving, and
+    /// [`SdkError::Transport`] when it cannot be reached. Servers without the
+    /// health service count as healthy once they answer. Carries the
+    /// client's headers and credentials like any other call.
+    pub async fn health_check(&mut self) -> Result<bool> {
+        let (endpoint, channel) = match &self.failover {
+            Some(f) => (Some(f.endpoint()), f.channel()),
+            None => (self.endpoint.clone(), self.channel.clone()),
+        };
+        let (metadata, _, ()) = self.prepare((), self.timeout)?.into_parts();
+        match health::check(channel, metadata).await {
+            Ok(serving) => Ok(serving),
+            Err(status) if status.code() == tonic::Code::Unimplemented => Ok(true),
+            Err(status) if status.code() == tonic::Code::Unavailable => {
+                // Dial directly to surface the underlying connection error;
+                // if that succeeds the server is up but refusing work.
+                let Some(mut endpoint) = endpoint else {
+                    return Err(SdkError::from_status(status));
+                };
+                if let Some(timeout) = self.timeout {
+                    endpoint = endpoint.connect_timeout(timeout);
+                }
+                endpoint.connect().await?;
+                Ok(false)
+            }
+            Err(status) => Err(SdkError::from_status(status)),
+        }
+    }
+
+    /// Methods the server exposes via gRPC reflection, as fully qualified
+    /// names like `agent_platform.ControlPlane.GetAgent`. Fails with
+    /// `Unimplemented` if the server does not enable reflection, and with
+    /// [`SdkError::InvalidResponse`] if it leaves a lookup unanswered.
+    pub async fn list_server_methods(&mut self) -> Result<Vec<String>> {
+        let channel = match &self.failover {
+            Some(f) => f.channel(),
+            None => self.channel.clone(),
+        };
+        let (metadata, _, ()) = self.prepare((), None)?.into_parts();
+        reflection::list_methods(channel, metadata).await
+    }
+
+    /// Pings every configured endpoint (each failover endpoint or pool member,
+    /// or the single connection) one at a time, returning each endpoint's
+    /// URI with its round-trip latency or the error it failed with. Pings
+    /// bypass failover and retries, and honor the client's timeout.
+    pub async fn ping_all(&mut self) -> Result<Vec<(String, Result<Duration>)>> {
+        let members = match (&self.failover, &self.pool) {
+            (Some(f), _) => f.members(),
+            (None, Some(p)) => p.members(),
+            (None, None) => {
+                let uri = self.endpoint.as_ref().map(|e| e.uri().to_string());
+                vec![(uri.unwrap_or_default(), self.channel.clone())]
+            }
+        };
+        let mut results = Vec::with_capacity(members.len());
+        for (endpoint, channel) in members {
+            let request = self.prepare(GetServerTimeRequest {}, self.timeout)?;
+            let started = Instant::now();
+            let mut client = ControlPlaneClient::new(channel);
+            let ping = client.get_server_time(request);
+            let outcome = match self.timeout {
+                Some(limit) => match tokio::time::timeout(limit, ping).await {
+                    Ok(result) => result.map_err(SdkError::from_status),
+                    Err(_) => Err(SdkError::Timeout(format!("ping after {limit:?}"))),
+                },
+                None => ping.await.map_err(SdkError::from_status),
+            };
+            results.push((endpoint, outcome.map(|_| started.elapsed())));
+        }
+        Ok(results)
+    }
+
+    /// Returns the control plane's current clock, for anchoring timestamps
+    /// and reset countdowns without relying on the local clock.
+    pub async fn server_time(&mut self) -> Result<SystemTime> {
+        let resp = self
+            .call(
+                "GetServerTime",
+                GetServerTimeRequest {},
+                |mut c, r| async move { c.get_server_time(r).await },
+            )
+            .await?;
+        time(resp.server_time)
+            .ok_or_else(|| SdkError::InvalidResponse("missing or invalid server_time".into()))
+    }
+}
+
+/// Whether `status` came from the connection failing rather than from the
+/// server: only client-side transport errors carry a source error.
+fn is_connection_failure(status: &tonic::Status) -> bool {
+    status.code() == tonic::Code::Unavailable && std::error::Error::source(status).is_some()
+}
+
+pub(crate) fn require(field: &str, value: &str) -> Result<()> {
+    if value.trim().is_empty() {
+        return Err(SdkError::InvalidInput(format!("{field} must not be empty")));
+    }
+    Ok(())
+}
+
+pub(crate) fn non_negative(field: &str, value: i64) -> Result<()> {
+    if value < 0 {
+        return Err(SdkError::InvalidInput(format!(
+            "{field} must not be negative, got {value}"
+        )));
+    }
+    Ok(())
+}
+
+/// Prefixes a failed check on item `i` of a batch with its position, e.g.
+/// `agents[2]: name must not be empty`.
+fn indexed(list: &str, i: usize, check: Result<()>) -> Result<()> {
+    check.map_err(|e| match e {
+        SdkError::InvalidInput(m) => SdkError::InvalidInput(format!("{list}[{i}]: {m}")),
+        e => e,
+    })
+}
+
+/// Maps the proto3 empty-string default for an unset optional field to `None`.
+fn opt(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+fn change_from_proto(e: ChangeEventProto) -> ChangeEvent {
+    let (cursor, org_id) = (e.cursor, e.org_id);
+    match e.kind.as_str() {
+        "policy_updated" => ChangeEvent::PolicyUpdated {
+            cursor,
+            org_id,
+            agent_id: opt(e.agent_id),
+            policy_id: e.policy_id,
+            policy: e.policy.map(policy_from_proto),
+        },
+        "budget_updated" => ChangeEvent::BudgetUpdated {
+            cursor,
+            org_id,
+            agent_id: opt(e.agent_id),
+            budget: e.budget.map(budget_from_proto),
+        },
+        "agent_deactivated" => ChangeEvent::AgentDeactivated {
+            cursor,
+            org_id,
+            agent_id: e.agent_id,
+            reason: opt(e.reason),
+        },
+        _ => ChangeEvent::Other {
+            cursor,
+            org_id,
+            kind: e.kind,
+        },
+    }
+}
+
+fn reservation_from_proto(r: ReservationProto) -> Reservation {
+    Reservation {
+        reservation_id: r.reservation_id,
+        org_id: r.org_id,
+        agent_id: r.agent_id,
+        tokens: r.tokens,
+        created_at: time(r.created_at),
+        expires_at: time(r.expires_at),
+    }
+}
+
+fn time(ts: Option<prost_types::Timestamp>) -> Option<SystemTime> {
+    ts.and_then(|ts| SystemTime::try_from(ts).ok())
+}
+
+/// Encodes string pairs as a `Struct`, or `None` when there are none.
+fn string_map_to_struct(map: &HashMap<String, String>) -> Option<prost_types::Struct> {
+    if map.is_empty() {
+        return None;
+    }
+    let fields = map
+        .iter()
+        .map(|(k, v)| {
+            let value = prost_types::Value {
+                kind: Some(prost_types::value::Kind::StringValue(v.clone())),
+            };
+            (k.clone(), value)
+        })
+        .collect();
+    Some(prost_types::Struct { fields })
+}
+
+fn json_to_struct(map: serde_json::Map<String, serde_json::Value>) -> prost_types::Struct {
+    prost_types::Struct {
+        fields: map
+            .into_iter()
+            .map(|(k, v)| (k, json_to_value(v)))
+            .collect(),
+    }
+}
+
+fn json_to_value(v: serde_json::Value) -> prost_types::Value {
+    use prost_types::value::Kind;
+    let kind = match v {
+        serde_json::Value::Null => Kind::NullValue(0),
+        serde_json::Value::Bool(b) => Kind::BoolValue(b),
+        serde_json::Value::Number(n) => Kind::NumberValue(n.as_f64().unwrap_or_default()),
+        serde_json::Value::String(s) => Kind::StringValue(s),
+        serde_json::Value::Array(a) => Kind::ListValue(prost_types::ListValue {
+            values: a.into_iter().map(json_to_value).collect(),
+        }),
+        serde_json::Value::Object(o) => Kind::StructValue(json_to_struct(o)),
+    };
+    prost_types::Value { kind: Some(kind) }
+}
+
+fn struct_to_string_map(s: prost_types::Struct) -> HashMap<String, String> {
+    s.fields
+        .into_iter()
+        .map(|(k, v)| match value_to_json(v) {
+            serde_json::Value::String(s) => (k, s),
+            other => (k, other.to_string()),
+        })
+        .collect()
+}
+
+fn struct_to_json(s: prost_types::Struct) -> serde_json::Map<String, serde_json::Value> {
+    s.fields
+        .into_iter()
+        .map(|(k, v)| (k, value_to_json(v)))
+        .collect()
+}
+
+fn value_to_json(v: prost_types::Value) -> serde_json::Value {
+    use prost_types::value::Kind;
+    match v.kind {
+        None | Some(Kind::NullValue(_)) => serde_json::Value::Null,
+        Some(Kind::BoolValue(b)) => b.into(),
+        Some(Kind::NumberValue(n)) => n.into(),
+        Some(Kind::StringValue(s)) => s.into(),
+        Some(Kind::ListValue(l)) => l.values.into_iter().map(value_to_json).collect(),
+        Some(Kind::StructValue(s)) => struct_to_json(s).into(),
+    }
+}
+
+pub(crate) fn audit_from_proto(e: AuditEntryProto) -> AuditEntry {
+    AuditEntry {
+        entry_id: e.entry_id,
+        org_id: e.org_id,
+        agent_id: e.agent_id,
+        delegated_user_id: opt(e.delegated_user_id),
+        execution_id: opt(e.execution_id),
+        action: e.action,
+        tool_name: opt(e.tool_name),
+        parameters: e.parameters.map(struct_to_json).unwrap_or_default(),
+        result: e.result,
+        reason: opt(e.reason),
+        latency_ms: e.latency_ms,
+        tokens_used: e.tokens_used,
+        timestamp: time(e.timestamp),
+        cursor: e.cursor,
+    }
+}
+
+fn activity_from_proto(e: ActivityEventProto) -> ActivityEvent {
+    ActivityEvent {
+        event_id: e.event_id,
+        org_id: e.org_id,
+        agent_id: e.agent_id,
+        action: e.action,
+        tool_name: opt(e.tool_name),
+        result: e.result,
+        reason: opt(e.reason),
+        tokens_used: e.tokens_used,
+        timestamp: time(e.timestamp),
+        cursor: e.cursor,
+    }
+}
+
+/// Maps a raw status error to its specific [`SdkError`] variant.
+fn classify(e: SdkError) -> SdkError {
+    match e {
+        SdkError::Status(status) => SdkError::from_status(*status),
+        e => e,
+    }
+}
+
+fn usage_request(org_id: &str, agent_id: &str, record: UsageRecord) -> ReportUsageRequest {
+    ReportUsageRequest {
+        org_id: org_id.to_string(),
+        agent_id: agent_id.to_string(),
+        execution_id: record.execution_id,
+        tokens_used: record.tokens_used,
+        tool_invocations: record.tool_invocations,
+        execution_duration_ms: record.duration_ms,
+        tool_name: record.tool_name.unwrap_or_default(),
+        metadata: record.metadata,
+        reservation_id: record.reservation_id.unwrap_or_default(),
+        model: record.model.unwrap_or_default(),
+    }
+}
+
+/// `authorization` header value for `token`, hidden from debug output.
+fn bearer(token: &str) -> Result<MetadataValue<Ascii>> {
+    let mut value: MetadataValue<Ascii> = format!("Bearer {token}")
+        .parse()
+        .map_err(|_| SdkError::Config("auth token is not a valid header value".into()))?;
+    value.set_sensitive(true);
+    Ok(value)
+}
+
+fn metadata_pair(key: &str, value: &str) -> Result<(MetadataKey<Ascii>, MetadataValue<Ascii>)> {
+    let parsed_key = MetadataKey::from_bytes(key.to_ascii_lowercase().as_bytes())
+        .map_err(|_| SdkError::Config(format!("invalid metadata key {key:?}")))?;
+    let parsed_value = value
+        .parse()
+        .map_err(|_| SdkError::Config(format!("invalid value for metadata key {key:?}")))?;
+    Ok((parsed_key, parsed_value))
+}
+
+/// Mask selecting `fields`, or `None` (all fields) when empty.
+fn field_mask(fields: &[&str]) -> Option<prost_types::FieldMask> {
+    (!fields.is_empty()).then(|| prost_types::FieldMask {
+        paths: fields.iter().map(|f| f.to_string()).collect(),
+    })
+}
+
+fn org_from_proto(o: OrganizationProto) -> Org {
+    Org {
+        org_id: o.org_id,
+        name: o.name,
+        metadata: o.metadata.map(struct_to_string_map).unwrap_or_default(),
+        deleted: o.deleted,
+        deleted_at: time(o.deleted_at),
+    }
+}
+
+fn repeated_page_token(method: &str, token: &str) -> SdkError {
+    SdkError::InvalidResponse(format!("{method} repeated page token {token:?}"))
+}
+
+fn agent_from_proto(a: AgentIdentityProto) -> Agent {
+    Agent {
+        agent_id: a.agent_id,
+        org_id: a.org_id,
+        name: a.name,
+        role: a.role.into(),
+        active: a.active,
+        delegated_user_id: opt(a.delegated_user_id),
+        delegate_is_agent: a.delegate_is_agent,
+        token_claims: a.token_claims.map(struct_to_string_map).unwrap_or_default(),
+        deactivation_reason: opt(a.deactivation_reason),
+        deactivated_at: time(a.deactivated_at),
+        deleted: a.deleted,
+        deleted_at: time(a.deleted_at),
+    }
+}
+
+fn registered_from_proto(mut a: AgentIdentityProto) -> RegisteredAgent {
+    let initial_credential = a.initial_credential.take().map(|c| AgentCredential {
+        credential_id: c.credential_id,
+        token: c.token,
+        expires_at: time(c.expires_at),
+    });
+    RegisteredAgent {
+        agent: agent_from_proto(a),
+        initial_credential,
+    }
+}
+
+fn decision_from_proto(d: PolicyDecisionProto) -> PolicyDecision {
+    PolicyDecision {
+        allowed: d.allowed,
+        reason: d.reason,
+        policy_id: opt(d.matched_policy_id),
+        warnings: d.warnings,
+        shadow_would_deny: d.shadow_would_deny,
+        cache_ttl: d.cache_ttl.and_then(|ttl| Duration::try_from(ttl).ok()),
+        matched_tool: opt(d.matched_tool),
+        effect: opt(d.effect).map(PolicyEffect::from),
+        applied_constraints: d.applied_constraints.map(struct_to_json),
+    }
+}
+
+fn budget_from_proto(b: BudgetProto) -> BudgetInfo {
+    BudgetInfo {
+        budget_id: b.budget_id,
+        token_limit: b.token_limit,
+        tokens_used: b.tokens_used,
+        tokens_remaining: b.tokens_remaining,
+        tool_invocations: b.tool_invocations,
+        reset_period_days: b.reset_period_days,
+        warning_threshold_percent: u8::try_from(b.warning_threshold_percent)
+            .ok()
+            .filter(|p| *p > 0),
+    }
+}
+
+fn usage_from_proto(u: UsageSummaryProto) -> UsageSummary {
+    UsageSummary {
+        total_tokens: u.total_tokens,
+        total_tool_invocations: u.total_tool_invocations,
+        total_duration_ms: u.total_execution_duration_ms,
+        report_count: u.report_count,
+    }
+}
+
+fn tool_to_proto(t: &ToolPermission) -> ToolPermissionProto {
+    ToolPermissionProto {
+        tool_name: t.tool_name.clone(),
+        effect: t.effect.to_string(),
+        parameters_constraint: t.parameters_constraint.clone().map(json_to_struct),
+    }
+}
+
+fn tool_from_proto(t: ToolPermissionProto) -> ToolPermission {
+    ToolPermission {
+        tool_name: t.tool_name,
+        effect: t.effect.into(),
+        parameters_constraint: t.parameters_constraint.map(struct_to_json),
+    }
+}
+
+fn usage_report_from_proto(r: UsageReportProto) -> UsageReport {
+    UsageReport {
+        org_id: r.org_id,
+        agent_id: r.agent_id,
+        execution_id: r.execution_id,
+        tokens_used: r.tokens_used,
+        tool_invocations: r.tool_invocations,
+        duration_ms: r.execution_duration_ms,
+        tool_name: opt(r.tool_name),
+        metadata: r.metadata,
+        reported_at: time(r.reported_at),
+        model: opt(r.model),
+    }
+}
+
+fn policy_from_proto(p: PolicyProto) -> Policy {
+    Policy {
+        policy_id: p.policy_id,
+        org_id: p.org_id,
+        agent_id: opt(p.agent_id),
+        tools: p.tools.into_iter().map(tool_from_proto).collect(),
+        token_limit: p.token_limit,
+        execution_timeout_seconds: p.execution_timeout_seconds,
+        mode: PolicyMode::from_wire(&p.mode),
+    }
+}
+
+fn effective_policy_from_proto(p: EffectivePolicyProto) -> EffectivePolicy {
+    EffectivePolicy {
+        agent_id: p.agent_id,
+        tools: p.tools.into_iter().map(tool_from_proto).collect(),
+        token_limit: p.token_limit,
+        execution_timeout_seconds: p.execution_timeout_seconds,
+        org_policy_id: opt(p.org_policy_id),
+        agent_policy_id: opt(p.agent_policy_id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::Constant;
+    use crate::test_server::{fail, reply, TestServer};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tonic::Status;
+
+    fn agent(active: bool) -> AgentIdentityProto {
+        AgentIdentityProto {
+            agent_id: "agent-1".into(),
+            org_id: "org-1".into(),
+            name: "bot".into(),
+            role: "executor".into(),
+            active,
+            ..Default::default()
+        }
+    }
+
+    async fn org_server(agents: Vec<AgentIdentityProto>) -> TestServer {
+        TestServer::start(move |call| match call.method() {
+            "ListAgents" => reply(&ListAgentsResponse {
+                agents: agents.clone(),
+                next_page_token: String::new(),
+            }),
+            "DeleteOrganization" => reply(&DeleteOrgResponse {
+                success: true,
+                impact: None,
+            }),
+            _ => fail(Status::unimplemented(call.path.clone())),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn delete_org_refuses_while_agents_are_active() {
+        let server = org_server(vec![agent(true)]).await;
+        let err = server.client().await.delete_org("org-1").await.unwrap_err();
+        assert!(matches!(err, SdkError::OrgNotEmpty(_)), "{err:?}");
+        assert_eq!(server.methods(), ["ListAgents"]);
+    }
+
+    #[tokio::test]
+    async fn delete_org_deletes_org_without_active_agents() {
+        let server = org_server(vec![agent(false)]).await;
+        assert!(server.client().await.delete_org("org-1").await.unwrap());
+        assert_eq!(server.methods(), ["ListAgents", "DeleteOrganization"]);
+        let req: DeleteOrgRequest = server.calls()[1].request();
+        assert!(!req.cascade);
+    }
+
+    #[tokio::test]
+    async fn cascading_delete_skips_the_agent_check() {
+        let server = org_server(vec![agent(true)]).await;
+        let mut client = server.client().await;
+        assert!(client.delete_org_with_options("org-1", true).await.unwrap());
+        assert_eq!(server.methods(), ["DeleteOrganization"]);
+        let req: DeleteOrgRequest = server.calls()[0].request();
+        assert!(req.cascade);
+    }
+
+    /// Fails the first `failures` calls with `Unavailable`, then reports
+    /// usage successfully.
+    async fn flaky_usage_server(failures: usize) -> TestServer {
+        let seen = AtomicUsize::new(0);
+        TestServer::start(move |_| {
+            if seen.fetch_add(1, Ordering::SeqCst) < failures {
+                return fail(Status::unavailable("try again"));
+            }
+            reply(&ReportUsageResponse {
+                success: true,
+                tokens_remaining: 700,
+            })
+        })
+        .await
+    }
+
+    fn retrying(client: AgentPlatformClient) -> AgentPlatformClient {
+        client.retry_policy(RetryPolicy {
+            max_attempts: 3,
+            backoff: Arc::new(Constant(Duration::ZERO)),
+            retry_mutations: true,
+        })
+    }
+
+    #[tokio::test]
+    async fn retries_resend_one_generated_idempotency_key() {
+        let server = flaky_usage_server(2).await;
+        let mut client = retrying(server.client().await);
+        let remaining = client
+            .report_usage("org-1", "agent-1", "exec-1", 300, 1, 90)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 700);
+
+        let keys: Vec<Vec<String>> = server
+            .calls()
+            .iter()
+            .map(|c| c.headers(IDEMPOTENCY_KEY_HEADER))
+            .collect();
+        assert_eq!(keys.len(), 3);
+        for sent in &keys {
+            assert_eq!(sent, &keys[0], "every attempt sends the same key once");
+        }
+        let key = &keys[0][0];
+        assert_eq!(key.len(), 36, "{key}");
+        assert_eq!(&key[14..15], "4", "{key}");
+    }
+
+    #[tokio::test]
+    async fn scoped_keys_follow_the_request_not_its_position() {
+        let server = flaky_usage_server(0).await;
+        let mut client = server.client().await;
+        let job = |mut client: AgentPlatformClient, first: &'static str| async move {
+            idempotency::with_key("job-42", async {
+                client
+                    .report_usage("org-1", "agent-1", first, 1, 1, 1)
+                    .await?;
+                client
+                    .report_usage("org-1", "agent-1", "exec-2", 1, 1, 1)
+                    .await
+            })
+            .await
+            .unwrap();
+        };
+        job(client.clone(), "exec-1").await;
+        // Re-running with an extra write up front leaves the existing keys.
+        idempotency::with_key("job-42", async {
+            client
+                .report_usage("org-1", "agent-1", "exec-0", 1, 1, 1)
+                .await
+                .unwrap();
+        })
+        .await;
+        job(client, "exec-1").await;
+
+        let keys: Vec<String> = server
+            .calls()
+            .iter()
+            .map(|c| c.headers(IDEMPOTENCY_KEY_HEADER).concat())
+            .collect();
+        assert!(keys[0].starts_with("job-42/ReportUsage/"), "{}", keys[0]);
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[2], keys[0]);
+        assert_eq!(keys[3..], keys[..2]);
+    }
+
+    async fn usage_stream_server() -> TestServer {
+        TestServer::start(|call| {
+            let total: i64 = call
+                .messages
+                .iter()
+                .map(|m| {
+                    ReportUsageRequest::decode(m.as_slice())
+                        .unwrap()
+                        .tokens_used
+                })
+                .sum();
+            reply(&ReportUsageResponse {
+                success: true,
+                tokens_remaining: 1_000_000 - total,
+            })
+        })
+        .await
+    }
+
+    fn record(i: i64) -> UsageRecord {
+        UsageRecord {
+            execution_id: format!("exec-{i}"),
+            tokens_used: i,
+            tool_invocations: 1,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn usage_stream_reports_every_event() {
+        let server = usage_stream_server().await;
+        let records = tokio_stream::iter((1..=1000).map(record));
+        let remaining = server
+            .client()
+            .await
+            .report_usage_stream("org-1", "agent-1", records)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 1_000_000 - 500_500);
+        let calls = server.calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].messages.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn usage_stream_outlives_the_call_timeout() {
+        let server = usage_stream_server().await;
+        let mut client = server
+            .client()
+            .await
+            .with_timeout(Duration::from_millis(50));
+        let records = tokio_stream::iter((1..=3).map(record)).throttle(Duration::from_millis(40));
+        let remaining = client
+            .report_usage_stream("org-1", "agent-1", records)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 1_000_000 - 6);
+    }
+
+    fn reflection_reply(
+        message: reflection::proto::server_reflection_response::MessageResponse,
+    ) -> Vec<u8> {
+        reflection::proto::ServerReflectionResponse {
+            message_response: Some(message),
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    fn descriptor(package: &str, service: &str, methods: &[&str]) -> Vec<u8> {
+        use prost_types::{FileDescriptorProto, MethodDescriptorProto, ServiceDescriptorProto};
+        FileDescriptorProto {
+            package: Some(package.into()),
+            service: vec![ServiceDescriptorProto {
+                name: Some(service.into()),
+                method: methods
+                    .iter()
+                    .map(|m| MethodDescriptorProto {
+                        name: Some(m.to_string()),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+        .encode_to_vec()
+    }
+
+    /// Reflection server listing two services that answers only the first
+    /// `answered` file lookups.
+    async fn reflection_server(answered: usize) -> TestServer {
+        use reflection::proto::server_reflection_request::MessageRequest;
+        use reflection::proto::server_reflection_response::MessageResponse;
+        use reflection::proto::*;
+        TestServer::start(move |call| {
+            let first = ServerReflectionRequest::decode(call.messages[0].as_slice()).unwrap();
+            if let Some(MessageRequest::ListServices(_)) = first.message_request {
+                let service = ["agent_platform.ControlPlane", "grpc.health.v1.Health"]
+                    .map(|name| ServiceResponse { name: name.into() })
+                    .to_vec();
+                return Ok(vec![reflection_reply(
+                    MessageResponse::ListServicesResponse(ListServiceResponse { service }),
+                )]);
+            }
+            let files = [
+                descriptor(
+                    "agent_platform",
+                    "ControlPlane",
+                    &["GetAgent", "ListAgents"],
+                ),
+                descriptor("grpc.health.v1", "Health", &["Check"]),
+            ];
+            Ok(files[..answered]
+                .iter()
+                .map(|file| {
+                    reflection_reply(MessageResponse::FileDescriptorResponse(
+                        FileDescriptorResponse {
+                            file_descriptor_proto: vec![file.clone()],
+                        },
+                    ))
+                })
+                .collect())
+        })
+        .await
+    }
+
+    async fn with_headers(server: &TestServer) -> AgentPlatformClient {
+        server
+            .client()
+            .await
+            .auth_token("t0ken")
+            .unwrap()
+            .instance_id("worker-7")
+            .unwrap()
+    }
+
+    fn assert_client_headers(call: &crate::test_server::Call) {
+        assert_eq!(
+            call.headers("authorization"),
+            ["Bearer t0ken"],
+            "{}",
+            call.path
+        );
+        assert_eq!(
+            call.headers(INSTANCE_ID_HEADER),
+            ["worker-7"],
+            "{}",
+            call.path
+        );
+    }
+
+    #[tokio::test]
+    async fn health_check_sends_client_headers() {
+        let server = TestServer::start(|_| {
+            reply(&health::proto::HealthCheckResponse {
+                status: health::proto::health_check_response::ServingStatus::Serving.into(),
+            })
+        })
+        .await;
+        let mut client = with_headers(&server).await;
+        assert!(client.health_check().await.unwrap());
+        let calls = server.calls();
+        assert_eq!(calls[0].path, "/grpc.health.v1.Health/Check");
+        assert_client_headers(&calls[0]);
+    }
+
+    #[tokio::test]
+    async fn list_server_methods_sends_client_headers() {
+        let server = reflection_server(2).await;
+        let mut client = with_headers(&server).await;
+        let methods = client.list_server_methods().await.unwrap();
+        assert_eq!(
+            methods,
+            [
+                "agent_platform.ControlPlane.GetAgent",
+                "agent_platform.ControlPlane.ListAgents",
+                "grpc.health.v1.Health.Check",
+            ]
+        );
+        let calls = server.calls();
+        assert_eq!(calls.len(), 2);
+        calls.iter().for_each(assert_client_headers);
+    }
+
+    #[tokio::test]
+    async fn list_server_methods_rejects_missing_lookups() {
+        let server = reflection_server(1).await;
+        let err = server
+            .client()
+            .await
+            .list_server_methods()
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SdkError::InvalidResponse(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn past_deadline_fails_without_dialing() {
+        // Nothing listens on port 1; a dial would fail with a transport error.
+        let channel = Endpoint::from_static("http://127.0.0.1:1").connect_lazy();
+        let mut client =
+            AgentPlatformClient::from_inner(ControlPlaneClient::new(channel.clone()), channel);
+        let err = deadline::with_deadline(Instant::now(), client.get_org("org-1"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SdkError::Timeout(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn retry_backoff_stops_at_the_deadline() {
+        let server = TestServer::start(|_| fail(Status::unavailable("down"))).await;
+        let mut client = server.client().await.retry_policy(RetryPolicy {
+            max_attempts: 5,
+            backoff: Arc::new(Constant(Duration::from_secs(10))),
+            retry_mutations: false,
+        });
+        let started = Instant::now();
+        let err = deadline::within(Duration::from_millis(100), client.get_org("org-1"))
+            .await
+            .unwrap_err();
+        assert!(
+            started.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            started.elapsed()
+        );
+        assert!(matches!(err, SdkError::Timeout(_)), "{err:?}");
+        assert_eq!(server.methods(), ["GetOrganization"]);
+    }
+
+    #[tokio::test]
+    async fn refused_consume_reports_tokens_remaining() {
+        let server = TestServer::start(|_| {
+            reply(&CheckBudgetResponse {
+                allowed: false,
+                tokens_remaining: 40,
+                reason: "insufficient budget".into(),
+                ..Default::default()
+            })
+        })
+        .await;
+        let err = server
+            .client()
+            .await
+            .consume_budget("org-1", "agent-1", 100)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                SdkError::BudgetExhausted { tokens_remaining: Some(40), reason }
+                    if reason == "insufficient budget"
+            ),
+            "{err:?}"
+        );
+    }
+
+    /// Allows `search` and `read`, denies everything else, one decision per
+    /// tool in request order.
+    async fn batch_policy_server() -> TestServer {
+        TestServer::start(|call| {
+            let req: EvaluatePoliciesRequest = call.request();
+            let decisions = req
+                .tools
+                .iter()
+                .map(|t| {
+                    let allowed = matches!(t.tool_name.as_str(), "search" | "read");
+                    PolicyDecisionProto {
+                        allowed,
+                        reason: format!("{} {}", t.tool_name, t.estimated_tokens),
+                        ..Default::default()
+                    }
+                })
+                .collect();
+            reply(&EvaluatePoliciesResponse { decisions })
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn batch_evaluation_keeps_input_order() {
+        let server = batch_policy_server().await;
+        let tools = [("search", 10), ("shell", 20), ("read", 30), ("deploy", 40)];
+        let decisions = server
+            .client()
+            .await
+            .evaluate_policies("org-1", "agent-1", &tools)
+            .await
+            .unwrap();
+        let outcomes: Vec<(bool, &str)> = decisions
+            .iter()
+            .map(|d| (d.allowed, d.reason.as_str()))
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                (true, "search 10"),
+                (false, "shell 20"),
+                (true, "read 30"),
+                (false, "deploy 40")
+            ]
+        );
+        assert_eq!(server.methods(), ["EvaluatePolicies"]);
+    }
+
+    #[tokio::test]
+    async fn empty_batch_sends_no_call() {
+        let server = batch_policy_server().await;
+        let decisions = server
+            .client()
+            .await
+            .evaluate_policies("org-1", "agent-1", &[])
+            .await
+            .unwrap();
+        assert!(decisions.is_empty());
+        assert!(server.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rate_limit_check_without_a_limit_has_no_remaining_count() {
+        let server = TestServer::start(|_| {
+            reply(&RateLimitCheckProto {
+                allowed: true,
+                remaining: -1,
+                ..Default::default()
+            })
+        })
+        .await;
+        let check = server
+            .client()
+            .await
+            .check_rate_limit("org-1", "agent-1")
+            .await
+            .unwrap();
+        assert!(check.allowed);
+        assert_eq!(check.remaining, None);
+        assert_eq!(check.reset_at, None);
+    }
+
+    /// Answers every call with an empty message, which decodes as the
+    /// response type's default.
+    async fn accepting_server() -> TestServer {
+        TestServer::start(|_| Ok(vec![Vec::new()])).await
+    }
+
+    #[tokio::test]
+    async fn reported_executions_list_back_in_order() {
+        // Keeps every report and lists them all, oldest first.
+        let reports = std::sync::Mutex::new(Vec::<UsageReportProto>::new());
+        let server = TestServer::start(move |call| {
+            let mut reports = reports.lock().unwrap();
+            match call.method() {
+                "ReportUsage" => {
+                    let req: ReportUsageRequest = call.request();
+                    reports.push(UsageReportProto {
+                        org_id: req.org_id,
+                        agent_id: req.agent_id,
+                        execution_id: req.execution_id,
+                        tokens_used: req.tokens_used,
+                        tool_invocations: req.tool_invocations,
+                        execution_duration_ms: req.execution_duration_ms,
+                        ..Default::default()
+                    });
+                    reply(&ReportUsageResponse {
+                        success: true,
+                        tokens_remaining: 0,
+                    })
+                }
+                "ListUsageReports" => reply(&ListUsageReportsResponse {
+                    reports: reports.clone(),
+                    next_page_token: String::new(),
+                }),
+                _ => fail(Status::unimplemented(call.path.clone())),
+            }
+        })
+        .await;
+        let mut client = server.client().await;
+        for (i, execution) in ["exec-1", "exec-2", "exec-3"].into_iter().enumerate() {
+            let tokens = 100 * (i as i64 + 1);
+            client
+                .report_usage("org-1", "agent-1", execution, tokens, 1, 50)
+                .await
+                .unwrap();
+        }
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let end = start + Duration::from_secs(3600);
+        let filter = ExecutionFilter {
+            window: Some(start..end),
+            tool_name: Some("search".into()),
+        };
+        let page = client
+            .list_executions("org-1", Some("agent-1"), &filter, 0, None)
+            .await
+            .unwrap();
+        let listed: Vec<(&str, i64)> = page
+            .items
+            .iter()
+            .map(|r| (r.execution_id.as_str(), r.tokens_used))
+            .collect();
+        assert_eq!(listed, [("exec-1", 100), ("exec-2", 200), ("exec-3", 300)]);
+        assert_eq!(page.next_page_token, None);
+
+        let sent = server
+            .calls()
+            .last()
+            .unwrap()
+            .request::<ListUsageReportsRequest>();
+        assert_eq!(sent.agent_id, "agent-1");
+        assert_eq!(sent.start_time, Some(start.into()));
+        assert_eq!(sent.end_time, Some(end.into()));
+        assert_eq!(sent.tool_name, "search");
+    }
+
+    #[tokio::test]
+    async fn renamed_orgs_read_back_with_the_new_name() {
+        let orgs = std::sync::Mutex::new(Vec::<OrganizationProto>::new());
+        let server = TestServer::start(move |call| {
+            let mut orgs = orgs.lock().unwrap();
+            match call.method() {
+                "CreateOrganization" => {
+                    let org = OrganizationProto {
+                        org_id: format!("org-{}", orgs.len() + 1),
+                        name: call.request::<CreateOrgRequest>().name,
+                        ..Default::default()
+                    };
+                    orgs.push(org.clone());
+                    reply(&org)
+                }
+                "UpdateOrganization" => {
+                    let req: UpdateOrgRequest = call.request();
+                    let Some(org) = orgs.iter_mut().find(|o| o.org_id == req.org_id) else {
+                        return fail(Status::not_found(format!("org {}", req.org_id)));
+                    };
+                    org.name = req.name;
+                    reply(org)
+                }
+                "GetOrganization" => {
+                    let org_id = call.request::<GetOrgRequest>().org_id;
+                    match orgs.iter().find(|o| o.org_id == org_id) {
+                        Some(org) => reply(org),
+                        None => fail(Status::not_found(format!("org {org_id}"))),
+                    }
+                }
+                _ => fail(Status::unimplemented(call.path.clone())),
+            }
+        })
+        .await;
+        let mut client = server.client().await;
+
+        let org = client.create_org("acme").await.unwrap();
+        let renamed = client.update_org(&org.org_id, "acme-eu").await.unwrap();
+        assert_eq!(
+            (renamed.org_id.as_str(), renamed.name.as_str()),
+            (org.org_id.as_str(), "acme-eu")
+        );
+        assert_eq!(client.get_org(&org.org_id).await.unwrap().name, "acme-eu");
+        let update = server.calls()[1].request::<UpdateOrgRequest>();
+        assert_eq!(update.update_mask.unwrap().paths, ["name"]);
+        assert!(matches!(
+            client.update_org("org-9", "nobody").await,
+            Err(SdkError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn org_batch_result_count_must_match() {
+        // Answers every batch with a single result.
+        let server = TestServer::start(|_| {
+            reply(&CreateOrgsResponse {
+                results: vec![OrgResultProto {
+                    organization: Some(OrganizationProto::default()),
+                    error: String::new(),
+                }],
+            })
+        })
+        .await;
+        let mut client = server.client().await;
+        let spec = |name: &str| OrgSpec {
+            name: name.to_string(),
+            metadata: HashMap::new(),
+        };
+        assert_eq!(
+            client
+                .create_orgs(vec![spec("a")])
+                .await
+                .unwrap()
+                .results
+                .len(),
+            1
+        );
+        let err = client
+            .create_orgs(vec![spec("a"), spec("b")])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, SdkError::InvalidResponse(m) if m == "expected 2 org results, got 1"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn budget_batch_result_count_must_match() {
+        // Answers every batch with a single result.
+        let server = TestServer::start(|_| {
+            reply(&SetBudgetsResponse {
+                results: vec![BudgetResultProto {
+                    budget: Some(BudgetProto::default()),
+                    error: String::new(),
+                }],
+            })
+        })
+        .await;
+        let mut client = server.client().await;
+        let budget = |agent: &str| (agent.to_string(), 1_000, 30);
+        let set = client.set_budgets("org-1", vec![budget("agent-1")]).await;
+        assert_eq!(set.unwrap().results.len(), 1);
+        let err = client
+            .set_budgets("org-1", vec![budget("agent-1"), budget("agent-2")])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, SdkError::InvalidResponse(m) if m == "expected 2 budget results, got 1"),
+            "{err:?}"
+        );
+    }
+
+    fn assert_invalid<T: std::fmt::Debug>(result: Result<T>, field: &str) {
+        match result {
+            Err(SdkError::InvalidInput(message)) => {
+                assert!(message.starts_with(field), "{message}")
+            }
+            other => panic!("expected InvalidInput for {field}, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_fields_fail_before_sending() {
+        let server = accepting_server().await;
+        let mut client = server.client().await;
+        assert_invalid(client.create_org("").await, "name");
+        assert_invalid(client.create_org("  ").await, "name");
+        assert_invalid(
+            client.register_agent("", "bot", "executor", None).await,
+            "org_id",
+        );
+        assert_invalid(
+            client.register_agent("org-1", "", "executor", None).await,
+            "name",
+        );
+        assert_invalid(
+            client.register_agent("org-1", "bot", "", None).await,
+            "role",
+        );
+        assert_invalid(client.set_policy("", None, &[], &[], 0, 0).await, "org_id");
+        assert_invalid(
+            client.set_policy("org-1", None, &[], &[], -1, 0).await,
+            "token_limit",
+        );
+        assert_invalid(
+            client.set_policy("org-1", None, &[], &[], 0, -1).await,
+            "timeout_seconds",
+        );
+        assert_invalid(client.set_budget("", None, 0, 0).await, "org_id");
+        assert_invalid(client.set_budget("org-1", None, -1, 0).await, "token_limit");
+        assert_invalid(
+            client.set_budget("org-1", None, 0, -1).await,
+            "reset_period_days",
+        );
+        assert!(server.calls().is_empty(), "{:?}", server.methods());
+    }
+
+    #[tokio::test]
+    async fn batch_and_default_inputs_are_checked_like_single_ones() {
+        let server = accepting_server().await;
+        let mut client = server.client().await;
+        let no_claims = HashMap::new();
+        assert_invalid(
+            client
+                .create_org_with_admin("", "admin", "admin", &no_claims)
+                .await,
+            "name",
+        );
+        assert_invalid(
+            client
+                .create_org_with_admin("acme", " ", "admin", &no_claims)
+                .await,
+            "admin_name",
+        );
+        assert_invalid(
+            client
+                .create_org_with_admin("acme", "admin", "", &no_claims)
+                .await,
+            "admin_role",
+        );
+        assert_invalid(
+            client.register_agents("", &[agent_spec("a")]).await,
+            "org_id",
+        );
+        assert_invalid(
+            client
+                .register_agents("org-1", &[agent_spec("a"), agent_spec("")])
+                .await,
+            "agents[1]: name",
+        );
+        assert_invalid(
+            client.set_default_agent_policy("org-1", &[], -1, 0).await,
+            "token_limit",
+        );
+        assert_invalid(
+            client.set_default_agent_policy("org-1", &[], 0, -1).await,
+            "timeout_seconds",
+        );
+        let budgets = vec![
+            ("agent-1".to_string(), 1_000, 30),
+            ("agent-2".to_string(), -5, 30),
+        ];
+        assert_invalid(
+            client.set_budgets("org-1", budgets).await,
+            "budgets[1]: token_limit",
+        );
+        assert_invalid(
+            client
+                .set_budgets("org-1", vec![("agent-1".to_string(), 0, -1)])
+                .await,
+            "budgets[0]: reset_period_days",
+        );
+        assert!(server.calls().is_empty(), "{:?}", server.methods());
+    }
+
+    #[tokio::test]
+    async fn zero_limits_select_the_server_default() {
+        let server = accepting_server().await;
+        let mut client = server.client().await;
+        client
+            .set_policy("org-1", None, &["search"], &[], 0, 0)
+            .await
+            .unwrap();
+        client.set_budget("org-1", None, 0, 0).await.unwrap();
+        assert_eq!(server.methods(), ["SetPolicy", "SetBudget"]);
+        let policy: SetPolicyRequest = server.calls()[0].request();
+        assert_eq!(
+            (policy.token_limit, policy.execution_timeout_seconds),
+            (0, 0)
+        );
+        let budget: SetBudgetRequest = server.calls()[1].request();
+        assert_eq!((budget.token_limit, budget.reset_period_days), (0, 0));
+    }
+
+    /// Keeps the constraint of the last `SetPolicy` and allows an
+    /// `EvaluatePolicy` whose context `host` matches its `*.suffix` pattern.
+    async fn constraint_server() -> TestServer {
+        let stored = std::sync::Mutex::new(None::<prost_types::Struct>);
+        TestServer::start(move |call| match call.method() {
+            "SetPolicy" => {
+                let req: SetPolicyRequest = call.request();
+                *stored.lock().unwrap() = req.tools[0].parameters_constraint.clone();
+                reply(&PolicyProto::default())
+            }
+            "EvaluatePolicy" => {
+                let req: EvaluatePolicyRequest = call.request();
+                let constraint = stored.lock().unwrap().clone().unwrap();
+                let host = |s: &prost_types::Struct| {
+                    s.fields.get("host").and_then(|v| match &v.kind {
+                        Some(prost_types::value::Kind::StringValue(h)) => Some(h.clone()),
+                        _ => None,
+                    })
+                };
+                let pattern = host(&constraint).unwrap();
+                let suffix = pattern.trim_start_matches('*');
+                let allowed = req
+                    .context
+                    .as_ref()
+                    .and_then(host)
+                    .is_some_and(|h| h.ends_with(suffix));
+                reply(&PolicyDecisionProto {
+                    allowed,
+                    matched_tool: "http".into(),
+                    effect: if allowed { "allow" } else { "" }.into(),
+                    applied_constraints: allowed.then_some(constraint),
+                    ..Default::default()
+                })
+            }
+            _ => fail(Status::unimplemented(call.path.clone())),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn constraints_reach_the_server_and_decide_with_context() {
+        let server = constraint_server().await;
+        let mut client = server.client().await.policy_cache(Duration::from_secs(60));
+        let constraint = serde_json::json!({ "host": "*.internal" });
+        let constraint = constraint.as_object().unwrap().clone();
+        client
+            .set_policy_from(PolicyBuilder::new("org-1").allow_with("http", constraint.clone()))
+            .await
+            .unwrap();
+        let sent: SetPolicyRequest = server.calls()[0].request();
+        let sent = sent.tools[0].parameters_constraint.clone().unwrap();
+        assert_eq!(struct_to_json(sent), constraint);
+
+        let evaluate = |host: &str| {
+            let context = serde_json::json!({ "host": host });
+            let context = context.as_object().unwrap().clone();
+            let mut client = client.clone();
+            async move {
+                client
+                    .evaluate_policy_with_context("org-1", "agent-1", "http", 10, context)
+                    .await
+                    .unwrap()
+            }
+        };
+        let inside = evaluate("api.internal").await;
+        assert!(inside.allowed);
+        assert_eq!(inside.applied_constraints, Some(constraint));
+        let outside = evaluate("example.com").await;
+        assert!(!outside.allowed);
+        assert_eq!(outside.applied_constraints, None);
+        assert_eq!(
+            server.methods(),
+            ["SetPolicy", "EvaluatePolicy", "EvaluatePolicy"]
+        );
+    }
+
+    /// A minimal agent registry: agents registered through it are fetched
+    /// and listed back as stored, can be deactivated and reactivated, and
+    /// are updated by field mask. A delegate that is a registered agent is
+    /// marked as one. Unknown ids are NotFound.
+    async fn registry_server() -> TestServer {
+        let agents = std::sync::Mutex::new(Vec::<AgentIdentityProto>::new());
+        TestServer::start(move |call| {
+            let mut agents = agents.lock().unwrap();
+            match call.method() {
+                "RegisterAgent" => {
+                    let req: RegisterAgentRequest = call.request();
+                    let agent = AgentIdentityProto {
+                        agent_id: format!("agent-{}", agents.len() + 1),
+                        org_id: req.org_id,
+                        name: req.name,
+                        role: req.role,
+                        delegate_is_agent: agents
+                            .iter()
+                            .any(|a| a.agent_id == req.delegated_user_id),
+                        delegated_user_id: req.delegated_user_id,
+                        active: true,
+                        ..Default::default()
+                    };
+                    agents.push(agent.clone());
+                    reply(&agent)
+                }
+                "GetAgent" => {
+                    let agent_id = call.request::<GetAgentRequest>().agent_id;
+                    match agents.iter().find(|a| a.agent_id == agent_id) {
+                        Some(agent) => reply(agent),
+                        None => fail(Status::not_found(format!("agent {agent_id}"))),
+                    }
+                }
+                "ListAgents" => reply(&ListAgentsResponse {
+                    agents: agents.clone(),
+                    next_page_token: String::new(),
+                }),
+                "DeactivateAgent" | "ReactivateAgent" => {
+                    let (agent_id, active) = match call.method() {
+                        "DeactivateAgent" => {
+                            (call.request::<DeactivateAgentRequest>().agent_id, false)
+                        }
+                        _ => (call.request::<ReactivateAgentRequest>().agent_id, true),
+                    };
+                    let Some(agent) = agents.iter_mut().find(|a| a.agent_id == agent_id) else {
+                        return fail(Status::not_found(format!("agent {agent_id}")));
+                    };
+                    agent.active = active;
+                    // Both responses are a single `success` field.
+                    reply(&ReactivateAgentResponse { success: true })
+                }
+                "UpdateAgent" => {
+                    let req: UpdateAgentRequest = call.request();
+                    let to_agent = agents.iter().any(|a| a.agent_id == req.delegated_user_id);
+                    let Some(agent) = agents.iter_mut().find(|a| a.agent_id == req.agent_id) else {
+                        return fail(Status::not_found(format!("agent {}", req.agent_id)));
+                    };
+                    for path in req.update_mask.unwrap_or_default().paths {
+                        match path.as_str() {
+                            "name" => agent.name = req.name.clone(),
+                            "role" => agent.role = req.role.clone(),
+                            "delegated_user_id" => {
+                                agent.delegated_user_id = req.delegated_user_id.clone();
+                                agent.delegate_is_agent = to_agent;
+                            }
+                            _ => return fail(Status::invalid_argument(path)),
+                        }
+                    }
+                    reply(agent)
+                }
+                _ => fail(Status::unimplemented(call.path.clone())),
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn token_claims_round_trip_through_registration() {
+        // Stores claims as sent and echoes them back.
+        let server = TestServer::start(|call| {
+            let req: RegisterAgentRequest = call.request();
+            reply(&AgentIdentityProto {
+                agent_id: "agent-1".into(),
+                org_id: req.org_id,
+                name: req.name,
+                role: req.role,
+                token_claims: req.token_claims,
+                active: true,
+                ..Default::default()
+            })
+        })
+        .await;
+        let mut client = server.client().await;
+        let claims = HashMap::from([
+            ("scope".to_string(), "tickets:read".to_string()),
+            ("aud".to_string(), "helpdesk".to_string()),
+        ]);
+        let agent = client
+            .register_agent_with_claims("org-1", "bot", Role::Executor, None, claims.clone())
+            .await
+            .unwrap()
+            .agent;
+        assert_eq!(agent.token_claims, claims);
+
+        let sent = server.calls()[0].request::<RegisterAgentRequest>();
+        assert_eq!(struct_to_string_map(sent.token_claims.unwrap()), claims);
+
+        let mut read_only = server.client().await.read_only();
+        let err = read_only
+            .register_agent_with_claims("org-1", "bot", Role::Executor, None, claims)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, SdkError::ReadOnly(m) if m == "register_agent_with_claims"),
+            "{err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn listed_agents_keep_their_delegated_user() {
+        let server = registry_server().await;
+        let mut client = server.client().await;
+        client
+            .register_agent("org-1", "helper", Role::Executor, Some("user-7"))
+            .await
+            .unwrap();
+        client
+            .register_agent("org-1", "worker", Role::Executor, None)
+            .await
+            .unwrap();
+        let delegation: Vec<(String, Option<String>)> = client
+            .list_agents("org-1")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|a| (a.name, a.delegated_user_id))
+            .collect();
+        assert_eq!(
+            delegation,
+            [
+                ("helper".to_string(), Some("user-7".to_string())),
+                ("worker".to_string(), None)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn effective_user_follows_agent_delegates_to_the_user() {
+        let server = registry_server().await;
+        let mut client = server.client().await;
+        let mut ids = Vec::new();
+        for (name, delegate) in [
+            ("owner", Some("user-7")),
+            ("helper", Some("agent-1")),
+            ("worker", None),
+        ] {
+            let registered = client
+                .register_agent("org-1", name, Role::Executor, delegate)
+                .await
+                .unwrap();
+            ids.push(registered.agent.agent_id);
+        }
+        let [owner, helper, worker] = &ids[..] else {
+            unreachable!()
+        };
+
+        // Direct, through one agent, and not delegated at all.
+        assert_eq!(
+            client
+                .effective_user("org-1", owner)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("user-7")
+        );
+        assert_eq!(
+            client
+                .effective_user("org-1", helper)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("user-7")
+        );
+        assert_eq!(client.effective_user("org-1", worker).await.unwrap(), None);
+        assert_eq!(
+            server.methods().iter().filter(|m| *m == "GetAgent").count(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn delegation_cycles_are_reported_as_such() {
+        let server = registry_server().await;
+        let mut client = server.client().await;
+        let first = client
+            .register_agent("org-1", "first", Role::Executor, None)
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        let second = client
+            .register_agent("org-1", "second", Role::Executor, Some(&first))
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        client
+            .update_agent("org-1", &first, None, None, Some(Some(&second)))
+            .await
+            .unwrap();
+
+        let err = client.effective_user("org-1", &first).await.unwrap_err();
+        assert!(
+            matches!(&err, SdkError::DelegationCycle(chain) if *chain == [first.clone(), second.clone(), first.clone()]),
+            "{err:?}"
+        );
+        assert_eq!(
+            err.to_string(),
+            "delegation cycle: agent-1 -> agent-2 -> agent-1"
+        );
+    }
+
+    /// Records usage reports against an agent budget of 1,000 tokens, which
+    /// `ResetBudget` starts over, and sums them for `GetUsage`. Agents that
+    /// have reported nothing are unknown.
+    async fn budget_server() -> TestServer {
+        // Reports so far, and how many of them predate the last reset.
+        let usage = std::sync::Mutex::new((Vec::<ReportUsageRequest>::new(), 0));
+        TestServer::start(move |call| {
+            let mut usage = usage.lock().unwrap();
+            let (reports, reset) = &mut *usage;
+            let budget = |reports: &[ReportUsageRequest]| {
+                let used: i64 = reports.iter().map(|r| r.tokens_used).sum();
+                BudgetProto {
+                    budget_id: "budget-1".into(),
+                    token_limit: 1_000,
+                    tokens_used: used,
+                    tokens_remaining: 1_000 - used,
+                    tool_invocations: reports.iter().map(|r| r.tool_invocations).sum(),
+                    reset_period_days: 30,
+                    ..Default::default()
+                }
+            };
+            match call.method() {
+                "ReportUsage" => {
+                    reports.push(call.request());
+                    let remaining = budget(&reports[*reset..]).tokens_remaining;
+                    reply(&ReportUsageResponse {
+                        success: true,
+                        tokens_remaining: remaining,
+                    })
+                }
+                "GetBudget" => reply(&budget(&reports[*reset..])),
+                "ResetBudget" => {
+                    *reset = reports.len();
+                    reply(&budget(&[]))
+                }
+                "GetUsage" => {
+                    let req: GetUsageRequest = call.request();
+                    let matching: Vec<_> = reports
+                        .iter()
+                        .filter(|r| req.agent_id.is_empty() || r.agent_id == req.agent_id)
+                        .collect();
+                    if matching.is_empty() && !req.agent_id.is_empty() {
+                        return fail(Status::not_found(format!("agent {}", req.agent_id)));
+                    }
+                    reply(&UsageSummaryProto {
+                        org_id: req.org_id,
+                        agent_id: req.agent_id,
+                        total_tokens: matching.iter().map(|r| r.tokens_used).sum(),
+                        total_tool_invocations: matching.iter().map(|r| r.tool_invocations).sum(),
+                        total_execution_duration_ms: matching
+                            .iter()
+                            .map(|r| r.execution_duration_ms)
+                            .sum(),
+                        report_count: matching.len() as i32,
+                    })
+                }
+                _ => fail(Status::unimplemented(call.path.clone())),
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn reset_budget_restores_the_full_limit() {
+        let server = budget_server().await;
+        let mut client = server.client().await;
+        for execution in ["exec-1", "exec-2"] {
+            client
+                .report_usage("org-1", "agent-1", execution, 300, 2, 50)
+                .await
+                .unwrap();
+        }
+        let live = client.get_budget("org-1", Some("agent-1")).await.unwrap();
+        assert_eq!((live.tokens_used, live.tokens_remaining), (600, 400));
+        assert_eq!(live.tool_invocations, 4);
+
+        let reset = client.reset_budget("org-1", Some("agent-1")).await.unwrap();
+        assert_eq!(reset.tokens_used, 0);
+        assert_eq!(reset.tool_invocations, 0);
+        assert_eq!(reset.tokens_remaining, reset.token_limit);
+        let after = client.get_budget("org-1", Some("agent-1")).await.unwrap();
+        assert_eq!(after.tokens_remaining, after.token_limit);
+        let req: ResetBudgetRequest = server.calls()[3].request();
+        assert_eq!(
+            (req.org_id.as_str(), req.agent_id.as_str()),
+            ("org-1", "agent-1")
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_budget_is_not_found() {
+        let server = TestServer::start(|_| fail(Status::not_found("no budget configured"))).await;
+        let err = server
+            .client()
+            .await
+            .get_budget("org-1", None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SdkError::NotFound(_)), "{err:?}");
+        let req: GetBudgetRequest = server.calls()[0].request();
+        assert_eq!(req.agent_id, "", "None reads the org-level budget");
+    }
+
+    #[tokio::test]
+    async fn usage_summary_sums_reported_usage() {
+        let server = budget_server().await;
+        let mut client = server.client().await;
+        let reports = [
+            ("agent-1", 100, 1, 20),
+            ("agent-1", 250, 3, 40),
+            ("agent-2", 50, 1, 5),
+        ];
+        for (i, (agent, tokens, invocations, ms)) in reports.into_iter().enumerate() {
+            client
+                .report_usage(
+                    "org-1",
+                    agent,
+                    &format!("exec-{i}"),
+                    tokens,
+                    invocations,
+                    ms,
+                )
+                .await
+                .unwrap();
+        }
+
+        let agent = client
+            .get_usage_summary("org-1", Some("agent-1"), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            (
+                agent.total_tokens,
+                agent.total_tool_invocations,
+                agent.total_duration_ms,
+                agent.report_count
+            ),
+            (350, 4, 60, 2)
+        );
+        let now = SystemTime::now();
+        let day = now - Duration::from_secs(24 * 60 * 60)..now;
+        let org = client
+            .get_usage_summary("org-1", None, Some(day.clone()))
+            .await
+            .unwrap();
+        assert_eq!((org.total_tokens, org.report_count), (400, 3));
+        let req: GetUsageRequest = server.calls().last().unwrap().request();
+        assert_eq!(req.start_time, Some(day.start.into()));
+        assert_eq!(req.end_time, Some(day.end.into()));
+
+        let err = client
+            .get_usage_summary("org-1", Some("agent-9"), None)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SdkError::NotFound(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn reactivated_agent_lists_as_active() {
+        let server = registry_server().await;
+        let mut client = server.client().await;
+        let agent = client
+            .register_agent("org-1", "bot", Role::Executor, None)
+            .await
+            .unwrap()
+            .agent;
+        let active = |agents: Vec<Agent>| agents.iter().map(|a| a.active).collect::<Vec<_>>();
+
+        assert!(client
+            .deactivate_agent("org-1", &agent.agent_id, None)
+            .await
+            .unwrap());
+        assert_eq!(active(client.list_agents("org-1").await.unwrap()), [false]);
+        assert!(client
+            .reactivate_agent("org-1", &agent.agent_id)
+            .await
+            .unwrap());
+        assert_eq!(active(client.list_agents("org-1").await.unwrap()), [true]);
+        // Already active: still true, not an error.
+        assert!(client
+            .reactivate_agent("org-1", &agent.agent_id)
+            .await
+            .unwrap());
+
+        let err = client
+            .reactivate_agent("org-1", "agent-9")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SdkError::NotFound(_)), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn update_agent_distinguishes_unchanged_from_cleared_delegation() {
+        let server = registry_server().await;
+        let mut client = server.client().await;
+        let id = client
+            .register_agent("org-1", "bot", Role::Executor, Some("user-1"))
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+
+        // `None` leaves delegation alone.
+        let renamed = client
+            .update_agent("org-1", &id, Some("helper"), Some(Role::Planner), None)
+            .await
+            .unwrap();
+        assert_eq!(renamed.name, "helper");
+        assert_eq!(renamed.role, Role::Planner);
+        assert_eq!(renamed.delegated_user_id.as_deref(), Some("user-1"));
+        let mask = server
+            .calls()
+            .last()
+            .unwrap()
+            .request::<UpdateAgentRequest>();
+        assert_eq!(mask.update_mask.unwrap().paths, ["name", "role"]);
+
+        let moved = client
+            .update_agent("org-1", &id, None, None, Some(Some("user-2")))
+            .await
+            .unwrap();
+        assert_eq!(moved.delegated_user_id.as_deref(), Some("user-2"));
+        assert_eq!(moved.name, "helper");
+
+        // `Some(None)` clears it.
+        let cleared = client
+            .update_agent("org-1", &id, None, None, Some(None))
+            .await
+            .unwrap();
+        assert_eq!(cleared.delegated_user_id, None);
+        let listed = client.list_agents("org-1").await.unwrap();
+        assert_eq!(listed[0].delegated_user_id, None);
+        assert_eq!(listed[0].name, "helper");
+    }
+
+    /// Registers a batch all-or-nothing, rejecting it if any spec is named
+    /// `taken`.
+    async fn batch_registry_server() -> TestServer {
+        TestServer::start(|call| {
+            let req: RegisterAgentsRequest = call.request();
+            if let Some(i) = req.agents.iter().position(|a| a.name == "taken") {
+                return fail(Status::invalid_argument(format!(
+                    "agents[{i}]: name is already in use"
+                )));
+            }
+            let agents = req
+                .agents
+                .into_iter()
+                .enumerate()
+                .map(|(i, a)| AgentIdentityProto {
+                    agent_id: format!("agent-{}", i + 1),
+                    org_id: req.org_id.clone(),
+                    name: a.name,
+                    role: a.role,
+                    delegated_user_id: a.delegated_user_id,
+                    token_claims: a.token_claims,
+                    active: true,
+                    initial_credential: Some(AgentCredentialProto {
+                        credential_id: format!("cred-{}", i + 1),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+                .collect();
+            reply(&RegisterAgentsResponse { agents })
+        })
+        .await
+    }
+
+    fn agent_spec(name: &str) -> AgentSpec {
+        AgentSpec {
+            name: name.to_string(),
+            role: Role::Executor,
+            delegated_user_id: None,
+            claims: HashMap::from([("team".to_string(), "ops".to_string())]),
+        }
+    }
+
+    #[tokio::test]
+    async fn batch_registration_keeps_input_order() {
+        let server = batch_registry_server().await;
+        let specs = [agent_spec("a"), agent_spec("b"), agent_spec("c")];
+        let registered = server
+            .client()
+            .await
+            .register_agents("org-1", &specs)
+            .await
+            .unwrap();
+        let names: Vec<(&str, &str)> = registered
+            .iter()
+            .map(|r| (r.agent.name.as_str(), r.agent.agent_id.as_str()))
+            .collect();
+        assert_eq!(
+            names,
+            [("a", "agent-1"), ("b", "agent-2"), ("c", "agent-3")]
+        );
+        assert!(registered.iter().all(|r| r.initial_credential.is_some()));
+        assert_eq!(registered[0].agent.token_claims, specs[0].claims);
+        assert_eq!(server.methods(), ["RegisterAgents"]);
+    }
+
+    #[tokio::test]
+    async fn failed_batch_names_the_rejected_spec() {
+        let server = batch_registry_server().await;
+        let specs = [agent_spec("a"), agent_spec("taken"), agent_spec("c")];
+        let err = server
+            .client()
+            .await
+            .register_agents("org-1", &specs)
+            .await
+            .unwrap_err();
+        let SdkError::Status(status) = err else {
+            panic!("expected a status error, got {err:?}");
+        };
+        assert_eq!(status.code(), tonic::Code::InvalidArgument);
+        assert!(status.message().starts_with("agents[1]"), "{status:?}");
+    }
+
+    fn org_reply(call: &crate::test_server::Call) -> crate::test_server::Reply {
+        reply(&OrganizationProto {
+            org_id: call.request::<GetOrgRequest>().org_id,
+            name: "acme".into(),
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn consumed_budget_keeps_the_warning() {
+        let server = TestServer::start(|_| {
+            reply(&CheckBudgetResponse {
+                allowed: true,
+                tokens_remaining: 50,
+                reason: "budget_ok".into(),
+                utilization: 0.95,
+                warning: "agent budget 95% used, past its 90% warning threshold".into(),
+                ..Default::default()
+            })
+        })
+        .await;
+        let warned = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = warned.clone();
+        let mut client = server
+            .client()
+            .await
+            .on_warning(move |w| seen.lock().unwrap().push(w.to_string()));
+
+        let check = client
+            .consume_budget("org-1", "agent-1", 100)
+            .await
+            .unwrap();
+        assert_eq!(check.utilization, 0.95);
+        assert_eq!(
+            check.warning.as_deref(),
+            Some("agent budget 95% used, past its 90% warning threshold")
+        );
+        assert_eq!(*warned.lock().unwrap(), [check.warning.unwrap()]);
+    }
+
+    #[test]
+    fn empty_optional_strings_read_as_none() {
+        assert_eq!(opt(String::new()), None);
+        assert_eq!(opt("x".into()), Some("x".into()));
+
+        let agent = agent_from_proto(AgentIdentityProto::default());
+        assert_eq!(
+            (agent.delegated_user_id, agent.deactivation_reason),
+            (None, None)
+        );
+        let agent = agent_from_proto(AgentIdentityProto {
+            delegated_user_id: "user-1".into(),
+            deactivation_reason: "rotated".into(),
+            ..Default::default()
+        });
+        assert_eq!(agent.delegated_user_id.as_deref(), Some("user-1"));
+        assert_eq!(agent.deactivation_reason.as_deref(), Some("rotated"));
+
+        let decision = decision_from_proto(PolicyDecisionProto::default());
+        assert_eq!(
+            (decision.policy_id, decision.matched_tool, decision.effect),
+            (None, None, None)
+        );
+        let decision = decision_from_proto(PolicyDecisionProto {
+            matched_policy_id: "policy-1".into(),
+            matched_tool: "search".into(),
+            effect: "allow".into(),
+            ..Default::default()
+        });
+        assert_eq!(decision.policy_id.as_deref(), Some("policy-1"));
+        assert_eq!(decision.matched_tool.as_deref(), Some("search"));
+        assert_eq!(decision.effect, Some(PolicyEffect::Allow));
+
+        let policy = policy_from_proto(PolicyProto::default());
+        assert_eq!(policy.agent_id, None);
+        let policy = policy_from_proto(PolicyProto {
+            agent_id: "agent-1".into(),
+            ..Default::default()
+        });
+        assert_eq!(policy.agent_id.as_deref(), Some("agent-1"));
+
+        let report = usage_report_from_proto(UsageReportProto::default());
+        assert_eq!((report.tool_name, report.model), (None, None));
+        let report = usage_report_from_proto(UsageReportProto {
+            tool_name: "search".into(),
+            model: "small".into(),
+            ..Default::default()
+        });
+        assert_eq!(report.tool_name.as_deref(), Some("search"));
+        assert_eq!(report.model.as_deref(), Some("small"));
+
+        let entry = audit_from_proto(AuditEntryProto::default());
+        assert_eq!((entry.tool_name, entry.reason), (None, None));
+        let entry = audit_from_proto(AuditEntryProto {
+            tool_name: "search".into(),
+            reason: "allowed".into(),
+            ..Default::default()
+        });
+        assert_eq!(entry.tool_name.as_deref(), Some("search"));
+        assert_eq!(entry.reason.as_deref(), Some("allowed"));
+    }
+
+    #[tokio::test]
+    async fn empty_page_token_means_the_last_page() {
+        let server = TestServer::start(|call| {
+            let next = match call.request::<ListOrgsRequest>().page_token.as_str() {
+                "" => "page-2",
+                _ => "",
+            };
+            reply(&ListOrgsResponse {
+                organizations: vec![OrganizationProto::default()],
+                next_page_token: next.into(),
+            })
+        })
+        .await;
+        let mut client = server.client().await;
+        let first = client.list_orgs_paged(1, None).await.unwrap();
+        assert_eq!(first.next_page_token.as_deref(), Some("page-2"));
+        let last = client.list_orgs_paged(1, Some("page-2")).await.unwrap();
+        assert_eq!(last.next_page_token, None);
+    }
+
+    #[tokio::test]
+    async fn listing_all_stops_when_a_page_token_repeats() {
+        // Every page, including the one it names, points on to "page-2".
+        let server = TestServer::start(|call| match call.method() {
+            "ListOrganizations" => reply(&ListOrgsResponse {
+                organizations: vec![OrganizationProto::default()],
+                next_page_token: "page-2".into(),
+            }),
+            "ListAgents" => reply(&ListAgentsResponse {
+                agents: vec![agent(true)],
+                next_page_token: "page-2".into(),
+            }),
+            _ => fail(Status::unimplemented(call.path.clone())),
+        })
+        .await;
+        let mut client = server.client().await;
+
+        let err = client.list_all_orgs().await.unwrap_err();
+        assert!(
+            matches!(&err, SdkError::InvalidResponse(m) if m == r#"ListOrganizations repeated page token "page-2""#),
+            "{err:?}"
+        );
+        let err = client.list_all_agents("org-1").await.unwrap_err();
+        assert!(matches!(err, SdkError::InvalidResponse(_)), "{err:?}");
+        assert_eq!(
+            server.methods(),
+            [
+                "ListOrganizations",
+                "ListOrganizations",
+                "ListAgents",
+                "ListAgents"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn clones_issue_calls_concurrently_over_one_channel() {
+        let server = TestServer::start(org_reply).await;
+        let client = server.client().await;
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let mut client = client.clone();
+                tokio::spawn(async move {
+                    let org_id = format!("org-{i}");
+                    for _ in 0..5 {
+                        assert_eq!(client.get_org(&org_id).await.unwrap().org_id, org_id);
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(server.calls().len(), 40);
+    }
+
+    #[tokio::test]
+    async fn calls_succeed_again_once_a_restarted_server_is_back() {
+        let server = TestServer::start(org_reply).await;
+        let mut client = server.client().await;
+        client.get_org("org-1").await.unwrap();
+
+        let addr = server.stop().await;
+        let err = client.get_org("org-1").await.unwrap_err();
+        assert!(matches!(&err, SdkError::Status(s) if s.code() == tonic::Code::Unavailable));
+
+        let restarted = TestServer::start_at(&addr, org_reply).await;
+        assert_eq!(client.get_org("org-1").await.unwrap().name, "acme");
+        assert_eq!(restarted.methods(), ["GetOrganization"]);
+    }
+
+    #[tokio::test]
+    async fn retries_reconnect_within_one_call() {
+        let server = TestServer::start(org_reply).await;
+        let mut client = server.client().await.retry_policy(RetryPolicy {
+            max_attempts: 20,
+            backoff: Arc::new(Constant(Duration::from_millis(25))),
+            retry_mutations: false,
+        });
+        client.get_org("org-1").await.unwrap();
+        let addr = server.stop().await;
+
+        // The server comes back while the client is backing off.
+        let restart = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            TestServer::start_at(&addr, org_reply).await
+        });
+        assert_eq!(client.get_org("org-1").await.unwrap().name, "acme");
+        let restarted = restart.await.unwrap();
+        assert_eq!(restarted.methods(), ["GetOrganization"]);
+    }
+
+    /// Holds reserved tokens against a 1,000-token budget until they are
+    /// committed by a usage report, released, or `ttl` passes.
+    async fn reservation_server(ttl: Duration) -> TestServer {
+        #[derive(Default)]
+        struct Ledger {
+            used: i64,
+            holds: HashMap<String, (i64, SystemTime)>,
+            next: usize,
+        }
+        let ledger = std::sync::Mutex::new(Ledger::default());
+        TestServer::start(move |call| {
+            let mut ledger = ledger.lock().unwrap();
+            let now = SystemTime::now();
+            ledger.holds.retain(|_, (_, expires)| *expires > now);
+            let held: i64 = ledger.holds.values().map(|(tokens, _)| tokens).sum();
+            let remaining = 1_000 - ledger.used - held;
+            match call.method() {
+                "CheckBudget" => {
+                    let req: CheckBudgetRequest = call.request();
+                    if req.estimated_tokens > remaining {
+                        return reply(&CheckBudgetResponse {
+                            allowed: false,
+                            tokens_remaining: remaining,
+                            reason: "insufficient budget".into(),
+                            ..Default::default()
+                        });
+                    }
+                    ledger.next += 1;
+                    let id = format!("res-{}", ledger.next);
+                    let expires = now + ttl;
+                    ledger
+                        .holds
+                        .insert(id.clone(), (req.estimated_tokens, expires));
+                    reply(&CheckBudgetResponse {
+                        allowed: true,
+                        tokens_remaining: remaining - req.estimated_tokens,
+                        reservation_id: id.clone(),
+                        reservation: Some(ReservationProto {
+                            reservation_id: id,
+                            org_id: req.org_id,
+                            agent_id: req.agent_id,
+                            tokens: req.estimated_tokens,
+                            created_at: Some(now.into()),
+                            expires_at: Some(expires.into()),
+                        }),
+                        ..Default::default()
+                    })
+                }
+                "ReportUsage" => {
+                    let req: ReportUsageRequest = call.request();
+                    let hold = ledger.holds.remove(&req.reservation_id).map_or(0, |h| h.0);
+                    ledger.used += req.tokens_used;
+                    reply(&ReportUsageResponse {
+                        success: true,
+                        tokens_remaining: remaining + hold - req.tokens_used,
+                    })
+                }
+                "ReleaseReservation" => {
+                    let req: ReleaseReservationRequest = call.request();
+                    let released = ledger.holds.remove(&req.reservation_id).is_some();
+                    reply(&ReleaseReservationResponse { released })
+                }
+                _ => fail(Status::unimplemented(call.path.clone())),
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn committing_a_reservation_returns_the_unused_part() {
+        let server = reservation_server(Duration::from_secs(60)).await;
+        let mut client = server.client().await;
+        let reservation = client
+            .reserve_budget("org-1", "agent-1", 400)
+            .await
+            .unwrap();
+        assert_eq!(reservation.tokens, 400);
+        assert!(reservation.expires_at.is_some());
+        let id = reservation.reservation_id.clone();
+
+        let remaining = client
+            .commit_reservation(reservation, "exec-1", 250)
+            .await
+            .unwrap();
+        assert_eq!(remaining, 750);
+        let report: ReportUsageRequest = server.calls()[1].request();
+        assert_eq!(report.reservation_id, id);
+        assert_eq!(report.tokens_used, 250);
+    }
+
+    #[tokio::test]
+    async fn released_reservation_frees_its_tokens_once() {
+        let server = reservation_server(Duration::from_secs(60)).await;
+        let mut client = server.client().await;
+        let reservation = client
+            .reserve_budget("org-1", "agent-1", 800)
+            .await
+            .unwrap();
+        let err = client
+            .reserve_budget("org-1", "agent-1", 800)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                SdkError::BudgetExhausted {
+                    tokens_remaining: Some(200),
+                    ..
+                }
+            ),
+            "{err:?}"
+        );
+
+        assert!(client
+            .release_reservation(reservation.clone())
+            .await
+            .unwrap());
+        assert!(!client.release_reservation(reservation).await.unwrap());
+        client
+            .reserve_budget("org-1", "agent-1", 1_000)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn expired_reservation_is_released_by_the_server() {
+        let server = reservation_server(Duration::from_millis(50)).await;
+        let mut client = server.client().await;
+        let reservation = client
+            .reserve_budget("org-1", "agent-1", 600)
+            .await
+            .unwrap();
+        assert!(client
+            .reserve_budget("org-1", "agent-1", 600)
+            .await
+            .is_err());
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(reservation.expires_at.unwrap() <= SystemTime::now());
+        assert!(!client.release_reservation(reservation).await.unwrap());
+        client
+            .reserve_budget("org-1", "agent-1", 600)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reservations_are_resent_only_as_mutations() {
+        let server = TestServer::start(|_| fail(Status::unavailable("try again"))).await;
+        let mut client = server.client().await.retry_policy(RetryPolicy {
+            max_attempts: 3,
+            backoff: Arc::new(Constant(Duration::ZERO)),
+            retry_mutations: false,
+        });
+        assert!(client.reserve_budget("org-1", "agent-1", 10).await.is_err());
+        assert_eq!(server.methods(), ["CheckBudget"]);
+
+        let mut client = retrying(client);
+        assert!(client.reserve_budget("org-1", "agent-1", 10).await.is_err());
+        let keys: Vec<Vec<String>> = server.calls()[1..]
+            .iter()
+            .map(|c| c.headers(IDEMPOTENCY_KEY_HEADER))
+            .collect();
+        assert_eq!(keys.len(), 3);
+        assert!(
+            keys.iter().all(|k| k.len() == 1 && k == &keys[0]),
+            "{keys:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn decisions_report_the_deciding_rule() {
+        let server = TestServer::start(|call| {
+            let req: EvaluatePolicyRequest = call.request();
+            let (allowed, matched_tool, effect) = match req.tool_name.as_str() {
+                "search" => (true, "*", "allow"),
+                "shell" => (false, "shell", "deny"),
+                _ => (false, "", ""),
+            };
+            reply(&PolicyDecisionProto {
+                allowed,
+                matched_policy_id: "policy-1".into(),
+                matched_tool: matched_tool.into(),
+                effect: effect.into(),
+                ..Default::default()
+            })
+        })
+        .await;
+        let client = server.client().await;
+        let decide = |tool: &'static str| {
+            let mut client = client.clone();
+            async move {
+                let d = client
+                    .evaluate_policy("org-1", "agent-1", tool, 0)
+                    .await
+                    .unwrap();
+                (d.allowed, d.matched_tool, d.effect)
+            }
+        };
+        assert_eq!(
+            decide("search").await,
+            (true, Some("*".into()), Some(PolicyEffect::Allow))
+        );
+        assert_eq!(
+            decide("shell").await,
+            (false, Some("shell".into()), Some(PolicyEffect::Deny))
+        );
+        assert_eq!(decide("deploy").await, (false, None, None));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn decisions_without_rule_details_still_deserialize() {
+        let decision: PolicyDecision = serde_json::from_value(serde_json::json!({
+            "allowed": true,
+            "reason": "wildcard allow",
+            "policy_id": "policy-1",
+        }))
+        .unwrap();
+        assert_eq!(decision.matched_tool, None);
+        assert_eq!(decision.effect, None);
+        assert_eq!(decision.applied_constraints, None);
+    }
+
+    /// Logs each policy, budget and deactivation change with a cursor.
+    /// `WatchChanges` replays the changes after `after_cursor`, then ends
+    /// the stream as a restarting server would.
+    async fn change_log_server() -> TestServer {
+        let log = std::sync::Mutex::new(Vec::<ChangeEventProto>::new());
+        TestServer::start(move |call| {
+            let mut log = log.lock().unwrap();
+            let cursor = log.len() as u64 + 1;
+            let (change, response) = match call.method() {
+                "SetPolicy" => {
+                    let req: SetPolicyRequest = call.request();
+                    let policy = PolicyProto {
+                        policy_id: format!("policy-{cursor}"),
+                        org_id: req.org_id.clone(),
+                        agent_id: req.agent_id.clone(),
+                        tools: req.tools,
+                        token_limit: req.token_limit,
+                        execution_timeout_seconds: req.execution_timeout_seconds,
+                        mode: req.mode,
+                        ..Default::default()
+                    };
+                    let change = ChangeEventProto {
+                        kind: "policy_updated".into(),
+                        org_id: req.org_id,
+                        agent_id: req.agent_id,
+                        policy_id: policy.policy_id.clone(),
+                        policy: Some(policy.clone()),
+                        ..Default::default()
+                    };
+                    (change, policy.encode_to_vec())
+                }
+                "SetBudget" => {
+                    let req: SetBudgetRequest = call.request();
+                    let budget = BudgetProto {
+                        budget_id: "budget-1".into(),
+                        token_limit: req.token_limit,
+                        tokens_remaining: req.token_limit,
+                        reset_period_days: req.reset_period_days,
+                        ..Default::default()
+                    };
+                    let change = ChangeEventProto {
+                        kind: "budget_updated".into(),
+                        org_id: req.org_id,
+                        agent_id: req.agent_id,
+                        budget: Some(budget.clone()),
+                        ..Default::default()
+                    };
+                    (change, budget.encode_to_vec())
+                }
+                "DeactivateAgent" => {
+                    let req: DeactivateAgentRequest = call.request();
+                    let change = ChangeEventProto {
+                        kind: "agent_deactivated".into(),
+                        org_id: req.org_id,
+                        agent_id: req.agent_id,
+                        reason: req.reason,
+                        ..Default::default()
+                    };
+                    let response = DeactivateAgentResponse { success: true };
+                    (change, response.encode_to_vec())
+                }
+                "WatchChanges" => {
+                    let req: WatchChangesRequest = call.request();
+                    // Cursor 0 asks for live changes only; there are none.
+                    return Ok(log
+                        .iter()
+                        .filter(|c| req.after_cursor > 0 && c.cursor > req.after_cursor)
+                        .map(Message::encode_to_vec)
+                        .collect());
+                }
+                _ => return fail(Status::unimplemented(call.path.clone())),
+            };
+            log.push(ChangeEventProto { cursor, ..change });
+            Ok(vec![response])
+        })
+        .await
+    }
+
+    async fn drain(mut stream: EventStream<ChangeEvent>) -> Vec<ChangeEvent> {
+        let mut events = Vec::new();
+        while let Some(event) = stream.next().await {
+            events.push(event.unwrap());
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn watch_resumes_with_changes_made_while_away() {
+        let server = change_log_server().await;
+        let mut client = server.client().await;
+        client
+            .deactivate_agent("org-1", "agent-0", None)
+            .await
+            .unwrap();
+        let seen = 1;
+        let idle = client
+            .watch_changes_from("org-1", seen, StreamConfig::default())
+            .await
+            .unwrap();
+        assert!(drain(idle).await.is_empty(), "nothing new since cursor 1");
+
+        client.set_budget("org-1", None, 5_000, 7).await.unwrap();
+        client
+            .set_policy("org-1", Some("agent-1"), &["search"], &[], 500, 30)
+            .await
+            .unwrap();
+        client
+            .deactivate_agent("org-1", "agent-1", Some("compromised"))
+            .await
+            .unwrap();
+        let resumed = client
+            .watch_changes_from("org-1", seen, StreamConfig::default())
+            .await
+            .unwrap();
+        let events = drain(resumed).await;
+        assert_eq!(events.len(), 3, "{events:?}");
+        assert!(
+            matches!(
+                &events[0],
+                ChangeEvent::BudgetUpdated { cursor: 2, agent_id: None, budget: Some(b), .. }
+                    if b.token_limit == 5_000 && b.reset_period_days == 7
+            ),
+            "{events:?}"
+        );
+        match &events[1] {
+            ChangeEvent::PolicyUpdated {
+                cursor: 3,
+                agent_id: Some(agent),
+                policy_id,
+                policy: Some(policy),
+                ..
+            } => {
+                assert_eq!(agent, "agent-1");
+                assert_eq!(policy_id, "policy-3");
+                assert_eq!(policy.token_limit, 500);
+                assert_eq!(policy.tools[0].tool_name, "search");
+            }
+            other => panic!("expected the policy change, got {other:?}"),
+        }
+        assert!(
+            matches!(
+                &events[2],
+                ChangeEvent::AgentDeactivated { cursor: 4, agent_id, reason: Some(r), .. }
+                    if agent_id == "agent-1" && r == "compromised"
+            ),
+            "{events:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn custom_metadata_arrives_alongside_the_auth_header() {
+        let server = TestServer::start(org_reply).await;
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let mut client = server
+            .client()
+            .await
+            .with_metadata("X-Tenant", "acme")
+            .unwrap()
+            .with_metadata("x-request-id", "overridden")
+            .unwrap()
+            .with_metadata("authorization", "Bearer spoofed")
+            .unwrap()
+            .auth_token("t0ken")
+            .unwrap()
+            .metadata_provider(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst) + 1;
+                vec![("x-request-id".into(), format!("req-{n}"))]
+            });
+        client.get_org("org-1").await.unwrap();
+        client.get_org("org-1").await.unwrap();
+
+        let calls = server.calls();
+        for (call, id) in calls.iter().zip(["req-1", "req-2"]) {
+            assert_eq!(call.headers("x-tenant"), ["acme"]);
+            assert_eq!(call.headers("x-request-id"), [id]);
+            assert_eq!(call.headers("authorization"), ["Bearer t0ken"]);
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_metadata_is_a_config_error() {
+        let server = TestServer::start(org_reply).await;
+        let result = server.client().await.with_metadata("bad key", "v");
+        assert!(matches!(result, Err(SdkError::Config(_))));
+
+        let mut client = server
+            .client()
+            .await
+            .metadata_provider(|| vec![("x-trace".into(), "line\nbreak".into())]);
+        let err = client.get_org("org-1").await.unwrap_err();
+        assert!(matches!(err, SdkError::Config(_)), "{err:?}");
+        assert!(server.calls().is_empty());
     }
 }
@@ -0,0 +1,656 @@
+use crate::client::{non_negative, require};
+use crate::error::{Result, SdkError};
+use crate::guard::Guard;
+use crate::models::{
+    Agent, AgentEntry, AgentSpec, BudgetCheck, BudgetEntry, BudgetInfo, EffectivePolicy, Org,
+    OrgConfig, Policy, PolicyDecision, PolicyEntry, RateLimit, RateLimitCheck, RegisteredAgent,
+    Role,
+};
+use crate::policy::PolicyBuilder;
+use crate::AgentPlatformClient;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+
+/// The core control plane operations, implemented by
+/// [`AgentPlatformClient`] and, with the `testing` feature, by
+/// `mock::MockAgentPlatformClient`. Code written against this trait can run
+/// its tests on the in-memory mock instead of a live server.
+///
+/// Each method behaves like the client method of the same name.
+pub trait ControlPlane: Send {
+    fn create_org(&mut self, name: &str) -> impl Future<Output = Result<Org>> + Send;
+
+    fn get_org(&mut self, org_id: &str) -> impl Future<Output = Result<Org>> + Send;
+
+    fn list_orgs(&mut self) -> impl Future<Output = Result<Vec<Org>>> + Send;
+
+    fn delete_org_with_options(
+        &mut self,
+        org_id: &str,
+        cascade: bool,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn delete_org(&mut self, org_id: &str) -> impl Future<Output = Result<bool>> + Send {
+        self.delete_org_with_options(org_id, false)
+    }
+
+    fn register_agent(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        role: impl Into<Role> + Send,
+        delegated_user_id: Option<&str>,
+    ) -> impl Future<Output = Result<RegisteredAgent>> + Send;
+
+    fn get_agent(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> impl Future<Output = Result<Agent>> + Send;
+
+    fn list_agents(&mut self, org_id: &str) -> impl Future<Output = Result<Vec<Agent>>> + Send;
+
+    fn list_all_agents(&mut self, org_id: &str) -> impl Future<Output = Result<Vec<Agent>>> + Send;
+
+    fn register_agents(
+        &mut self,
+        org_id: &str,
+        specs: &[AgentSpec],
+    ) -> impl Future<Output = Result<Vec<RegisteredAgent>>> + Send;
+
+    fn deactivate_agent(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        reason: Option<&str>,
+    ) -> impl Future<Output = Result<bool>> + Send;
+
+    fn set_policy_from(
+        &mut self,
+        policy: PolicyBuilder,
+    ) -> impl Future<Output = Result<String>> + Send;
+
+    fn list_policies(&mut self, org_id: &str) -> impl Future<Output = Result<Vec<Policy>>> + Send;
+
+    fn get_effective_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> impl Future<Output = Result<EffectivePolicy>> + Send;
+
+    fn evaluate_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> impl Future<Output = Result<PolicyDecision>> + Send;
+
+    fn evaluate_policies(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tools: &[(&str, i64)],
+    ) -> impl Future<Output = Result<Vec<PolicyDecision>>> + Send;
+
+    fn set_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+    ) -> impl Future<Output = Result<BudgetInfo>> + Send;
+
+    fn set_budget_with_warning(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+        warning_threshold_percent: Option<u8>,
+    ) -> impl Future<Output = Result<BudgetInfo>> + Send;
+
+    fn get_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+    ) -> impl Future<Output = Result<BudgetInfo>> + Send;
+
+    fn check_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+    ) -> impl Future<Output = Result<BudgetCheck>> + Send;
+
+    fn report_usage(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        execution_id: &str,
+        tokens_used: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+    ) -> impl Future<Output = Result<i64>> + Send;
+
+    fn set_rate_limit(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        max_per_minute: i32,
+    ) -> impl Future<Output = Result<RateLimit>> + Send;
+
+    fn check_rate_limit(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> impl Future<Output = Result<RateLimitCheck>> + Send;
+
+    /// Checks that the policy allows `tool_name` and that the budget covers
+    /// `estimated_tokens`, in that order, returning a [`Guard`] to report the
+    /// actual usage through once the work is done. Fails with
+    /// [`SdkError::PolicyDenied`](crate::SdkError::PolicyDenied) or
+    /// [`SdkError::BudgetExhausted`](crate::SdkError::BudgetExhausted) when
+    /// either says no. The guard holds a clone of `self`.
+    fn guard(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> impl Future<Output = Result<Guard<Self>>> + Send
+    where
+        Self: Clone,
+    {
+        async move {
+            let decision = self
+                .evaluate_policy(org_id, agent_id, tool_name, estimated_tokens)
+                .await?
+                .require_allowed(tool_name)?;
+            let budget = self
+                .check_budget(org_id, agent_id, estimated_tokens)
+                .await?
+                .require_allowed()?;
+            Ok(Guard::new(
+                self.clone(),
+                org_id,
+                agent_id,
+                tool_name,
+                decision,
+                budget,
+            ))
+        }
+    }
+
+    /// Snapshots the org's active agents and their policies and budgets, for
+    /// [`import_org_config`](Self::import_org_config). Policies and budgets of
+    /// deactivated agents are left out, as are agents without a budget of
+    /// their own. Fails with [`SdkError::InvalidInput`] if two active agents
+    /// share a name, since the config could not tell them apart.
+    fn export_org_config(&mut self, org_id: &str) -> impl Future<Output = Result<OrgConfig>> + Send
+    where
+        Self: Sized,
+    {
+        export_org_config(self, org_id)
+    }
+
+    /// Applies `config` to `org_id`, replacing the policy and budget of each
+    /// scope it covers. Agents are matched by name to the org's active
+    /// agents. With `create_agents`, config agents the org lacks are first
+    /// registered in one all-or-nothing batch, and their registrations are
+    /// returned; store the bootstrap credentials, which are not shown again.
+    ///
+    /// The whole config is checked before anything is written: a negative
+    /// limit, an agent listed twice, or a policy or budget for an agent the
+    /// org does not have fails with [`SdkError::InvalidInput`] and changes
+    /// nothing. Policies and budgets are then set one call at a time, so an
+    /// error partway through leaves the earlier ones applied; importing the
+    /// same config again finishes the job.
+    fn import_org_config(
+        &mut self,
+        org_id: &str,
+        config: &OrgConfig,
+        create_agents: bool,
+    ) -> impl Future<Output = Result<Vec<RegisteredAgent>>> + Send
+    where
+        Self: Sized,
+    {
+        import_org_config(self, org_id, config, create_agents)
+    }
+}
+
+impl ControlPlane for AgentPlatformClient {
+    async fn create_org(&mut self, name: &str) -> Result<Org> {
+        AgentPlatformClient::create_org(self, name).await
+    }
+
+    async fn get_org(&mut self, org_id: &str) -> Result<Org> {
+        AgentPlatformClient::get_org(self, org_id).await
+    }
+
+    async fn list_orgs(&mut self) -> Result<Vec<Org>> {
+        AgentPlatformClient::list_orgs(self).await
+    }
+
+    async fn delete_org_with_options(&mut self, org_id: &str, cascade: bool) -> Result<bool> {
+        AgentPlatformClient::delete_org_with_options(self, org_id, cascade).await
+    }
+
+    async fn register_agent(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        role: impl Into<Role> + Send,
+        delegated_user_id: Option<&str>,
+    ) -> Result<RegisteredAgent> {
+        AgentPlatformClient::register_agent(self, org_id, name, role, delegated_user_id).await
+    }
+
+    async fn get_agent(&mut self, org_id: &str, agent_id: &str) -> Result<Agent> {
+        AgentPlatformClient::get_agent(self, org_id, agent_id).await
+    }
+
+    async fn list_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        AgentPlatformClient::list_agents(self, org_id).await
+    }
+
+    async fn list_all_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        AgentPlatformClient::list_all_agents(self, org_id).await
+    }
+
+    async fn register_agents(
+        &mut self,
+        org_id: &str,
+        specs: &[AgentSpec],
+    ) -> Result<Vec<RegisteredAgent>> {
+        AgentPlatformClient::register_agents(self, org_id, specs).await
+    }
+
+    async fn deactivate_agent(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        reason: Option<&str>,
+    ) -> Result<bool> {
+        AgentPlatformClient::deactivate_agent(self, org_id, agent_id, reason).await
+    }
+
+    async fn set_policy_from(&mut self, policy: PolicyBuilder) -> Result<String> {
+        AgentPlatformClient::set_policy_from(self, policy).await
+    }
+
+    async fn list_policies(&mut self, org_id: &str) -> Result<Vec<Policy>> {
+        AgentPlatformClient::list_policies(self, org_id).await
+    }
+
+    async fn get_effective_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> Result<EffectivePolicy> {
+        AgentPlatformClient::get_effective_policy(self, org_id, agent_id).await
+    }
+
+    async fn evaluate_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> Result<PolicyDecision> {
+        AgentPlatformClient::evaluate_policy(self, org_id, agent_id, tool_name, estimated_tokens)
+            .await
+    }
+
+    async fn evaluate_policies(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tools: &[(&str, i64)],
+    ) -> Result<Vec<PolicyDecision>> {
+        AgentPlatformClient::evaluate_policies(self, org_id, agent_id, tools).await
+    }
+
+    async fn set_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+    ) -> Result<BudgetInfo> {
+        AgentPlatformClient::set_budget(self, org_id, agent_id, token_limit, reset_period_days)
+            .await
+    }
+
+    async fn set_budget_with_warning(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+        warning_threshold_percent: Option<u8>,
+    ) -> Result<BudgetInfo> {
+        AgentPlatformClient::set_budget_with_warning(
+            self,
+            org_id,
+            agent_id,
+            token_limit,
+            reset_period_days,
+            warning_threshold_percent,
+        )
+        .await
+    }
+
+    async fn get_budget(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<BudgetInfo> {
+        AgentPlatformClient::get_budget(self, org_id, agent_id).await
+    }
+
+    async fn check_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+    ) -> Result<BudgetCheck> {
+        AgentPlatformClient::check_budget(self, org_id, agent_id, estimated_tokens).await
+    }
+
+    async fn report_usage(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        execution_id: &str,
+        tokens_used: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+    ) -> Result<i64> {
+        AgentPlatformClient::report_usage(
+            self,
+            org_id,
+            agent_id,
+            execution_id,
+            tokens_used,
+            tool_invocations,
+            duration_ms,
+        )
+        .await
+    }
+
+    async fn set_rate_limit(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        max_per_minute: i32,
+    ) -> Result<RateLimit> {
+        AgentPlatformClient::set_rate_limit(self, org_id, agent_id, max_per_minute).await
+    }
+
+    async fn check_rate_limit(&mut self, org_id: &str, agent_id: &str) -> Result<RateLimitCheck> {
+        AgentPlatformClient::check_rate_limit(self, org_id, agent_id).await
+    }
+}
+
+async fn export_org_config<C: ControlPlane>(cp: &mut C, org_id: &str) -> Result<OrgConfig> {
+    let mut agents: Vec<Agent> = cp
+        .list_all_agents(org_id)
+        .await?
+        .into_iter()
+        .filter(|a| a.active)
+        .collect();
+    agents.sort_by(|a, b| a.name.cmp(&b.name));
+    if let Some(pair) = agents.windows(2).find(|w| w[0].name == w[1].name) {
+        return Err(SdkError::InvalidInput(format!(
+            "org {org_id} has more than one active agent named '{}'",
+            pair[0].name
+        )));
+    }
+    let names: HashMap<&str, &str> = agents
+        .iter()
+        .map(|a| (a.agent_id.as_str(), a.name.as_str()))
+        .collect();
+
+    let mut policies = Vec::new();
+    for p in cp.list_policies(org_id).await? {
+        let agent = match p.agent_id.as_deref() {
+            None => None,
+            Some(id) => match names.get(id) {
+                Some(name) => Some(name.to_string()),
+                None => continue,
+            },
+        };
+        policies.push(PolicyEntry {
+            agent,
+            tools: p.tools,
+            token_limit: p.token_limit,
+            execution_timeout_seconds: p.execution_timeout_seconds,
+            mode: p.mode,
+        });
+    }
+    policies.sort_by(|a, b| a.agent.cmp(&b.agent));
+
+    let mut budgets = Vec::new();
+    for agent in std::iter::once(None).chain(agents.iter().map(Some)) {
+        let budget = match cp
+            .get_budget(org_id, agent.map(|a| a.agent_id.as_str()))
+            .await
+        {
+            Ok(budget) => budget,
+            Err(SdkError::NotFound(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        budgets.push(BudgetEntry {
+            agent: agent.map(|a| a.name.clone()),
+            token_limit: budget.token_limit,
+            reset_period_days: budget.reset_period_days,
+            warning_threshold_percent: budget.warning_threshold_percent,
+        });
+    }
+
+    Ok(OrgConfig {
+        agents: agents
+            .into_iter()
+            .map(|a| AgentEntry {
+                name: a.name,
+                role: a.role,
+                delegated_user_id: a.delegated_user_id,
+                token_claims: a.token_claims,
+            })
+            .collect(),
+        policies,
+        budgets,
+    })
+}
+
+async fn import_org_config<C: ControlPlane>(
+    cp: &mut C,
+    org_id: &str,
+    config: &OrgConfig,
+    create_agents: bool,
+) -> Result<Vec<RegisteredAgent>> {
+    require("org_id", org_id)?;
+    let mut listed = HashSet::new();
+    if let Some(a) = config
+        .agents
+        .iter()
+        .find(|a| !listed.insert(a.name.as_str()))
+    {
+        return Err(SdkError::InvalidInput(format!(
+            "config lists agent '{}' more than once",
+            a.name
+        )));
+    }
+    for p in &config.policies {
+        non_negative("token_limit", p.token_limit)?;
+        non_negative("timeout_seconds", p.execution_timeout_seconds.into())?;
+    }
+    for b in &config.budgets {
+        non_negative("token_limit", b.token_limit)?;
+        non_negative("reset_period_days", b.reset_period_days.into())?;
+        if let Some(percent) = b.warning_threshold_percent.filter(|p| *p > 100) {
+            return Err(SdkError::InvalidInput(format!(
+                "warning_threshold_percent must be at most 100, got {percent}"
+            )));
+        }
+    }
+
+    let mut ids: HashMap<String, String> = cp
+        .list_all_agents(org_id)
+        .await?
+        .into_iter()
+        .filter(|a| a.active)
+        .map(|a| (a.name, a.agent_id))
+        .collect();
+    let missing: Vec<AgentSpec> = config
+        .agents
+        .iter()
+        .filter(|a| create_agents && !ids.contains_key(&a.name))
+        .map(|a| AgentSpec {
+            name: a.name.clone(),
+            role: a.role.clone(),
+            delegated_user_id: a.delegated_user_id.clone(),
+            claims: a.token_claims.clone(),
+        })
+        .collect();
+    let pending: HashSet<&str> = missing.iter().map(|s| s.name.as_str()).collect();
+    let unresolved = config
+        .policies
+        .iter()
+        .map(|p| &p.agent)
+        .chain(config.budgets.iter().map(|b| &b.agent))
+        .flatten()
+        .find(|name| !ids.contains_key(*name) && !pending.contains(name.as_str()));
+    if let Some(name) = unresolved {
+        return Err(SdkError::InvalidInput(format!(
+            "config refers to agent '{name}', which org {org_id} does not have"
+        )));
+    }
+
+    let registered = if missing.is_empty() {
+        Vec::new()
+    } else {
+        cp.register_agents(org_id, &missing).await?
+    };
+    for r in &registered {
+        ids.insert(r.agent.name.clone(), r.agent.agent_id.clone());
+    }
+    let resolve = |agent: &Option<String>| -> Result<Option<String>> {
+        agent
+            .as_ref()
+            .map(|name| {
+                ids.get(name).cloned().ok_or_else(|| {
+                    SdkError::InvalidResponse(format!(
+                        "agent '{name}' missing from registration result"
+                    ))
+                })
+            })
+            .transpose()
+    };
+    for p in &config.policies {
+        let policy = PolicyBuilder {
+            org_id: org_id.to_string(),
+            agent_id: resolve(&p.agent)?,
+            tools: p.tools.clone(),
+            token_limit: p.token_limit,
+            timeout_seconds: p.execution_timeout_seconds,
+            mode: p.mode,
+        };
+        cp.set_policy_from(policy).await?;
+    }
+    for b in &config.budgets {
+        let agent_id = resolve(&b.agent)?;
+        cp.set_budget_with_warning(
+            org_id,
+            agent_id.as_deref(),
+            b.token_limit,
+            b.reset_period_days,
+            b.warning_threshold_percent,
+        )
+        .await?;
+    }
+    Ok(registered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockAgentPlatformClient;
+    use crate::models::PolicyMode;
+
+    fn spec(name: &str, claims: &[(&str, &str)]) -> AgentSpec {
+        AgentSpec {
+            name: name.to_string(),
+            role: Role::Executor,
+            delegated_user_id: Some("user-1".to_string()),
+            claims: claims
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn exported_config_imports_into_an_equal_org() {
+        let mut mock = MockAgentPlatformClient::new();
+        let source = mock.create_org("source").await.unwrap().org_id;
+        let agents = mock
+            .register_agents(
+                &source,
+                &[spec("writer", &[("team", "docs")]), spec("reader", &[])],
+            )
+            .await
+            .unwrap();
+        let writer = agents[0].agent.agent_id.clone();
+        let limits = serde_json::json!({"max_results": 5});
+        mock.set_policy_from(PolicyBuilder::new(&source).allow("search").deny("shell"))
+            .await
+            .unwrap();
+        let agent_policy = PolicyBuilder::new(&source)
+            .for_agent(&writer)
+            .allow_with("search", limits.as_object().unwrap().clone())
+            .token_limit(500)
+            .mode(PolicyMode::Shadow);
+        mock.set_policy_from(agent_policy).await.unwrap();
+        mock.set_budget(&source, None, 10_000, 30).await.unwrap();
+        mock.set_budget_with_warning(&source, Some(&writer), 2_000, 7, Some(80))
+            .await
+            .unwrap();
+
+        let exported = mock.export_org_config(&source).await.unwrap();
+        assert_eq!(exported.agents.len(), 2);
+        assert_eq!(exported.policies.len(), 2);
+        assert_eq!(exported.budgets.len(), 2);
+
+        let target = mock.create_org("target").await.unwrap().org_id;
+        let registered = mock
+            .import_org_config(&target, &exported, true)
+            .await
+            .unwrap();
+        assert_eq!(registered.len(), 2);
+        assert_eq!(mock.export_org_config(&target).await.unwrap(), exported);
+    }
+
+    #[tokio::test]
+    async fn import_rejects_an_agent_listed_twice() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap().org_id;
+        let entry = AgentEntry {
+            name: "bot".to_string(),
+            role: Role::Executor,
+            delegated_user_id: None,
+            token_claims: HashMap::new(),
+        };
+        let config = OrgConfig {
+            agents: vec![entry.clone(), entry],
+            ..OrgConfig::default()
+        };
+        let err = mock
+            .import_org_config(&org, &config, true)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, SdkError::InvalidInput(m) if m.contains("'bot'")),
+            "{err:?}"
+        );
+        mock.assert_not_called("list_all_agents");
+        mock.assert_not_called("register_agents");
+    }
+}
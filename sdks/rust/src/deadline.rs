@@ -0,0 +1,64 @@
+use crate::error::{Result, SdkError};
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Runs `fut` with `deadline` as the latest time any unary call it makes
+/// may finish, for honoring a caller's end-to-end deadline. Each call sends
+/// the time remaining as its gRPC timeout, or the client's own timeout if
+/// that is shorter, so the server can abandon the work too. A call made
+/// once the deadline has passed fails with [`SdkError::Timeout`] without
+/// being sent, and retries never wait past it.
+///
+/// Nested scopes keep the earlier deadline. The deadline is task-local, so
+/// it does not follow work into spawned tasks.
+///
+/// # Example
+/// ```no_run
+/// use agent_platform_sdk::deadline;
+/// use std::time::{Duration, Instant};
+///
+/// # async fn run(client: &mut agent_platform_sdk::AgentPlatformClient) -> agent_platform_sdk::error::Result<()> {
+/// let deadline = Instant::now() + Duration::from_millis(800);
+/// let decision = deadline::with_deadline(deadline, async {
+///     client.evaluate_policy("org-1", "agent-7", "search", 500).await
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_deadline<F: Future>(deadline: Instant, fut: F) -> F::Output {
+    let deadline = current().map_or(deadline, |outer| outer.min(deadline));
+    DEADLINE.scope(deadline, fut).await
+}
+
+/// [`with_deadline`] for a deadline `remaining` from now.
+pub async fn within<F: Future>(remaining: Duration, fut: F) -> F::Output {
+    with_deadline(Instant::now() + remaining, fut).await
+}
+
+/// The deadline of the enclosing [`with_deadline`], if any.
+pub fn current() -> Option<Instant> {
+    DEADLINE.try_with(|d| *d).ok()
+}
+
+/// Time left before the ambient deadline, if any; zero once it has passed.
+pub(crate) fn remaining() -> Option<Duration> {
+    current().map(|d| d.saturating_duration_since(Instant::now()))
+}
+
+/// Tightens `timeout` to the ambient deadline, failing once it has passed.
+pub(crate) fn clamp(method: &str, timeout: Option<Duration>) -> Result<Option<Duration>> {
+    let Some(remaining) = remaining() else {
+        return Ok(timeout);
+    };
+    if remaining.is_zero() {
+        return Err(SdkError::Timeout(format!(
+            "{method}: deadline passed before the call was sent"
+        )));
+    }
+    Ok(Some(timeout.map_or(remaining, |t| t.min(remaining))))
+}
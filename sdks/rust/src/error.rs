@@ -1,3 +1,6 @@
+use crate::retry::RetryPolicy;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,17 +8,240 @@ pub enum SdkError {
     #[error("gRPC transport error: {0}")]
     Transport(#[from] tonic::transport::Error),
 
+    /// Boxed because `tonic::Status` is large and would bloat every `Result`.
     #[error("gRPC status error: {0}")]
-    Status(#[from] tonic::Status),
+    Status(Box<tonic::Status>),
 
     #[error("not found: {0}")]
     NotFound(String),
 
-    #[error("policy denied: {0}")]
-    PolicyDenied(String),
+    /// `tool` and `policy_id` are known when the error comes from a policy
+    /// decision, not when the server rejected a call outright.
+    #[error("policy denied: {reason}")]
+    PolicyDenied {
+        tool: Option<String>,
+        reason: String,
+        policy_id: Option<String>,
+    },
 
-    #[error("budget exhausted: {0}")]
-    BudgetExhausted(String),
+    /// `tokens_remaining` is known when the error comes from a budget check,
+    /// not when the server rejected a call outright.
+    #[error("budget exhausted: {reason}")]
+    BudgetExhausted {
+        tokens_remaining: Option<i64>,
+        reason: String,
+    },
+
+    /// Too many calls in the current window; retry after `reset_at`.
+    #[error("rate limited: {reason}")]
+    RateLimited {
+        reset_at: Option<std::time::SystemTime>,
+        reason: String,
+    },
+
+    #[error("unauthenticated: {0}")]
+    Unauthenticated(String),
+
+    #[error("timed out: {0}")]
+    Timeout(String),
+
+    /// A non-cascading `delete_org` found active agents.
+    #[error("org still has active agents: {0}")]
+    OrgNotEmpty(String),
+
+    #[error("read-only client cannot call {0}")]
+    ReadOnly(String),
+
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+
+    /// Agents delegate to one another in a loop. Lists the chain from the
+    /// starting agent to the first repeated id.
+    #[error("delegation cycle: {}", .0.join(" -> "))]
+    DelegationCycle(Vec<String>),
+
+    #[error("invalid client configuration: {0}")]
+    Config(String),
+
+    /// An argument was rejected before anything was sent.
+    #[error("invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("audit export failed verification: {0}")]
+    IntegrityViolation(String),
+}
+
+/// Metadata key the control plane sets on a failure to say which of its
+/// checks produced it: `policy_denied`, `budget_exhausted` or
+/// `rate_limited`.
+pub const ERROR_REASON_HEADER: &str = "x-error-reason";
+
+impl From<tonic::Status> for SdkError {
+    fn from(status: tonic::Status) -> Self {
+        SdkError::Status(Box::new(status))
+    }
+}
+
+impl SdkError {
+    /// Maps the status codes the control plane uses for specific failures to
+    /// their variants, keeping the server's message. Other codes stay
+    /// [`SdkError::Status`].
+    ///
+    /// `PermissionDenied` and `ResourceExhausted` are also produced by the
+    /// transport and by auth layers (a message over the size limit, a token
+    /// missing a scope), so they are only mapped to a policy or budget
+    /// failure when the server marks them with [`ERROR_REASON_HEADER`].
+    pub fn from_status(status: tonic::Status) -> Self {
+        let message = status.message().to_string();
+        let reason = status
+            .metadata()
+            .get(ERROR_REASON_HEADER)
+            .and_then(|v| v.to_str().ok());
+        match (status.code(), reason) {
+            (tonic::Code::NotFound, _) => SdkError::NotFound(message),
+            (tonic::Code::PermissionDenied, Some("policy_denied")) => SdkError::PolicyDenied {
+                tool: None,
+                reason: message,
+                policy_id: None,
+            },
+            (tonic::Code::ResourceExhausted, Some("budget_exhausted")) => {
+                SdkError::BudgetExhausted {
+                    tokens_remaining: None,
+                    reason: message,
+                }
+            }
+            (tonic::Code::ResourceExhausted, Some("rate_limited")) => SdkError::RateLimited {
+                reason: message,
+                reset_at: None,
+            },
+            (tonic::Code::Unauthenticated, _) => SdkError::Unauthenticated(message),
+            (tonic::Code::DeadlineExceeded, _) => SdkError::Timeout(message),
+            _ => status.into(),
+        }
+    }
+
+    /// Whether the same call may succeed if tried again: transport errors,
+    /// timeouts, rate limiting once the window resets, and transient
+    /// statuses (see [`RetryPolicy::is_retryable`]). The client's retry loop
+    /// uses this, so an exhausted budget is never retried.
+    pub fn retriable(&self) -> bool {
+        match self {
+            SdkError::Transport(_) | SdkError::Timeout(_) | SdkError::RateLimited { .. } => true,
+            SdkError::Status(status) => RetryPolicy::is_retryable(status.code()),
+            _ => false,
+        }
+    }
+
+    /// A plain-data copy of this error, for forwarding it across a service
+    /// boundary.
+    pub fn to_serializable(&self) -> SerializableError {
+        let code = match self {
+            SdkError::Transport(_) => "transport".to_string(),
+            SdkError::Status(status) => snake_case(&format!("{:?}", status.code())),
+            SdkError::NotFound(_) => "not_found".to_string(),
+            SdkError::PolicyDenied { .. } => "policy_denied".to_string(),
+            SdkError::BudgetExhausted { .. } => "budget_exhausted".to_string(),
+            SdkError::RateLimited { .. } => "rate_limited".to_string(),
+            SdkError::Unauthenticated(_) => "unauthenticated".to_string(),
+            SdkError::Timeout(_) => "timeout".to_string(),
+            SdkError::OrgNotEmpty(_) => "org_not_empty".to_string(),
+            SdkError::ReadOnly(_) => "read_only".to_string(),
+            SdkError::InvalidResponse(_) => "invalid_response".to_string(),
+            SdkError::DelegationCycle(_) => "delegation_cycle".to_string(),
+            SdkError::Config(_) => "config".to_string(),
+            SdkError::InvalidInput(_) => "invalid_input".to_string(),
+            SdkError::Json(_) => "json".to_string(),
+            SdkError::IntegrityViolation(_) => "integrity_violation".to_string(),
+        };
+        SerializableError {
+            code,
+            message: self.to_string(),
+            retriable: self.retriable(),
+        }
+    }
+}
+
+/// [`SdkError`] reduced to data, from [`SdkError::to_serializable`].
+///
+/// `code` names the variant in snake_case (`"not_found"`,
+/// `"budget_exhausted"`, ...); for [`SdkError::Status`] it is the gRPC code
+/// instead (`"unavailable"`, `"invalid_argument"`, ...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SerializableError {
+    pub code: String,
+    pub message: String,
+    pub retriable: bool,
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
 }
 
 pub type Result<T> = std::result::Result<T, SdkError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tonic::{Code, Status};
+
+    fn marked(code: Code, reason: &str) -> Status {
+        let mut status = Status::new(code, "denied");
+        status
+            .metadata_mut()
+            .insert(ERROR_REASON_HEADER, reason.parse().unwrap());
+        status
+    }
+
+    #[test]
+    fn unmarked_codes_stay_status() {
+        let too_large = SdkError::from_status(Status::resource_exhausted("message too large"));
+        assert!(matches!(too_large, SdkError::Status(_)));
+        assert!(!too_large.retriable());
+        let scope = SdkError::from_status(Status::permission_denied("missing scope"));
+        assert!(matches!(scope, SdkError::Status(_)));
+    }
+
+    #[test]
+    fn marked_codes_map_to_variants() {
+        assert!(matches!(
+            SdkError::from_status(marked(Code::PermissionDenied, "policy_denied")),
+            SdkError::PolicyDenied { .. }
+        ));
+        let budget = SdkError::from_status(marked(Code::ResourceExhausted, "budget_exhausted"));
+        assert!(matches!(budget, SdkError::BudgetExhausted { .. }));
+        assert!(!budget.retriable());
+        let limited = SdkError::from_status(marked(Code::ResourceExhausted, "rate_limited"));
+        assert!(matches!(limited, SdkError::RateLimited { .. }));
+        assert!(limited.retriable());
+    }
+
+    #[test]
+    fn retriable_agrees_with_retry_policy() {
+        let codes = [
+            Code::Unavailable,
+            Code::ResourceExhausted,
+            Code::Internal,
+            Code::InvalidArgument,
+            Code::Aborted,
+        ];
+        for code in codes {
+            let err = SdkError::from_status(Status::new(code, ""));
+            assert_eq!(err.retriable(), RetryPolicy::is_retryable(code), "{code:?}");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::proto::control_plane_client::ControlPlaneClient;
+use std::time::{Duration, Instant};
+use tonic::transport::{Channel, Endpoint};
+use tonic::Code;
+
+/// Consecutive failures on the active endpoint before its circuit opens.
+const FAILURE_THRESHOLD: u32 = 5;
+/// How long to stay on a secondary before sending a probe call to the primary.
+const REPROBE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Endpoint selection for a client connected with failover. Index 0 is the
+/// primary; the rest are secondaries tried in order.
+#[derive(Clone)]
+pub(crate) struct Failover {
+    endpoints: Vec<Endpoint>,
+    channels: Vec<Channel>,
+    connected: Vec<bool>,
+    active: usize,
+    failures: u32,
+    opened_at: Option<Instant>,
+    probing: bool,
+}
+
+impl Failover {
+    /// Builds lazily connecting channels for each endpoint.
+    pub(crate) fn new(endpoints: Vec<Endpoint>) -> Self {
+        let channels = endpoints.iter().map(|e| e.connect_lazy()).collect();
+        Self {
+            connected: vec![false; endpoints.len()],
+            endpoints,
+            channels,
+            active: 0,
+            failures: 0,
+            opened_at: None,
+            probing: false,
+        }
+    }
+
+    /// Client to use for the next call. While on a secondary, periodically
+    /// routes a single call back to the primary to see if it has recovered.
+    pub(crate) fn select(&mut self) -> ControlPlaneClient<Channel> {
+        if let Some(opened) = self.opened_at {
+            if self.active != 0 && opened.elapsed() >= REPROBE_INTERVAL {
+                self.probing = true;
+                return ControlPlaneClient::new(self.channels[0].clone());
+            }
+        }
+        ControlPlaneClient::new(self.channels[self.active].clone())
+    }
+
+    /// Channel of the active endpoint, for calls outside the control plane
+    /// service.
+    pub(crate) fn channel(&self) -> Channel {
+        self.channels[self.active].clone()
+    }
+
+    /// The active endpoint, for dialing it directly.
+    pub(crate) fn endpoint(&self) -> Endpoint {
+        self.endpoints[self.active].clone()
+    }
+
+    /// Every endpoint's URI with its channel, primary first.
+    pub(crate) fn members(&self) -> Vec<(String, Channel)> {
+        self.endpoints
+            .iter()
+            .map(|e| e.uri().to_string())
+            .zip(self.channels.iter().cloned())
+            .collect()
+    }
+
+    /// Eagerly connects the active endpoint, replacing its lazy channel.
+    /// Does nothing if it has already been warmed.
+    pub(crate) async fn warm_up(&mut self) -> Result<(), tonic::transport::Error> {
+        if !self.connected[self.active] {
+            self.channels[self.active] = self.endpoints[self.active].connect().await?;
+            self.connected[self.active] = true;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn record(&mut self, code: Option<Code>) {
+        let failed = matches!(code, Some(Code::Unavailable | Code::DeadlineExceeded));
+        if std::mem::take(&mut self.probing) {
+            if failed {
+                // Primary still down: keep the circuit open for another interval.
+                self.opened_at = Some(Instant::now());
+            } else {
+                self.active = 0;
+                self.failures = 0;
+                self.opened_at = None;
+            }
+            return;
+        }
+        if !failed {
+            self.failures = 0;
+            return;
+        }
+        self.failures += 1;
+        if self.failures >= FAILURE_THRESHOLD && self.endpoints.len() > 1 {
+            self.failures = 0;
+            self.active = if self.active + 1 < self.endpoints.len() {
+                self.active + 1
+            } else {
+                1
+            };
+            self.opened_at = Some(Instant::now());
+        }
+    }
+}
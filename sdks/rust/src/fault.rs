@@ -0,0 +1,33 @@
+use rand::Rng;
+use std::time::Duration;
+
+/// Synthetic failures injected by the client itself, for testing how a
+/// service copes with control plane outages. Enable with
+/// [`AgentPlatformClient::fault_injection`](crate::AgentPlatformClient::fault_injection).
+///
+/// Injected errors are `Unavailable` statuses that never reach the server.
+/// They count toward failover and metrics exactly like real failures.
+#[derive(Debug, Clone, Default)]
+pub struct FaultConfig {
+    /// Probability from 0.0 to 1.0 that a matching call fails.
+    pub error_rate: f64,
+    /// Delay added before each matching call, whether or not it fails.
+    pub latency_injection: Duration,
+    /// RPC names to affect, e.g. `"EvaluatePolicy"`. Empty affects all calls.
+    pub which_methods: Vec<String>,
+}
+
+impl FaultConfig {
+    /// Applies the configured latency to `method`, then returns the error to
+    /// fail it with, if any.
+    pub(crate) async fn inject(&self, method: &str) -> Option<tonic::Status> {
+        if !self.which_methods.is_empty() && !self.which_methods.iter().any(|m| m == method) {
+            return None;
+        }
+        if !self.latency_injection.is_zero() {
+            tokio::time::sleep(self.latency_injection).await;
+        }
+        let fail = rand::thread_rng().gen::<f64>() < self.error_rate;
+        fail.then(|| tonic::Status::unavailable(format!("injected fault in {method}")))
+    }
+}
@@ -0,0 +1,157 @@
+use crate::control_plane::ControlPlane;
+use crate::error::Result;
+use crate::idempotency;
+use crate::models::{BudgetCheck, PolicyDecision};
+use crate::AgentPlatformClient;
+
+/// Permission to run one tool call, from [`ControlPlane::guard`] on the
+/// client or the mock. Both the policy and the budget allowed it;
+/// [`finish`](Self::finish) reports what the call actually used.
+///
+/// Dropping a guard without finishing reports nothing.
+///
+/// # Example
+/// ```no_run
+/// # async fn run(client: &mut agent_platform_sdk::AgentPlatformClient) -> agent_platform_sdk::error::Result<()> {
+/// let guard = client.guard("org-1", "agent-7", "search", 2_000).await?;
+/// // ... run the tool ...
+/// let remaining = guard.finish(1_640, 1, 850).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[must_use = "a guard reports usage only when finished"]
+pub struct Guard<C: ControlPlane = AgentPlatformClient> {
+    client: C,
+    org_id: String,
+    agent_id: String,
+    execution_id: String,
+    decision: PolicyDecision,
+    budget: BudgetCheck,
+}
+
+impl<C: ControlPlane> Guard<C> {
+    pub(crate) fn new(
+        client: C,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        decision: PolicyDecision,
+        budget: BudgetCheck,
+    ) -> Self {
+        Self {
+            client,
+            org_id: org_id.to_string(),
+            agent_id: agent_id.to_string(),
+            execution_id: format!("{tool_name}-{}", idempotency::unique_id()),
+            decision,
+            budget,
+        }
+    }
+
+    /// Id the usage will be reported under.
+    pub fn execution_id(&self) -> &str {
+        &self.execution_id
+    }
+
+    /// The policy decision that allowed the call, including any warnings.
+    pub fn decision(&self) -> &PolicyDecision {
+        &self.decision
+    }
+
+    /// The budget check that allowed the call, including any warning.
+    pub fn budget(&self) -> &BudgetCheck {
+        &self.budget
+    }
+
+    /// Reports the call's actual usage. Returns the tokens remaining in
+    /// the budget.
+    pub async fn finish(
+        mut self,
+        actual_tokens: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+    ) -> Result<i64> {
+        self.client
+            .report_usage(
+                &self.org_id,
+                &self.agent_id,
+                &self.execution_id,
+                actual_tokens,
+                tool_invocations,
+                duration_ms,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::control_plane::ControlPlane;
+    use crate::error::SdkError;
+    use crate::mock::MockAgentPlatformClient;
+    use crate::policy::PolicyBuilder;
+
+    /// A mock with one agent allowed `search`, under a 1,000-token budget.
+    async fn setup() -> (MockAgentPlatformClient, String, String) {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap().org_id;
+        let agent = mock
+            .register_agent(&org, "bot", "executor", None)
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        mock.set_policy_from(PolicyBuilder::new(&org).allow("search"))
+            .await
+            .unwrap();
+        mock.set_budget(&org, Some(&agent), 1_000, 30)
+            .await
+            .unwrap();
+        (mock, org, agent)
+    }
+
+    #[tokio::test]
+    async fn allowed_call_reports_usage_on_finish() {
+        let (mut mock, org, agent) = setup().await;
+        let guard = mock.guard(&org, &agent, "search", 200).await.unwrap();
+        assert!(guard.decision().allowed);
+        assert!(guard.execution_id().starts_with("search-"));
+        mock.assert_not_called("report_usage");
+
+        let remaining = guard.finish(150, 1, 20).await.unwrap();
+        assert_eq!(remaining, 850);
+        mock.assert_usage_reported(&agent, 150);
+    }
+
+    #[tokio::test]
+    async fn policy_denial_skips_the_budget_check() {
+        let (mut mock, org, agent) = setup().await;
+        let err = mock.guard(&org, &agent, "shell", 200).await.err().unwrap();
+        assert!(
+            matches!(&err, SdkError::PolicyDenied { tool: Some(t), .. } if t == "shell"),
+            "{err:?}"
+        );
+        mock.assert_not_called("check_budget");
+    }
+
+    #[tokio::test]
+    async fn budget_denial_is_budget_exhausted() {
+        let (mut mock, org, agent) = setup().await;
+        let err = mock
+            .guard(&org, &agent, "search", 5_000)
+            .await
+            .err()
+            .unwrap();
+        assert!(
+            matches!(
+                err,
+                SdkError::BudgetExhausted {
+                    tokens_remaining: Some(1_000),
+                    ..
+                }
+            ),
+            "{err:?}"
+        );
+        mock.assert_not_called("report_usage");
+    }
+}
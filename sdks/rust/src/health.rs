@@ -0,0 +1,27 @@
+use tonic::metadata::MetadataMap;
+use tonic::transport::Channel;
+use tonic::Extensions;
+
+pub(crate) mod proto {
+    tonic::include_proto!("grpc.health.v1");
+}
+
+use proto::health_check_response::ServingStatus;
+use proto::health_client::HealthClient;
+use proto::HealthCheckRequest;
+
+/// Asks the standard gRPC health service whether the server as a whole is
+/// serving, sending `metadata` (the client's headers and deadline) with the
+/// request. Failures, including `Unimplemented` from servers without the
+/// service, are returned for the caller to interpret.
+pub(crate) async fn check(channel: Channel, metadata: MetadataMap) -> Result<bool, tonic::Status> {
+    let request = tonic::Request::from_parts(
+        metadata,
+        Extensions::default(),
+        HealthCheckRequest {
+            service: String::new(),
+        },
+    );
+    let resp = HealthClient::new(channel).check(request).await?;
+    Ok(resp.into_inner().status() == ServingStatus::Serving)
+}
@@ -0,0 +1,109 @@
+use crate::sha256;
+use prost::Message;
+use rand::RngCore;
+use std::fmt::Write;
+use std::future::Future;
+
+tokio::task_local! {
+    static KEY: String;
+}
+
+/// Runs `fut` with `key` identifying its mutating calls, sent as the
+/// `idempotency-key` metadata header. A server that has already applied a
+/// call with that key returns the earlier result instead of applying it
+/// again, so choose a key that stays the same when the whole operation is
+/// re-run, such as one derived from a job id, and a crash between sending a
+/// call and reading its reply does not double-apply it.
+///
+/// Each mutating call in the scope sends `key/Method/digest`, where the
+/// digest is taken over the encoded request. Each distinct write gets its
+/// own key, re-running the scope reproduces them even if calls are added,
+/// dropped or reordered, and the client's own retries resend the same key.
+/// Two identical requests in one scope share a key, so the second is
+/// treated as a repeat of the first. Streaming calls such as
+/// `report_usage_stream` carry no key, since their requests are not known
+/// up front. Like [`crate::deadline`], the scope is task-local.
+///
+/// Outside a scope, mutating calls from a client with a
+/// [`RetryPolicy`](crate::retry::RetryPolicy) send a generated key, so a
+/// resent call is still applied once.
+///
+/// # Example
+#[cfg_attr(feature = "testing", doc = "```")]
+#[cfg_attr(not(feature = "testing"), doc = "```ignore")]
+/// use agent_platform_sdk::control_plane::ControlPlane;
+/// use agent_platform_sdk::idempotency;
+/// use agent_platform_sdk::mock::MockAgentPlatformClient;
+///
+/// # #[tokio::main]
+/// # async fn main() -> agent_platform_sdk::error::Result<()> {
+/// let mut mock = MockAgentPlatformClient::new();
+/// let org = mock.create_org("acme").await?;
+/// let agent = mock.register_agent(&org.org_id, "bot", "executor", None).await?.agent;
+/// mock.set_budget(&org.org_id, Some(&agent.agent_id), 1_000, 30).await?;
+///
+/// // The same job reporting twice, e.g. after a restart.
+/// for _ in 0..2 {
+///     let report = mock.report_usage(&org.org_id, &agent.agent_id, "exec-1", 300, 1, 90);
+///     idempotency::with_key("job-42", report).await?;
+/// }
+/// let budget = mock.get_budget(&org.org_id, Some(&agent.agent_id)).await?;
+/// assert_eq!(budget.tokens_used, 300);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_key<F: Future>(key: impl Into<String>, fut: F) -> F::Output {
+    KEY.scope(key.into(), fut).await
+}
+
+/// Key for a `method` call sending `request` inside the enclosing
+/// [`with_key`], if any.
+pub(crate) fn scoped_key(method: &str, request: &impl Message) -> Option<String> {
+    KEY.try_with(|key| {
+        let digest = sha256::digest(&[&request.encode_to_vec()]);
+        format!("{key}/{method}/{}", hex(&digest[..16]))
+    })
+    .ok()
+}
+
+/// A random (version 4) UUID.
+pub(crate) fn unique_id() -> String {
+    let mut bytes = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let h = hex(&bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..]
+    )
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut out, b| {
+        let _ = write!(out, "{b:02x}");
+        out
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn unique_ids_are_random_uuids() {
+        let ids: HashSet<String> = (0..1000).map(|_| unique_id()).collect();
+        assert_eq!(ids.len(), 1000);
+        for id in &ids {
+            let groups: Vec<usize> = id.split('-').map(str::len).collect();
+            assert_eq!(groups, [8, 4, 4, 4, 12], "{id}");
+            assert_eq!(&id[14..15], "4", "{id}");
+            assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"), "{id}");
+        }
+    }
+}
@@ -1,10 +1,43 @@
+pub mod audit;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod builder;
+mod cache;
 pub mod client;
+pub mod control_plane;
+pub mod deadline;
 pub mod error;
+mod failover;
+#[cfg(feature = "testing")]
+pub mod fault;
+pub mod guard;
+mod health;
+pub mod idempotency;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(any(test, feature = "testing"))]
+pub mod mock;
 pub mod models;
+pub mod policy;
+mod pool;
+pub mod prelude;
+pub mod profile;
+mod reflection;
+pub mod retry;
+#[cfg(feature = "rfc3339")]
+pub mod rfc3339;
+mod sha256;
+pub mod stream;
+#[cfg(test)]
+mod test_server;
+mod trace;
 
 pub mod proto {
     tonic::include_proto!("agent_platform");
 }
 
+pub use builder::ClientBuilder;
 pub use client::AgentPlatformClient;
+pub use control_plane::ControlPlane;
 pub use error::SdkError;
+pub use profile::ClientProfile;
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bucket bounds in seconds, from sub-millisecond to ten seconds.
+pub const LATENCY_BUCKETS: [f64; 14] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Point-in-time copy of one method's latency histogram.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramSnapshot {
+    /// `(upper bound in seconds, cumulative count)` for each bucket in
+    /// [`LATENCY_BUCKETS`]. Observations above the last bound only appear in
+    /// `count`.
+    pub buckets: Vec<(f64, u64)>,
+    pub count: u64,
+    pub sum_seconds: f64,
+}
+
+#[derive(Default)]
+struct Histogram {
+    counts: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum_seconds: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|b| seconds <= *b) {
+            self.counts[i] += 1;
+        }
+        self.count += 1;
+        self.sum_seconds += seconds;
+    }
+
+    fn snapshot(&self) -> HistogramSnapshot {
+        let mut cumulative = 0;
+        let buckets = LATENCY_BUCKETS
+            .iter()
+            .zip(self.counts.iter())
+            .map(|(bound, n)| {
+                cumulative += n;
+                (*bound, cumulative)
+            })
+            .collect();
+        HistogramSnapshot {
+            buckets,
+            count: self.count,
+            sum_seconds: self.sum_seconds,
+        }
+    }
+}
+
+/// Per-RPC latency histograms recorded by the client.
+#[derive(Default)]
+pub(crate) struct Metrics {
+    histograms: Mutex<HashMap<&'static str, Histogram>>,
+}
+
+impl Metrics {
+    pub(crate) fn observe(&self, method: &'static str, elapsed: Duration) {
+        self.histograms
+            .lock()
+            .unwrap()
+            .entry(method)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    pub(crate) fn snapshot(&self) -> HashMap<String, HistogramSnapshot> {
+        self.histograms
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(method, h)| (method.to_string(), h.snapshot()))
+            .collect()
+    }
+
+    /// Renders all histograms in the Prometheus text exposition format.
+    pub(crate) fn render_prometheus(&self) -> String {
+        const NAME: &str = "agent_platform_sdk_request_duration_seconds";
+        let mut snapshots: Vec<_> = self.snapshot().into_iter().collect();
+        snapshots.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP {NAME} Control plane RPC latency.");
+        let _ = writeln!(out, "# TYPE {NAME} histogram");
+        for (method, h) in snapshots {
+            for (bound, n) in &h.buckets {
+                let _ = writeln!(
+                    out,
+                    "{NAME}_bucket{{method=\"{method}\",le=\"{bound}\"}} {n}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{NAME}_bucket{{method=\"{method}\",le=\"+Inf\"}} {}",
+                h.count
+            );
+            let _ = writeln!(out, "{NAME}_sum{{method=\"{method}\"}} {}", h.sum_seconds);
+            let _ = writeln!(out, "{NAME}_count{{method=\"{method}\"}} {}", h.count);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(h: &HistogramSnapshot, bound: f64) -> u64 {
+        h.buckets.iter().find(|(b, _)| *b == bound).unwrap().1
+    }
+
+    #[test]
+    fn observations_land_in_the_first_bucket_that_holds_them() {
+        let mut h = Histogram::default();
+        // A value on a bound counts in that bucket, not the next.
+        h.observe(0.001);
+        h.observe(0.0011);
+        let snap = h.snapshot();
+        assert_eq!(bucket(&snap, 0.0005), 0);
+        assert_eq!(bucket(&snap, 0.001), 1);
+        assert_eq!(bucket(&snap, 0.0025), 2);
+        assert_eq!(bucket(&snap, 10.0), 2);
+        assert_eq!(snap.count, 2);
+    }
+
+    #[test]
+    fn observations_past_the_last_bound_only_count() {
+        let mut h = Histogram::default();
+        h.observe(12.0);
+        let snap = h.snapshot();
+        assert!(snap.buckets.iter().all(|(_, n)| *n == 0), "{snap:?}");
+        assert_eq!(snap.count, 1);
+        assert_eq!(snap.sum_seconds, 12.0);
+    }
+
+    #[test]
+    fn renders_prometheus_text() {
+        let metrics = Metrics::default();
+        metrics.observe("GetOrganization", Duration::from_millis(1));
+        metrics.observe("GetOrganization", Duration::from_secs(12));
+        metrics.observe("CheckBudget", Duration::from_millis(250));
+        let text = metrics.render_prometheus();
+        let lines: Vec<&str> = text.lines().collect();
+
+        const NAME: &str = "agent_platform_sdk_request_duration_seconds";
+        assert_eq!(
+            lines[0],
+            format!("# HELP {NAME} Control plane RPC latency.")
+        );
+        assert_eq!(lines[1], format!("# TYPE {NAME} histogram"));
+        // Methods sorted by name, each with every bucket, +Inf, sum and count.
+        let per_method = LATENCY_BUCKETS.len() + 3;
+        assert_eq!(lines.len(), 2 + 2 * per_method);
+        assert!(lines[2].contains(r#"method="CheckBudget""#));
+        for line in [
+            format!(r#"{NAME}_bucket{{method="CheckBudget",le="0.1"}} 0"#),
+            format!(r#"{NAME}_bucket{{method="CheckBudget",le="0.25"}} 1"#),
+            format!(r#"{NAME}_sum{{method="CheckBudget"}} 0.25"#),
+            format!(r#"{NAME}_bucket{{method="GetOrganization",le="0.001"}} 1"#),
+            format!(r#"{NAME}_bucket{{method="GetOrganization",le="10"}} 1"#),
+            format!(r#"{NAME}_bucket{{method="GetOrganization",le="+Inf"}} 2"#),
+            format!(r#"{NAME}_count{{method="GetOrganization"}} 2"#),
+        ] {
+            assert!(lines.contains(&line.as_str()), "missing {line} in\n{text}");
+        }
+    }
+}
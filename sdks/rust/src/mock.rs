@@ -0,0 +1,1070 @@
+use crate::client::{non_negative, require};
+use crate::control_plane::ControlPlane;
+use crate::error::{Result, SdkError};
+use crate::idempotency;
+use crate::models::{
+    Agent, AgentCredential, AgentSpec, BudgetCheck, BudgetInfo, EffectivePolicy, Org, Policy,
+    PolicyDecision, PolicyEffect, PolicyMode, RateLimit, RateLimitCheck, RegisteredAgent, Role,
+    ToolPermission, UsageReport,
+};
+use crate::policy::PolicyBuilder;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+/// Server defaults applied when a limit is left at zero.
+const DEFAULT_POLICY_TOKENS: i64 = 100_000;
+const DEFAULT_TIMEOUT_SECONDS: i32 = 300;
+const DEFAULT_BUDGET_TOKENS: i64 = 1_000_000;
+const DEFAULT_RESET_DAYS: i32 = 30;
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// An in-memory stand-in for the control plane, implementing
+/// [`ControlPlane`] so services can be tested without a server.
+///
+/// Policies and budgets follow the server's rules: an explicit deny beats an
+/// explicit allow, which beats a `*` allow, and anything else is denied;
+/// agent rules override org rules except org denies; usage is deducted from
+/// both the agent and the org budget. Ids are sequential (`org-1`,
+/// `agent-1`, ...), so results are deterministic.
+///
+/// Clones share state, so keep one to inspect the calls made through
+/// another.
+///
+/// # Example
+/// ```
+/// use agent_platform_sdk::control_plane::ControlPlane;
+/// use agent_platform_sdk::mock::MockAgentPlatformClient;
+/// use agent_platform_sdk::policy::PolicyBuilder;
+///
+/// # #[tokio::main]
+/// # async fn main() -> agent_platform_sdk::error::Result<()> {
+/// let mut mock = MockAgentPlatformClient::new();
+/// let org = mock.create_org("acme").await?;
+/// let agent = mock.register_agent(&org.org_id, "bot", "worker", None).await?.agent;
+/// mock.set_policy_from(PolicyBuilder::new(&org.org_id).allow("search")).await?;
+///
+/// assert!(mock.evaluate_policy(&org.org_id, &agent.agent_id, "search", 10).await?.allowed);
+/// mock.report_usage(&org.org_id, &agent.agent_id, "exec-1", 250, 1, 40).await?;
+/// mock.assert_usage_reported(&agent.agent_id, 250);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MockAgentPlatformClient {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Last id handed out per prefix.
+    next_ids: HashMap<String, u64>,
+    orgs: Vec<Org>,
+    agents: Vec<Agent>,
+    policies: HashMap<(String, Option<String>), Policy>,
+    budgets: HashMap<(String, Option<String>), BudgetInfo>,
+    calls: Vec<String>,
+    usage: Vec<UsageReport>,
+    /// `report_usage` results by idempotency key.
+    reported: HashMap<String, i64>,
+    rate_limits: HashMap<(String, Option<String>), i32>,
+    /// Start and call count of each rate limit's current window.
+    windows: HashMap<(String, Option<String>), (SystemTime, i32)>,
+    /// How far [`MockAgentPlatformClient::advance`] has moved the clock.
+    clock: Duration,
+}
+
+impl State {
+    fn now(&self) -> SystemTime {
+        SystemTime::now() + self.clock
+    }
+
+    fn id(&mut self, prefix: &str) -> String {
+        let n = self.next_ids.entry(prefix.to_string()).or_default();
+        *n += 1;
+        format!("{prefix}-{n}")
+    }
+
+    fn org(&self, org_id: &str) -> Result<&Org> {
+        self.orgs
+            .iter()
+            .find(|o| o.org_id == org_id)
+            .ok_or_else(|| SdkError::NotFound(format!("org {org_id}")))
+    }
+
+    fn agent_mut(&mut self, org_id: &str, agent_id: &str) -> Result<&mut Agent> {
+        self.agents
+            .iter_mut()
+            .find(|a| a.org_id == org_id && a.agent_id == agent_id)
+            .ok_or_else(|| SdkError::NotFound(format!("agent {agent_id}")))
+    }
+
+    fn effective_policy(&self, org_id: &str, agent_id: &str) -> Option<Policy> {
+        let org = self.policies.get(&(org_id.to_string(), None));
+        let agent = self
+            .policies
+            .get(&(org_id.to_string(), Some(agent_id.to_string())));
+        match (org, agent) {
+            (Some(org), Some(agent)) => Some(merge(org, agent)),
+            (org, agent) => agent.or(org).cloned(),
+        }
+    }
+
+    fn decide(
+        &self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> PolicyDecision {
+        let Some(policy) = self.effective_policy(org_id, agent_id) else {
+            return decision(false, "no policy found for org/agent".into(), None);
+        };
+        let mut result = evaluate(&policy, tool_name, estimated_tokens);
+        if policy.mode == PolicyMode::Shadow && !result.allowed {
+            result.allowed = true;
+            result.shadow_would_deny = true;
+        }
+        result
+    }
+
+    fn register(&mut self, org_id: &str, spec: AgentSpec) -> RegisteredAgent {
+        let delegate_is_agent = spec.delegated_user_id.as_ref().is_some_and(|id| {
+            self.agents
+                .iter()
+                .any(|a| a.agent_id == *id && a.org_id == org_id)
+        });
+        let agent = Agent {
+            agent_id: self.id("agent"),
+            org_id: org_id.to_string(),
+            name: spec.name,
+            role: spec.role,
+            active: true,
+            delegated_user_id: spec.delegated_user_id,
+            delegate_is_agent,
+            token_claims: spec.claims,
+            deactivation_reason: None,
+            deactivated_at: None,
+            deleted: false,
+            deleted_at: None,
+        };
+        let credential = AgentCredential {
+            credential_id: self.id("cred"),
+            token: format!("mock-token-{}", agent.agent_id),
+            expires_at: None,
+        };
+        self.agents.push(agent.clone());
+        RegisteredAgent {
+            agent,
+            initial_credential: Some(credential),
+        }
+    }
+
+    fn budget(&self, org_id: &str, agent_id: Option<&str>) -> Option<&BudgetInfo> {
+        self.budgets
+            .get(&(org_id.to_string(), agent_id.map(str::to_string)))
+    }
+
+    fn budget_mut(&mut self, org_id: &str, agent_id: Option<&str>) -> Option<&mut BudgetInfo> {
+        self.budgets
+            .get_mut(&(org_id.to_string(), agent_id.map(str::to_string)))
+    }
+}
+
+impl MockAgentPlatformClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the mock's clock forward, e.g. past the end of a rate-limit
+    /// window.
+    pub fn advance(&self, by: Duration) {
+        self.state().clock += by;
+    }
+
+    /// Names of the methods called so far, in order.
+    pub fn calls(&self) -> Vec<String> {
+        self.state().calls.clone()
+    }
+
+    /// How many times `method` was called.
+    pub fn call_count(&self, method: &str) -> usize {
+        self.state().calls.iter().filter(|c| *c == method).count()
+    }
+
+    /// Every `report_usage` call, in order.
+    pub fn usage_reports(&self) -> Vec<UsageReport> {
+        self.state().usage.clone()
+    }
+
+    /// Total tokens reported for `agent_id`.
+    pub fn reported_tokens(&self, agent_id: &str) -> i64 {
+        self.state()
+            .usage
+            .iter()
+            .filter(|u| u.agent_id == agent_id)
+            .map(|u| u.tokens_used)
+            .sum()
+    }
+
+    /// Panics unless `method` was called at least once.
+    pub fn assert_called(&self, method: &str) {
+        let calls = self.calls();
+        assert!(
+            calls.iter().any(|c| c == method),
+            "expected a call to {method}, got {calls:?}"
+        );
+    }
+
+    /// Panics if `method` was called.
+    pub fn assert_not_called(&self, method: &str) {
+        let count = self.call_count(method);
+        assert!(count == 0, "expected no call to {method}, got {count}");
+    }
+
+    /// Panics unless `report_usage` was called for `agent_id` with exactly
+    /// `tokens` tokens.
+    pub fn assert_usage_reported(&self, agent_id: &str, tokens: i64) {
+        let usage = self.usage_reports();
+        assert!(
+            usage
+                .iter()
+                .any(|u| u.agent_id == agent_id && u.tokens_used == tokens),
+            "expected report_usage for {agent_id} with {tokens} tokens, got {:?}",
+            usage
+                .iter()
+                .map(|u| (&u.agent_id, u.tokens_used))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    /// Locks the store and records `method` as called.
+    fn record(&self, method: &str) -> MutexGuard<'_, State> {
+        let mut state = self.state();
+        state.calls.push(method.to_string());
+        state
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl ControlPlane for MockAgentPlatformClient {
+    async fn create_org(&mut self, name: &str) -> Result<Org> {
+        let mut state = self.record("create_org");
+        require("name", name)?;
+        let org = Org {
+            org_id: state.id("org"),
+            name: name.to_string(),
+            metadata: HashMap::new(),
+            deleted: false,
+            deleted_at: None,
+        };
+        state.orgs.push(org.clone());
+        Ok(org)
+    }
+
+    async fn get_org(&mut self, org_id: &str) -> Result<Org> {
+        self.record("get_org").org(org_id).cloned()
+    }
+
+    async fn list_orgs(&mut self) -> Result<Vec<Org>> {
+        Ok(self.record("list_orgs").orgs.clone())
+    }
+
+    async fn delete_org_with_options(&mut self, org_id: &str, cascade: bool) -> Result<bool> {
+        let mut state = self.record("delete_org");
+        state.org(org_id)?;
+        let active = state
+            .agents
+            .iter()
+            .filter(|a| a.org_id == org_id && a.active)
+            .count();
+        if !cascade && active > 0 {
+            return Err(SdkError::OrgNotEmpty(format!(
+                "{org_id}: {active} active agents"
+            )));
+        }
+        state.orgs.retain(|o| o.org_id != org_id);
+        state.agents.retain(|a| a.org_id != org_id);
+        state.policies.retain(|(org, _), _| org != org_id);
+        state.budgets.retain(|(org, _), _| org != org_id);
+        Ok(true)
+    }
+
+    async fn register_agent(
+        &mut self,
+        org_id: &str,
+        name: &str,
+        role: impl Into<Role> + Send,
+        delegated_user_id: Option<&str>,
+    ) -> Result<RegisteredAgent> {
+        let spec = AgentSpec {
+            name: name.to_string(),
+            role: role.into(),
+            delegated_user_id: delegated_user_id.map(str::to_string),
+            claims: HashMap::new(),
+        };
+        let mut state = self.record("register_agent");
+        require("org_id", org_id)?;
+        validate_spec(&spec)?;
+        state.org(org_id)?;
+        Ok(state.register(org_id, spec))
+    }
+
+    async fn register_agents(
+        &mut self,
+        org_id: &str,
+        specs: &[AgentSpec],
+    ) -> Result<Vec<RegisteredAgent>> {
+        let mut state = self.record("register_agents");
+        require("org_id", org_id)?;
+        state.org(org_id)?;
+        for (i, spec) in specs.iter().enumerate() {
+            validate_spec(spec).map_err(|e| match e {
+                SdkError::InvalidInput(m) => SdkError::InvalidInput(format!("agents[{i}]: {m}")),
+                e => e,
+            })?;
+        }
+        Ok(specs
+            .iter()
+            .map(|spec| state.register(org_id, spec.clone()))
+            .collect())
+    }
+
+    async fn get_agent(&mut self, org_id: &str, agent_id: &str) -> Result<Agent> {
+        self.record("get_agent")
+            .agent_mut(org_id, agent_id)
+            .map(|a| a.clone())
+    }
+
+    async fn list_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        let state = self.record("list_agents");
+        state.org(org_id)?;
+        Ok(state
+            .agents
+            .iter()
+            .filter(|a| a.org_id == org_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn list_all_agents(&mut self, org_id: &str) -> Result<Vec<Agent>> {
+        let state = self.record("list_all_agents");
+        state.org(org_id)?;
+        Ok(state
+            .agents
+            .iter()
+            .filter(|a| a.org_id == org_id)
+            .cloned()
+            .collect())
+    }
+
+    async fn deactivate_agent(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        reason: Option<&str>,
+    ) -> Result<bool> {
+        let mut state = self.record("deactivate_agent");
+        let agent = state.agent_mut(org_id, agent_id)?;
+        let was_active = agent.active;
+        if was_active {
+            agent.active = false;
+            agent.deactivation_reason = reason.map(str::to_string);
+            agent.deactivated_at = Some(SystemTime::now());
+        }
+        Ok(was_active)
+    }
+
+    async fn set_policy_from(&mut self, policy: PolicyBuilder) -> Result<String> {
+        let mut state = self.record("set_policy");
+        require("org_id", &policy.org_id)?;
+        non_negative("token_limit", policy.token_limit)?;
+        non_negative("timeout_seconds", policy.timeout_seconds.into())?;
+        let key = (policy.org_id.clone(), policy.agent_id.clone());
+        let policy_id = match state.policies.get(&key) {
+            Some(existing) => existing.policy_id.clone(),
+            None => state.id("policy"),
+        };
+        let stored = Policy {
+            policy_id: policy_id.clone(),
+            org_id: policy.org_id,
+            agent_id: policy.agent_id,
+            tools: policy.tools,
+            token_limit: or_default(policy.token_limit, DEFAULT_POLICY_TOKENS),
+            execution_timeout_seconds: or_default(policy.timeout_seconds, DEFAULT_TIMEOUT_SECONDS),
+            mode: policy.mode,
+        };
+        state.policies.insert(key, stored);
+        Ok(policy_id)
+    }
+
+    async fn list_policies(&mut self, org_id: &str) -> Result<Vec<Policy>> {
+        let state = self.record("list_policies");
+        let mut policies: Vec<Policy> = state
+            .policies
+            .values()
+            .filter(|p| p.org_id == org_id)
+            .cloned()
+            .collect();
+        policies.sort_by(|a, b| a.policy_id.cmp(&b.policy_id));
+        Ok(policies)
+    }
+
+    async fn get_effective_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+    ) -> Result<EffectivePolicy> {
+        let state = self.record("get_effective_policy");
+        let level = |agent: Option<&str>| {
+            state
+                .policies
+                .get(&(org_id.to_string(), agent.map(str::to_string)))
+                .map(|p| p.policy_id.clone())
+        };
+        let (org_policy_id, agent_policy_id) = (level(None), level(Some(agent_id)));
+        let policy = state
+            .effective_policy(org_id, agent_id)
+            .ok_or_else(|| SdkError::NotFound("no policy found for org/agent".into()))?;
+        Ok(EffectivePolicy {
+            agent_id: agent_id.to_string(),
+            tools: policy.tools,
+            token_limit: policy.token_limit,
+            execution_timeout_seconds: policy.execution_timeout_seconds,
+            org_policy_id,
+            agent_policy_id,
+        })
+    }
+
+    async fn evaluate_policy(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tool_name: &str,
+        estimated_tokens: i64,
+    ) -> Result<PolicyDecision> {
+        let state = self.record("evaluate_policy");
+        Ok(state.decide(org_id, agent_id, tool_name, estimated_tokens))
+    }
+
+    async fn evaluate_policies(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        tools: &[(&str, i64)],
+    ) -> Result<Vec<PolicyDecision>> {
+        let state = self.record("evaluate_policies");
+        Ok(tools
+            .iter()
+            .map(|(tool_name, tokens)| state.decide(org_id, agent_id, tool_name, *tokens))
+            .collect())
+    }
+
+    async fn set_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+    ) -> Result<BudgetInfo> {
+        self.set_budget_with_warning(org_id, agent_id, token_limit, reset_period_days, None)
+            .await
+    }
+
+    async fn set_budget_with_warning(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        token_limit: i64,
+        reset_period_days: i32,
+        warning_threshold_percent: Option<u8>,
+    ) -> Result<BudgetInfo> {
+        let mut state = self.record("set_budget");
+        require("org_id", org_id)?;
+        non_negative("token_limit", token_limit)?;
+        non_negative("reset_period_days", reset_period_days.into())?;
+        if let Some(p) = warning_threshold_percent.filter(|p| *p > 100) {
+            return Err(SdkError::InvalidInput(format!(
+                "warning_threshold_percent must be at most 100, got {p}"
+            )));
+        }
+        let warning_threshold_percent = warning_threshold_percent.filter(|p| *p > 0);
+        let token_limit = or_default(token_limit, DEFAULT_BUDGET_TOKENS);
+        let reset_period_days = or_default(reset_period_days, DEFAULT_RESET_DAYS);
+        let budget = match state.budget_mut(org_id, agent_id) {
+            Some(existing) => {
+                existing.token_limit = token_limit;
+                existing.tokens_remaining = (token_limit - existing.tokens_used).max(0);
+                existing.reset_period_days = reset_period_days;
+                existing.warning_threshold_percent = warning_threshold_percent;
+                existing.clone()
+            }
+            None => {
+                let budget = BudgetInfo {
+                    budget_id: state.id("budget"),
+                    token_limit,
+                    tokens_used: 0,
+                    tokens_remaining: token_limit,
+                    tool_invocations: 0,
+                    reset_period_days,
+                    warning_threshold_percent,
+                };
+                let key = (org_id.to_string(), agent_id.map(str::to_string));
+                state.budgets.insert(key, budget.clone());
+                budget
+            }
+        };
+        Ok(budget)
+    }
+
+    async fn get_budget(&mut self, org_id: &str, agent_id: Option<&str>) -> Result<BudgetInfo> {
+        self.record("get_budget")
+            .budget(org_id, agent_id)
+            .cloned()
+            .ok_or_else(|| SdkError::NotFound(format!("budget for {org_id}")))
+    }
+
+    async fn check_budget(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        estimated_tokens: i64,
+    ) -> Result<BudgetCheck> {
+        let state = self.record("check_budget");
+        let budgets: Vec<(&str, &BudgetInfo)> = [("agent", Some(agent_id)), ("org", None)]
+            .into_iter()
+            .filter_map(|(level, id)| state.budget(org_id, id).map(|b| (level, b)))
+            .collect();
+        let utilization = budgets
+            .iter()
+            .map(|(_, b)| used_share(b, estimated_tokens))
+            .fold(0.0, f64::max);
+        if let Some((level, b)) = budgets
+            .iter()
+            .find(|(_, b)| b.tokens_remaining < estimated_tokens)
+        {
+            let remaining = b.tokens_remaining;
+            return Ok(BudgetCheck {
+                allowed: false,
+                tokens_remaining: remaining,
+                reason: format!(
+                    "{level} budget exhausted: {remaining} remaining, {estimated_tokens} requested"
+                ),
+                reservation_id: None,
+                utilization,
+                warning: None,
+            });
+        }
+        let warning = budgets.iter().find_map(|(level, b)| {
+            let threshold = b.warning_threshold_percent?;
+            let percent = used_share(b, estimated_tokens) * 100.0;
+            (percent >= threshold.into()).then(|| {
+                format!(
+                    "{level} budget {percent:.0}% used, past its {threshold}% warning threshold"
+                )
+            })
+        });
+        Ok(BudgetCheck {
+            allowed: true,
+            tokens_remaining: budgets
+                .iter()
+                .map(|(_, b)| b.tokens_remaining)
+                .min()
+                .unwrap_or(0),
+            reason: "budget_ok".into(),
+            reservation_id: None,
+            utilization,
+            warning,
+        })
+    }
+
+    async fn set_rate_limit(
+        &mut self,
+        org_id: &str,
+        agent_id: Option<&str>,
+        max_per_minute: i32,
+    ) -> Result<RateLimit> {
+        let mut state = self.record("set_rate_limit");
+        require("org_id", org_id)?;
+        non_negative("max_per_minute", max_per_minute.into())?;
+        let key = (org_id.to_string(), agent_id.map(str::to_string));
+        if max_per_minute == 0 {
+            state.rate_limits.remove(&key);
+            state.windows.remove(&key);
+        } else {
+            state.rate_limits.insert(key, max_per_minute);
+        }
+        Ok(RateLimit { max_per_minute })
+    }
+
+    async fn check_rate_limit(&mut self, org_id: &str, agent_id: &str) -> Result<RateLimitCheck> {
+        let mut state = self.record("check_rate_limit");
+        let now = state.now();
+        let keys = [
+            (org_id.to_string(), Some(agent_id.to_string())),
+            (org_id.to_string(), None),
+        ];
+        // (key, limit, calls left, window end) of each limit that applies.
+        let mut limits = Vec::new();
+        for key in keys {
+            let Some(&max) = state.rate_limits.get(&key) else {
+                continue;
+            };
+            let window = state.windows.entry(key.clone()).or_insert((now, 0));
+            if now >= window.0 + RATE_LIMIT_WINDOW {
+                *window = (now, 0);
+            }
+            limits.push((key, max, max - window.1, window.0 + RATE_LIMIT_WINDOW));
+        }
+        let Some(tightest) = limits.iter().min_by_key(|l| l.2).cloned() else {
+            return Ok(RateLimitCheck {
+                allowed: true,
+                remaining: None,
+                reset_at: None,
+                reason: String::new(),
+            });
+        };
+        let allowed = tightest.2 > 0;
+        if allowed {
+            for (key, ..) in &limits {
+                if let Some(window) = state.windows.get_mut(key) {
+                    window.1 += 1;
+                }
+            }
+        }
+        Ok(RateLimitCheck {
+            allowed,
+            remaining: Some((tightest.2 - i32::from(allowed)).max(0)),
+            reset_at: Some(tightest.3),
+            reason: if allowed {
+                String::new()
+            } else {
+                format!("rate limit of {} calls per minute reached", tightest.1)
+            },
+        })
+    }
+
+    async fn report_usage(
+        &mut self,
+        org_id: &str,
+        agent_id: &str,
+        execution_id: &str,
+        tokens_used: i64,
+        tool_invocations: i32,
+        duration_ms: i64,
+    ) -> Result<i64> {
+        let mut state = self.record("report_usage");
+        let request = crate::proto::ReportUsageRequest {
+            org_id: org_id.to_string(),
+            agent_id: agent_id.to_string(),
+            execution_id: execution_id.to_string(),
+            tokens_used,
+            tool_invocations,
+            execution_duration_ms: duration_ms,
+            ..Default::default()
+        };
+        let key = idempotency::scoped_key("ReportUsage", &request);
+        if let Some(remaining) = key.as_ref().and_then(|k| state.reported.get(k)) {
+            return Ok(*remaining);
+        }
+        state.usage.push(UsageReport {
+            org_id: org_id.to_string(),
+            agent_id: agent_id.to_string(),
+            execution_id: execution_id.to_string(),
+            tokens_used,
+            tool_invocations,
+            duration_ms,
+            tool_name: None,
+            metadata: HashMap::new(),
+            reported_at: Some(SystemTime::now()),
+            model: None,
+        });
+        let mut remaining = 0;
+        for level in [Some(agent_id), None] {
+            if let Some(budget) = state.budget_mut(org_id, level) {
+                budget.tokens_used += tokens_used;
+                budget.tool_invocations += tool_invocations;
+                budget.tokens_remaining = (budget.token_limit - budget.tokens_used).max(0);
+                if level.is_some() {
+                    remaining = budget.tokens_remaining;
+                }
+            }
+        }
+        if let Some(key) = key {
+            state.reported.insert(key, remaining);
+        }
+        Ok(remaining)
+    }
+}
+
+/// Share of `budget`'s limit used once `estimated_tokens` more are spent.
+fn used_share(budget: &BudgetInfo, estimated_tokens: i64) -> f64 {
+    if budget.token_limit == 0 {
+        return 0.0;
+    }
+    (budget.tokens_used + estimated_tokens) as f64 / budget.token_limit as f64
+}
+
+fn validate_spec(spec: &AgentSpec) -> Result<()> {
+    require("name", &spec.name)?;
+    require("role", spec.role.as_str())
+}
+
+fn or_default<T: Default + PartialEq>(value: T, default: T) -> T {
+    if value == T::default() {
+        default
+    } else {
+        value
+    }
+}
+
+/// Agent rules replace org rules for the same tool, except org denies; the
+/// tighter of the two limits applies.
+fn merge(org: &Policy, agent: &Policy) -> Policy {
+    let org_denied = |name: &str| {
+        org.tools
+            .iter()
+            .any(|t| t.tool_name == name && t.effect == PolicyEffect::Deny)
+    };
+    let mut tools = org.tools.clone();
+    for tool in agent.tools.iter().filter(|t| !org_denied(&t.tool_name)) {
+        tools.retain(|t| t.tool_name != tool.tool_name);
+        tools.push(tool.clone());
+    }
+    Policy {
+        policy_id: agent.policy_id.clone(),
+        org_id: org.org_id.clone(),
+        agent_id: agent.agent_id.clone(),
+        tools,
+        token_limit: org.token_limit.min(agent.token_limit),
+        execution_timeout_seconds: org
+            .execution_timeout_seconds
+            .min(agent.execution_timeout_seconds),
+        mode: agent.mode,
+    }
+}
+
+fn evaluate(policy: &Policy, tool_name: &str, estimated_tokens: i64) -> PolicyDecision {
+    let id = Some(policy.policy_id.clone());
+    if estimated_tokens > policy.token_limit {
+        let reason = format!(
+            "estimated tokens {estimated_tokens} exceeds limit {}",
+            policy.token_limit
+        );
+        return decision(false, reason, id);
+    }
+    let rule = |name: &str, effect: PolicyEffect| {
+        policy
+            .tools
+            .iter()
+            .find(|t| t.tool_name == name && t.effect == effect)
+    };
+    let matched = [
+        (
+            tool_name,
+            PolicyEffect::Deny,
+            format!("tool '{tool_name}' explicitly denied"),
+        ),
+        (
+            tool_name,
+            PolicyEffect::Allow,
+            format!("tool '{tool_name}' explicitly allowed"),
+        ),
+        ("*", PolicyEffect::Allow, "wildcard allow".to_string()),
+    ]
+    .into_iter()
+    .find_map(|(name, effect, reason)| rule(name, effect).map(|t| (t, reason)));
+    match matched {
+        Some((tool, reason)) => matched_decision(tool, reason, id),
+        None => decision(
+            false,
+            format!("tool '{tool_name}' not in allowed list (default deny)"),
+            id,
+        ),
+    }
+}
+
+fn matched_decision(
+    tool: &ToolPermission,
+    reason: String,
+    policy_id: Option<String>,
+) -> PolicyDecision {
+    PolicyDecision {
+        matched_tool: Some(tool.tool_name.clone()),
+        effect: Some(tool.effect.clone()),
+        applied_constraints: tool.parameters_constraint.clone(),
+        ..decision(tool.effect == PolicyEffect::Allow, reason, policy_id)
+    }
+}
+
+fn decision(allowed: bool, reason: String, policy_id: Option<String>) -> PolicyDecision {
+    PolicyDecision {
+        allowed,
+        reason,
+        policy_id,
+        warnings: Vec::new(),
+        shadow_would_deny: false,
+        cache_ttl: None,
+        matched_tool: None,
+        effect: None,
+        applied_constraints: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PolicyPrecedence;
+
+    #[tokio::test]
+    async fn delete_empty_org() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap();
+        assert!(mock.delete_org(&org.org_id).await.unwrap());
+        assert!(matches!(
+            mock.get_org(&org.org_id).await,
+            Err(SdkError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn delete_org_without_cascade_refuses_active_agents() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap();
+        let agent = mock
+            .register_agent(&org.org_id, "bot", "executor", None)
+            .await
+            .unwrap()
+            .agent;
+        assert!(matches!(
+            mock.delete_org(&org.org_id).await,
+            Err(SdkError::OrgNotEmpty(_))
+        ));
+        assert!(mock.get_org(&org.org_id).await.is_ok());
+
+        mock.deactivate_agent(&org.org_id, &agent.agent_id, None)
+            .await
+            .unwrap();
+        assert!(mock.delete_org(&org.org_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn cascading_delete_removes_agents_and_budgets() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap();
+        mock.register_agent(&org.org_id, "bot", "executor", None)
+            .await
+            .unwrap();
+        mock.set_budget(&org.org_id, None, 1_000, 30).await.unwrap();
+        assert!(mock
+            .delete_org_with_options(&org.org_id, true)
+            .await
+            .unwrap());
+        assert!(mock.get_org(&org.org_id).await.is_err());
+        assert!(mock.get_budget(&org.org_id, None).await.is_err());
+        assert!(mock.state().agents.is_empty());
+    }
+
+    async fn org_with_agent(mock: &mut MockAgentPlatformClient) -> (String, String) {
+        let org = mock.create_org("acme").await.unwrap().org_id;
+        let agent = mock
+            .register_agent(&org, "bot", "executor", None)
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        (org, agent)
+    }
+
+    #[tokio::test]
+    async fn agent_deny_overrides_org_allow() {
+        let mut mock = MockAgentPlatformClient::new();
+        let (org, agent) = org_with_agent(&mut mock).await;
+        mock.set_policy_from(PolicyBuilder::new(&org).allow("shell"))
+            .await
+            .unwrap();
+        mock.set_policy_from(PolicyBuilder::new(&org).for_agent(&agent).deny("shell"))
+            .await
+            .unwrap();
+
+        let effective = mock.get_effective_policy(&org, &agent).await.unwrap();
+        assert_eq!(effective.precedence(), PolicyPrecedence::AgentOverOrg);
+        let decision = mock
+            .evaluate_policy(&org, &agent, "shell", 0)
+            .await
+            .unwrap();
+        assert!(!decision.allowed);
+        assert_eq!(decision.policy_id, effective.agent_policy_id);
+    }
+
+    #[tokio::test]
+    async fn org_deny_overrides_agent_allow() {
+        let mut mock = MockAgentPlatformClient::new();
+        let (org, agent) = org_with_agent(&mut mock).await;
+        mock.set_policy_from(PolicyBuilder::new(&org).deny("shell"))
+            .await
+            .unwrap();
+        mock.set_policy_from(PolicyBuilder::new(&org).for_agent(&agent).allow("shell"))
+            .await
+            .unwrap();
+
+        let effective = mock.get_effective_policy(&org, &agent).await.unwrap();
+        assert_eq!(effective.precedence(), PolicyPrecedence::AgentOverOrg);
+        let shell = effective.tools.iter().find(|t| t.tool_name == "shell");
+        assert_eq!(shell.map(|t| &t.effect), Some(&PolicyEffect::Deny));
+        let decision = mock
+            .evaluate_policy(&org, &agent, "shell", 0)
+            .await
+            .unwrap();
+        assert!(!decision.allowed);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_resets_after_the_window() {
+        let mut mock = MockAgentPlatformClient::new();
+        let (org, agent) = org_with_agent(&mut mock).await;
+        mock.set_rate_limit(&org, Some(&agent), 2).await.unwrap();
+
+        let first = mock.check_rate_limit(&org, &agent).await.unwrap();
+        let second = mock.check_rate_limit(&org, &agent).await.unwrap();
+        assert_eq!((first.allowed, first.remaining), (true, Some(1)));
+        assert_eq!((second.allowed, second.remaining), (true, Some(0)));
+        let refused = mock.check_rate_limit(&org, &agent).await.unwrap();
+        assert_eq!((refused.allowed, refused.remaining), (false, Some(0)));
+        assert_eq!(refused.reset_at, first.reset_at);
+        let err = refused.require_allowed().unwrap_err();
+        assert!(
+            matches!(err, SdkError::RateLimited { reset_at: Some(t), .. } if Some(t) == first.reset_at)
+        );
+
+        mock.advance(Duration::from_secs(59));
+        assert!(!mock.check_rate_limit(&org, &agent).await.unwrap().allowed);
+        mock.advance(Duration::from_secs(1));
+        let renewed = mock.check_rate_limit(&org, &agent).await.unwrap();
+        assert_eq!((renewed.allowed, renewed.remaining), (true, Some(1)));
+        assert!(renewed.reset_at > first.reset_at);
+    }
+
+    #[tokio::test]
+    async fn org_rate_limit_covers_every_agent() {
+        let mut mock = MockAgentPlatformClient::new();
+        let (org, agent) = org_with_agent(&mut mock).await;
+        let other = mock
+            .register_agent(&org, "other", "executor", None)
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        let unlimited = mock.check_rate_limit(&org, &agent).await.unwrap();
+        assert_eq!((unlimited.allowed, unlimited.remaining), (true, None));
+
+        mock.set_rate_limit(&org, None, 1).await.unwrap();
+        mock.set_rate_limit(&org, Some(&agent), 5).await.unwrap();
+        assert!(mock.check_rate_limit(&org, &agent).await.unwrap().allowed);
+        assert!(!mock.check_rate_limit(&org, &other).await.unwrap().allowed);
+
+        mock.set_rate_limit(&org, None, 0).await.unwrap();
+        let check = mock.check_rate_limit(&org, &other).await.unwrap();
+        assert_eq!((check.allowed, check.remaining), (true, None));
+    }
+
+    #[tokio::test]
+    async fn failed_batch_registers_no_agents() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap().org_id;
+        let spec = |name: &str| AgentSpec {
+            name: name.to_string(),
+            role: Role::Executor,
+            delegated_user_id: None,
+            claims: HashMap::new(),
+        };
+        let err = mock
+            .register_agents(&org, &[spec("a"), spec(""), spec("c")])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, SdkError::InvalidInput(m) if m.starts_with("agents[1]")),
+            "{err:?}"
+        );
+        assert!(mock.list_agents(&org).await.unwrap().is_empty());
+
+        let registered = mock
+            .register_agents(&org, &[spec("a"), spec("b")])
+            .await
+            .unwrap();
+        let names: Vec<_> = registered.iter().map(|r| r.agent.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn budget_warning_starts_at_its_threshold() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap().org_id;
+        let agent = mock
+            .register_agent(&org, "bot", "executor", None)
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        mock.set_budget_with_warning(&org, Some(&agent), 1_000, 30, Some(90))
+            .await
+            .unwrap();
+
+        let below = mock.check_budget(&org, &agent, 899).await.unwrap();
+        assert!(below.allowed);
+        assert_eq!(below.utilization, 0.899);
+        assert_eq!(below.warning, None);
+
+        let at = mock.check_budget(&org, &agent, 900).await.unwrap();
+        assert!(at.allowed);
+        assert_eq!(at.utilization, 0.9);
+        assert_eq!(
+            at.warning.as_deref(),
+            Some("agent budget 90% used, past its 90% warning threshold")
+        );
+
+        // Over the limit the check is denied rather than warned about.
+        let over = mock.check_budget(&org, &agent, 1_001).await.unwrap();
+        assert!(!over.allowed);
+        assert_eq!(over.utilization, 1.001);
+        assert_eq!(over.warning, None);
+    }
+
+    #[tokio::test]
+    async fn validation_matches_the_client() {
+        let mut mock = MockAgentPlatformClient::new();
+        let org = mock.create_org("acme").await.unwrap().org_id;
+        let agent = mock
+            .register_agent(&org, "bot", "executor", None)
+            .await
+            .unwrap()
+            .agent
+            .agent_id;
+        // Ids count per kind.
+        assert_eq!((org.as_str(), agent.as_str()), ("org-1", "agent-1"));
+
+        let err = mock
+            .set_policy_from(PolicyBuilder::new(&org).timeout(-1))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: timeout_seconds must not be negative, got -1"
+        );
+        let err = mock
+            .set_budget_with_warning(&org, None, 1_000, 30, Some(101))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid input: warning_threshold_percent must be at most 100, got 101"
+        );
+        // Like the client, negative usage is passed on rather than rejected.
+        mock.report_usage(&org, &agent, "exec-1", -5, 0, 0)
+            .await
+            .unwrap();
+    }
+}
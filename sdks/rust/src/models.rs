@@ -1,48 +1,1319 @@
+//! Plain data types returned by [`AgentPlatformClient`](crate::AgentPlatformClient).
+//!
+//! Models are often persisted as JSON and read back by services on other SDK
+//! versions, so they stay forward and backward compatible: unknown keys are
+//! ignored (no `deny_unknown_fields`), and fields added to an existing model
+//! carry `#[serde(default)]` so JSON written before they existed still loads.
+//!
+#![cfg_attr(feature = "serde", doc = "```")]
+#![cfg_attr(not(feature = "serde"), doc = "```ignore")]
+//! use agent_platform_sdk::models::{Agent, PolicyDecision};
+//!
+//! // Written by a newer SDK that knows about more fields.
+//! let json = r#"{
+//!     "agent_id": "a1", "org_id": "o1", "name": "bot", "role": "executor",
+//!     "active": true, "delegated_user_id": null, "added_later": {"x": 1}
+//! }"#;
+//! let agent: Agent = serde_json::from_str(json).unwrap();
+//! let again: Agent = serde_json::from_str(&serde_json::to_string(&agent).unwrap()).unwrap();
+//! assert_eq!(again.agent_id, "a1");
+//!
+//! // Written by an older SDK, before warnings and cache TTLs existed.
+//! let old = r#"{"allowed": true, "reason": "ok", "policy_id": null}"#;
+//! let decision: PolicyDecision = serde_json::from_str(old).unwrap();
+//! assert!(decision.warnings.is_empty() && decision.cache_ttl.is_none());
+//! assert!(decision.matched_tool.is_none() && decision.effect.is_none());
+//! ```
+//!
+//! Field names are part of that contract. The exact shapes of the core models
+//! are pinned here, so renaming a field fails the doctests:
+//!
+#![cfg_attr(feature = "serde", doc = "```")]
+#![cfg_attr(not(feature = "serde"), doc = "```ignore")]
+//! use agent_platform_sdk::models::{Agent, Org, Policy};
+//! use serde_json::{json, Value};
+//!
+//! let org = Org::from_json(r#"{"org_id": "o1", "name": "acme"}"#).unwrap();
+//! let shape: Value = serde_json::from_str(&org.to_json().unwrap()).unwrap();
+//! assert_eq!(shape, json!({
+//!     "org_id": "o1", "name": "acme", "metadata": {},
+//!     "deleted": false, "deleted_at": null
+//! }));
+//!
+//! // Delegation omitted on input reads as `None` and is written as `null`.
+//! let agent = Agent::from_json(
+//!     r#"{"agent_id": "a1", "org_id": "o1", "name": "bot", "role": "executor", "active": true}"#,
+//! ).unwrap();
+//! assert_eq!(agent.delegated_user_id, None);
+//! let shape: Value = serde_json::from_str(&agent.to_json().unwrap()).unwrap();
+//! assert_eq!(shape, json!({
+//!     "agent_id": "a1", "org_id": "o1", "name": "bot", "role": "executor",
+//!     "active": true, "delegated_user_id": null, "delegate_is_agent": false,
+//!     "token_claims": {}, "deactivation_reason": null, "deactivated_at": null,
+//!     "deleted": false, "deleted_at": null
+//! }));
+//!
+//! // Present, it round-trips as a plain string.
+//! let delegated = Agent { delegated_user_id: Some("u1".into()), ..agent };
+//! let shape: Value = serde_json::from_str(&delegated.to_json().unwrap()).unwrap();
+//! assert_eq!(shape["delegated_user_id"], "u1");
+//! assert_eq!(Agent::from_json(&delegated.to_json().unwrap()).unwrap(), delegated);
+//!
+//! let policy = Policy::from_json(r#"{
+//!     "policy_id": "p1", "org_id": "o1", "agent_id": null,
+//!     "tools": [{"tool_name": "search", "effect": "allow"}],
+//!     "token_limit": 1000, "execution_timeout_seconds": 30
+//! }"#).unwrap();
+//! let shape: Value = serde_json::from_str(&policy.to_json().unwrap()).unwrap();
+//! assert_eq!(shape, json!({
+//!     "policy_id": "p1", "org_id": "o1", "agent_id": null,
+//!     "tools": [{"tool_name": "search", "effect": "allow", "parameters_constraint": null}],
+//!     "token_limit": 1000, "execution_timeout_seconds": 30, "mode": "enforce"
+//! }));
+//! ```
+
+use crate::error::SdkError;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::{Duration, SystemTime};
+
+/// `to_json` / `from_json` in the stable model format, for consumers that
+/// don't use serde directly.
+#[cfg(feature = "serde")]
+macro_rules! json_helpers {
+    ($($t:ty),* $(,)?) => {$(
+        impl $t {
+            pub fn to_json(&self) -> crate::error::Result<String> {
+                Ok(serde_json::to_string(self)?)
+            }
+
+            pub fn from_json(json: &str) -> crate::error::Result<Self> {
+                Ok(serde_json::from_str(json)?)
+            }
+        }
+    )*};
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg(feature = "serde")]
+json_helpers!(Org, Agent, Policy, PolicyDecision, BudgetInfo);
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Org {
     pub org_id: String,
     pub name: String,
+    /// Free-form org attributes such as billing tier or region. Non-string
+    /// values set by other clients are JSON-encoded.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: HashMap<String, String>,
+    /// Set on tombstones of recently deleted orgs; see
+    /// [`list_orgs_with_tombstones`](crate::AgentPlatformClient::list_orgs_with_tombstones).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deleted: bool,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub deleted_at: Option<SystemTime>,
+}
+
+/// One org to create with `create_orgs`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrgSpec {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: HashMap<String, String>,
+}
+
+/// One agent to register with `register_agents`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentSpec {
+    pub name: String,
+    pub role: Role,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub delegated_user_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub claims: HashMap<String, String>,
+}
+
+/// Everything that deleting an org would remove.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DeleteImpact {
+    pub agent_count: i32,
+    pub policy_count: i32,
+    pub budget_count: i32,
+    pub usage_record_count: i64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What an agent is for. Serialized as its wire string; a role this SDK
+/// does not know is kept verbatim in `Other`, so it survives a round trip.
+/// Build roles from strings with `From`, which maps known names to their
+/// variants, rather than constructing `Other` directly.
+///
+#[cfg_attr(feature = "serde", doc = "```")]
+#[cfg_attr(not(feature = "serde"), doc = "```ignore")]
+/// use agent_platform_sdk::models::Role;
+///
+/// for role in [Role::Executor, Role::Planner, Role::Reviewer, Role::Admin] {
+///     assert_eq!(Role::from(role.as_str()), role);
+/// }
+/// let custom: Role = serde_json::from_str(r#""triager""#).unwrap();
+/// assert_eq!(custom, Role::Other("triager".into()));
+/// assert_eq!(serde_json::to_string(&custom).unwrap(), r#""triager""#);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub enum Role {
+    /// The server default.
+    #[default]
+    Executor,
+    Planner,
+    Reviewer,
+    Admin,
+    Other(String),
+}
+
+impl Role {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Role::Executor => "executor",
+            Role::Planner => "planner",
+            Role::Reviewer => "reviewer",
+            Role::Admin => "admin",
+            Role::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for Role {
+    fn from(s: &str) -> Self {
+        match s {
+            "executor" => Role::Executor,
+            "planner" => Role::Planner,
+            "reviewer" => Role::Reviewer,
+            "admin" => Role::Admin,
+            _ => Role::Other(s.to_string()),
+        }
+    }
+}
+
+impl From<String> for Role {
+    fn from(s: String) -> Self {
+        match Role::from(s.as_str()) {
+            Role::Other(_) => Role::Other(s),
+            known => known,
+        }
+    }
+}
+
+impl From<Role> for String {
+    fn from(role: Role) -> Self {
+        match role {
+            Role::Other(s) => s,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Agent {
     pub agent_id: String,
     pub org_id: String,
     pub name: String,
-    pub role: String,
+    pub role: Role,
     pub active: bool,
     pub delegated_user_id: Option<String>,
+    /// `delegated_user_id` names another agent in the same org rather than
+    /// a user; see
+    /// [`effective_user`](crate::AgentPlatformClient::effective_user).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub delegate_is_agent: bool,
+    /// Claims set at registration; see `register_agent_with_claims`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub token_claims: HashMap<String, String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deactivation_reason: Option<String>,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub deactivated_at: Option<SystemTime>,
+    /// Set on tombstones of recently deleted agents; see
+    /// [`list_agents_with_tombstones`](crate::AgentPlatformClient::list_agents_with_tombstones).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deleted: bool,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub deleted_at: Option<SystemTime>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Discovery-safe summary of an agent, e.g. for a directory listing. Unlike
+/// [`Agent`] it carries no claims, budgets, or policy internals.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentCard {
+    pub agent_id: String,
+    pub name: String,
+    pub role: Role,
+    /// Tools the agent's effective policy allows.
+    pub capabilities: Vec<String>,
+}
+
+/// The result of `register_agent`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RegisteredAgent {
+    pub agent: Agent,
+    /// Bootstrap credential minted for the new agent. It is returned only in
+    /// this response and cannot be fetched again later, only rotated, so
+    /// store it before dropping this value.
+    pub initial_credential: Option<AgentCredential>,
+}
+
+/// A token an agent authenticates with. `Debug` output omits the token.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentCredential {
+    pub credential_id: String,
+    pub token: String,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub expires_at: Option<SystemTime>,
+}
+
+impl std::fmt::Debug for AgentCredential {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentCredential")
+            .field("credential_id", &self.credential_id)
+            .field("token", &"<redacted>")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Whether a credential is currently accepted, from `validate_credential`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CredentialStatus {
+    pub valid: bool,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub expires_at: Option<SystemTime>,
+    /// Why the credential is invalid, e.g. expired or rotated.
+    pub reason: Option<String>,
+}
+
+/// Differences between a locally cached agent set and the server's.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ReconcileReport {
+    /// On the server but not in the local set.
+    pub new: Vec<Agent>,
+    /// Ids in the local set that no longer exist on the server.
+    pub deleted: Vec<String>,
+    /// Server versions of agents whose local copy differs.
+    pub changed: Vec<Agent>,
+}
+
+impl ReconcileReport {
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.deleted.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PolicyDecision {
     pub allowed: bool,
     pub reason: String,
     pub policy_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warnings: Vec<String>,
+    /// True when a shadow-mode policy would have denied this call. `allowed`
+    /// still reflects only enforced policies.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shadow_would_deny: bool,
+    /// Server directive for how long this decision may be cached.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cache_ttl: Option<Duration>,
+    /// Tool name of the rule that decided, `"*"` for a wildcard allow.
+    /// `None` when no rule matched and the call fell through to default deny.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub matched_tool: Option<String>,
+    /// Effect of the deciding rule; `None` for default deny.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub effect: Option<PolicyEffect>,
+    /// Parameter constraints of the deciding rule, if it had any.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub applied_constraints: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl PolicyDecision {
+    /// `Ok` if the call is allowed, otherwise [`SdkError::PolicyDenied`]
+    /// naming `tool_name`, for callers that treat a denial as an error.
+    pub fn require_allowed(self, tool_name: &str) -> crate::error::Result<Self> {
+        if self.allowed {
+            return Ok(self);
+        }
+        Err(SdkError::PolicyDenied {
+            tool: Some(tool_name.to_string()),
+            reason: self.reason,
+            policy_id: self.policy_id,
+        })
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BudgetInfo {
     pub budget_id: String,
     pub token_limit: i64,
     pub tokens_used: i64,
     pub tokens_remaining: i64,
     pub tool_invocations: i32,
+    /// Days between automatic resets.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reset_period_days: i32,
+    /// Utilization percentage at which `check_budget` starts warning.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warning_threshold_percent: Option<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Usage to report for one execution. Optional fields default to unset.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UsageRecord {
+    pub execution_id: String,
+    pub tokens_used: i64,
+    pub tool_invocations: i32,
+    pub duration_ms: i64,
+    pub tool_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: HashMap<String, String>,
+    pub reservation_id: Option<String>,
+    /// LLM model the tokens were spent on, for `get_usage_by_model`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub model: Option<String>,
+}
+
+/// A whole agent execution with its per-tool breakdown, for
+/// `report_execution`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Execution {
+    pub execution_id: String,
+    pub tool_calls: Vec<ToolCall>,
+    /// Charged to the budget; normally the sum of the tool call tokens.
+    pub total_tokens: i64,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ToolCall {
+    pub tool_name: String,
+    pub tokens_used: i64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub duration_ms: i64,
+}
+
+/// A single recorded `report_usage` call.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct UsageReport {
+    pub org_id: String,
+    pub agent_id: String,
+    pub execution_id: String,
+    pub tokens_used: i64,
+    pub tool_invocations: i32,
+    pub duration_ms: i64,
+    pub tool_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: HashMap<String, String>,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub reported_at: Option<SystemTime>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub model: Option<String>,
+}
+
+/// Narrows `list_executions`. The default matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionFilter {
+    /// Only reports made within this range, end exclusive.
+    pub window: Option<Range<SystemTime>>,
+    pub tool_name: Option<String>,
+}
+
+/// Per-item outcome of a batched call, in request order.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BatchResult<T> {
+    pub results: Vec<std::result::Result<T, String>>,
+}
+
+impl<T> BatchResult<T> {
+    pub fn all_succeeded(&self) -> bool {
+        self.results.iter().all(|r| r.is_ok())
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = &T> {
+        self.results.iter().filter_map(|r| r.as_ref().ok())
+    }
+
+    /// Failed items as `(request index, error message)`.
+    pub fn failed(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.as_ref().err().map(|e| (i, e.as_str())))
+    }
+}
+
+/// One page of a paginated listing.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Pass to the next call to fetch the following page; `None` on the
+    /// last page.
+    pub next_page_token: Option<String>,
+}
+
+/// How much of the org-level budget is handed out to agent budgets.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AllocationReport {
+    /// Zero when the org has no org-level budget.
+    pub org_limit: i64,
+    pub allocated: i64,
+    pub overallocated: bool,
+    pub per_agent: Vec<AgentAllocation>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentAllocation {
+    pub agent_id: String,
+    pub token_limit: i64,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BudgetCheck {
     pub allowed: bool,
     pub tokens_remaining: i64,
     pub reason: String,
+    /// Hold created by `check_budget_with_reservation`; pass it back in
+    /// [`UsageRecord::reservation_id`] to settle it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reservation_id: Option<String>,
+    /// Share of the budget limit used once the checked tokens are spent,
+    /// from 0.0 up; the higher of the agent and org budgets.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub utilization: f64,
+    /// Early warning that utilization has reached the budget's warning
+    /// threshold. The check is still allowed.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warning: Option<String>,
+}
+
+impl BudgetCheck {
+    /// `Ok` if the tokens fit, otherwise [`SdkError::BudgetExhausted`].
+    pub fn require_allowed(self) -> crate::error::Result<Self> {
+        if self.allowed {
+            return Ok(self);
+        }
+        Err(SdkError::BudgetExhausted {
+            tokens_remaining: Some(self.tokens_remaining),
+            reason: self.reason,
+        })
+    }
+}
+
+/// A historical call whose outcome a backtested policy would change.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BacktestEvent {
+    pub entry_id: String,
+    pub agent_id: String,
+    pub tool_name: String,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub timestamp: Option<SystemTime>,
+    pub originally_allowed: bool,
+    pub would_allow: bool,
+    pub reason: String,
+}
+
+/// A pending policy change that the server applies at `activate_at`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScheduledPolicyChange {
+    pub change_id: String,
+    pub org_id: String,
+    pub agent_id: Option<String>,
+    pub tools: Vec<ToolPermission>,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub activate_at: Option<SystemTime>,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub created_at: Option<SystemTime>,
+}
+
+/// A budget hold that has not yet been settled by a usage report.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Reservation {
+    pub reservation_id: String,
+    pub org_id: String,
+    pub agent_id: String,
+    pub tokens: i64,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub created_at: Option<SystemTime>,
+    /// When the server releases the hold if it hasn't been committed.
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub expires_at: Option<SystemTime>,
+}
+
+/// A budget that just reset, from `watch_budget_resets`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BudgetResetEvent {
+    pub org_id: String,
+    /// `None` for the org-level budget.
+    pub agent_id: Option<String>,
+    /// The limit available again after the reset.
+    pub token_limit: i64,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub reset_at: Option<SystemTime>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct UsageSummary {
     pub total_tokens: i64,
     pub total_tool_invocations: i32,
     pub total_duration_ms: i64,
     pub report_count: i32,
 }
+
+impl UsageSummary {
+    /// Adds `other`'s totals into `self`.
+    pub fn merge(&mut self, other: &UsageSummary) {
+        self.total_tokens += other.total_tokens;
+        self.total_tool_invocations += other.total_tool_invocations;
+        self.total_duration_ms += other.total_duration_ms;
+        self.report_count += other.report_count;
+    }
+
+    /// Combined totals of all `items`.
+    pub fn sum(items: impl IntoIterator<Item = UsageSummary>) -> UsageSummary {
+        items
+            .into_iter()
+            .fold(UsageSummary::default(), |mut acc, s| {
+                acc.merge(&s);
+                acc
+            })
+    }
+}
+
+impl std::ops::AddAssign<&UsageSummary> for UsageSummary {
+    fn add_assign(&mut self, other: &UsageSummary) {
+        self.merge(other);
+    }
+}
+
+/// Token prices used to turn usage into cost, in USD per 1,000 tokens.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PricingModel {
+    /// Price for usage with no model, or a model missing from `per_model`.
+    pub default_per_1k_tokens: f64,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub per_model: HashMap<String, f64>,
+}
+
+impl PricingModel {
+    /// Cost of `tokens` tokens used with `model`.
+    pub fn cost(&self, model: Option<&str>, tokens: i64) -> f64 {
+        let rate = model
+            .and_then(|m| self.per_model.get(m))
+            .copied()
+            .unwrap_or(self.default_per_1k_tokens);
+        rate * tokens as f64 / 1000.0
+    }
+}
+
+/// Projected spend from [`forecast_cost`](crate::AgentPlatformClient::forecast_cost).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CostForecast {
+    /// Cost of the usage reported so far.
+    pub cost_to_date: f64,
+    /// Average spend per day since the earliest report.
+    pub daily_burn_rate: f64,
+    /// `cost_to_date` plus the burn rate carried forward to `until`.
+    pub projected_cost: f64,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339"))]
+    pub until: SystemTime,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ToolPermission {
+    pub tool_name: String,
+    pub effect: PolicyEffect,
+    /// Conditions on the call's parameters, keyed by parameter name, that
+    /// must hold for this permission to apply. `None` = unconditional.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parameters_constraint: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// Whether a policy's denies are enforced or only logged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PolicyMode {
+    #[default]
+    Enforce,
+    /// Never denies; the server logs what would have been denied.
+    Shadow,
+}
+
+impl PolicyMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PolicyMode::Enforce => "enforce",
+            PolicyMode::Shadow => "shadow",
+        }
+    }
+
+    /// Parses the wire value; anything unrecognized is treated as `Enforce`.
+    pub fn from_wire(s: &str) -> Self {
+        match s {
+            "shadow" => PolicyMode::Shadow,
+            _ => PolicyMode::Enforce,
+        }
+    }
+}
+
+/// Whether a tool rule allows or denies. Like [`Role`], it is serialized as
+/// its wire string and keeps effects this SDK does not know in `Other`;
+/// the server treats those as matching nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "String", into = "String"))]
+pub enum PolicyEffect {
+    Allow,
+    Deny,
+    Other(String),
+}
+
+impl PolicyEffect {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PolicyEffect::Allow => "allow",
+            PolicyEffect::Deny => "deny",
+            PolicyEffect::Other(s) => s,
+        }
+    }
+}
+
+impl From<&str> for PolicyEffect {
+    fn from(s: &str) -> Self {
+        match s {
+            "allow" => PolicyEffect::Allow,
+            "deny" => PolicyEffect::Deny,
+            _ => PolicyEffect::Other(s.to_string()),
+        }
+    }
+}
+
+impl From<String> for PolicyEffect {
+    fn from(s: String) -> Self {
+        match PolicyEffect::from(s.as_str()) {
+            PolicyEffect::Other(_) => PolicyEffect::Other(s),
+            known => known,
+        }
+    }
+}
+
+impl From<PolicyEffect> for String {
+    fn from(effect: PolicyEffect) -> Self {
+        match effect {
+            PolicyEffect::Other(s) => s,
+            known => known.as_str().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for PolicyEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Policy {
+    pub policy_id: String,
+    pub org_id: String,
+    pub agent_id: Option<String>,
+    pub tools: Vec<ToolPermission>,
+    pub token_limit: i64,
+    pub execution_timeout_seconds: i32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: PolicyMode,
+}
+
+impl Policy {
+    /// Stable hash of the policy's rules, for cheap change detection.
+    ///
+    /// Covers tool permissions with their parameter constraints, token limit,
+    /// timeout, and mode. Tool order and constraint key order do not affect
+    /// the result; ids and scope do not contribute. The algorithm is 64-bit
+    /// FNV-1a over a canonical encoding, so hashes are comparable across
+    /// processes and SDK versions.
+    pub fn content_hash(&self) -> String {
+        let mut tools: Vec<(&str, &str, String)> = self
+            .tools
+            .iter()
+            .map(|t| {
+                let mut constraint = String::new();
+                if let Some(map) = &t.parameters_constraint {
+                    canonical_object(map, &mut constraint);
+                }
+                (t.tool_name.as_str(), t.effect.as_str(), constraint)
+            })
+            .collect();
+        tools.sort_unstable();
+
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for b in bytes {
+                hash ^= u64::from(*b);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        for (name, effect, constraint) in tools {
+            feed(name.as_bytes());
+            feed(&[0]);
+            feed(effect.as_bytes());
+            feed(&[0]);
+            feed(constraint.as_bytes());
+            feed(&[0]);
+        }
+        feed(&self.token_limit.to_le_bytes());
+        feed(&self.execution_timeout_seconds.to_le_bytes());
+        feed(self.mode.as_str().as_bytes());
+        format!("{hash:016x}")
+    }
+}
+
+/// Compact JSON for `map` with keys sorted at every level, so equal
+/// constraints encode the same whatever order they were built in.
+fn canonical_object(map: &serde_json::Map<String, serde_json::Value>, out: &mut String) {
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_unstable_by_key(|(k, _)| k.as_str());
+    out.push('{');
+    for (i, (key, value)) in entries.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str(&serde_json::Value::from(key.as_str()).to_string());
+        out.push(':');
+        canonical(value, out);
+    }
+    out.push('}');
+}
+
+fn canonical(value: &serde_json::Value, out: &mut String) {
+    match value {
+        serde_json::Value::Object(map) => canonical_object(map, out),
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// An org's agents, policies, and budgets, from
+/// [`export_org_config`](crate::AgentPlatformClient::export_org_config), for
+/// keeping in version control and applying to another org with
+/// [`import_org_config`](crate::AgentPlatformClient::import_org_config).
+///
+/// Agents are identified by name rather than id, since ids differ between
+/// orgs. Entries are sorted (agents by name, org-level policy and budget
+/// first), so exports of equivalent orgs compare equal.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OrgConfig {
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub agents: Vec<AgentEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub policies: Vec<PolicyEntry>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub budgets: Vec<BudgetEntry>,
+}
+
+/// An active agent in an [`OrgConfig`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentEntry {
+    pub name: String,
+    pub role: Role,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub delegated_user_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub token_claims: HashMap<String, String>,
+}
+
+/// A policy in an [`OrgConfig`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PolicyEntry {
+    /// Name of the agent the policy applies to; `None` for the org policy.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub agent: Option<String>,
+    pub tools: Vec<ToolPermission>,
+    pub token_limit: i64,
+    pub execution_timeout_seconds: i32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: PolicyMode,
+}
+
+/// A budget in an [`OrgConfig`]. Only limits are kept, not usage.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BudgetEntry {
+    /// Name of the agent the budget applies to; `None` for the org budget.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub agent: Option<String>,
+    pub token_limit: i64,
+    pub reset_period_days: i32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub warning_threshold_percent: Option<u8>,
+}
+
+/// A likely mistake in an org's policies, from
+/// [`lint_policies`](crate::AgentPlatformClient::lint_policies).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PolicyLintFinding {
+    pub severity: LintSeverity,
+    pub message: String,
+    /// Policies involved, org-level first.
+    pub policy_ids: Vec<String>,
+    pub tool_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum LintSeverity {
+    /// Legal but easy to misread, such as an agent denying an org-allowed tool.
+    Info,
+    /// Redundant or ineffective, such as a rule shadowed by another.
+    Warning,
+    /// A rule that can never take effect as written.
+    Error,
+}
+
+/// The policy that actually governs an agent after org- and agent-level
+/// policies are merged.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EffectivePolicy {
+    pub agent_id: String,
+    pub tools: Vec<ToolPermission>,
+    pub token_limit: i64,
+    pub execution_timeout_seconds: i32,
+    pub org_policy_id: Option<String>,
+    pub agent_policy_id: Option<String>,
+}
+
+impl EffectivePolicy {
+    /// Which levels were combined to produce this policy.
+    pub fn precedence(&self) -> PolicyPrecedence {
+        match (&self.org_policy_id, &self.agent_policy_id) {
+            (Some(_), Some(_)) => PolicyPrecedence::AgentOverOrg,
+            (None, Some(_)) => PolicyPrecedence::Agent,
+            (Some(_), None) => PolicyPrecedence::Org,
+            (None, None) => PolicyPrecedence::Unset,
+        }
+    }
+}
+
+/// How an [`EffectivePolicy`] was resolved, from
+/// [`EffectivePolicy::precedence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PolicyPrecedence {
+    /// Neither level has a policy, so every tool is denied.
+    Unset,
+    /// Only the org-wide policy applies.
+    Org,
+    /// Only the agent's own policy applies.
+    Agent,
+    /// Both apply: agent rules replace org rules for the same tool, except
+    /// that org denies win, and the lower token limit and timeout apply.
+    AgentOverOrg,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RateLimit {
+    pub max_per_minute: i32,
+}
+
+/// Result of [`check_rate_limit`](crate::AgentPlatformClient::check_rate_limit).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RateLimitCheck {
+    pub allowed: bool,
+    /// Calls left in the current one-minute window, or `None` when no limit
+    /// applies.
+    pub remaining: Option<i32>,
+    /// When the current window ends and the count starts over.
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub reset_at: Option<SystemTime>,
+    pub reason: String,
+}
+
+impl RateLimitCheck {
+    /// `Ok` if the call fits in the window, otherwise
+    /// [`SdkError::RateLimited`].
+    pub fn require_allowed(self) -> crate::error::Result<Self> {
+        if self.allowed {
+            return Ok(self);
+        }
+        Err(SdkError::RateLimited {
+            reset_at: self.reset_at,
+            reason: self.reason,
+        })
+    }
+}
+
+/// Everything that configures an agent, fetched in one call.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentConfig {
+    pub agent: Agent,
+    pub effective_policy: Option<EffectivePolicy>,
+    pub budget: Option<BudgetInfo>,
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// What a scheduler needs to decide whether an agent is eligible to run.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SchedulingInfo {
+    pub agent_id: String,
+    pub effective_policy: Option<EffectivePolicy>,
+    pub budget: Option<BudgetInfo>,
+    pub rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ActivityEvent {
+    pub event_id: String,
+    pub org_id: String,
+    pub agent_id: String,
+    pub action: String,
+    pub tool_name: Option<String>,
+    pub result: String,
+    pub reason: Option<String>,
+    pub tokens_used: i64,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub timestamp: Option<SystemTime>,
+    /// Position in the org's event sequence. Persist the last one processed
+    /// and pass it to `watch_activity_from` to resume after a restart.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cursor: u64,
+}
+
+/// An admin change that may invalidate cached decisions, from
+/// [`watch_changes`](crate::AgentPlatformClient::watch_changes).
+///
+/// Each event carries the `cursor` to resume from after a disconnect; see
+/// [`ChangeEvent::cursor`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "snake_case"))]
+pub enum ChangeEvent {
+    PolicyUpdated {
+        cursor: u64,
+        org_id: String,
+        /// `None` for the org-level policy.
+        agent_id: Option<String>,
+        policy_id: String,
+        /// The policy as it now stands; `None` if it was deleted.
+        policy: Option<Policy>,
+    },
+    BudgetUpdated {
+        cursor: u64,
+        org_id: String,
+        agent_id: Option<String>,
+        budget: Option<BudgetInfo>,
+    },
+    AgentDeactivated {
+        cursor: u64,
+        org_id: String,
+        agent_id: String,
+        reason: Option<String>,
+    },
+    /// A kind of change this SDK version doesn't know. Treat it as
+    /// invalidating everything for the org.
+    Other {
+        cursor: u64,
+        org_id: String,
+        kind: String,
+    },
+}
+
+impl ChangeEvent {
+    pub fn cursor(&self) -> u64 {
+        match self {
+            ChangeEvent::PolicyUpdated { cursor, .. }
+            | ChangeEvent::BudgetUpdated { cursor, .. }
+            | ChangeEvent::AgentDeactivated { cursor, .. }
+            | ChangeEvent::Other { cursor, .. } => *cursor,
+        }
+    }
+
+    pub fn org_id(&self) -> &str {
+        match self {
+            ChangeEvent::PolicyUpdated { org_id, .. }
+            | ChangeEvent::BudgetUpdated { org_id, .. }
+            | ChangeEvent::AgentDeactivated { org_id, .. }
+            | ChangeEvent::Other { org_id, .. } => org_id,
+        }
+    }
+}
+
+/// A policy evaluation picked by `sample_policy_decisions`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PolicyDecisionEvent {
+    pub org_id: String,
+    pub agent_id: String,
+    pub tool_name: String,
+    pub decision: PolicyDecision,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub timestamp: Option<SystemTime>,
+}
+
+/// Failure stats for one agent over the server's recent activity window.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AgentHealth {
+    /// Fraction of policy evaluations that were denied, from 0.0 to 1.0.
+    pub deny_rate: f64,
+    pub exhaustion_count: i64,
+    pub error_count: i64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AuditEntry {
+    pub entry_id: String,
+    pub org_id: String,
+    pub agent_id: String,
+    pub delegated_user_id: Option<String>,
+    pub execution_id: Option<String>,
+    pub action: String,
+    pub tool_name: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub parameters: serde_json::Map<String, serde_json::Value>,
+    pub result: String,
+    pub reason: Option<String>,
+    pub latency_ms: i64,
+    pub tokens_used: i64,
+    #[cfg_attr(feature = "rfc3339", serde(with = "crate::rfc3339::option", default))]
+    pub timestamp: Option<SystemTime>,
+    /// Position in the org's audit sequence, for `tail_audit_log_from`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cursor: u64,
+}
+
+/// An audit entry together with the exact bytes the server hashed.
+/// [`audit::verify`](crate::audit::verify) checks that `entry` is what
+/// `payload` decodes to.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SignedAuditEntry {
+    pub entry: AuditEntry,
+    pub payload: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+/// A hash-chained, server-signed range of the audit log. Check it with
+/// [`audit::verify`](crate::audit::verify) before trusting it.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SignedAuditExport {
+    pub entries: Vec<SignedAuditEntry>,
+    pub anchor_hash: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub key_id: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy(tools: Vec<ToolPermission>) -> Policy {
+        Policy {
+            policy_id: "policy-1".into(),
+            org_id: "org-1".into(),
+            agent_id: None,
+            tools,
+            token_limit: 1_000,
+            execution_timeout_seconds: 30,
+            mode: PolicyMode::Enforce,
+        }
+    }
+
+    fn http(constraint: serde_json::Value) -> ToolPermission {
+        let serde_json::Value::Object(map) = constraint else {
+            panic!("constraint must be an object");
+        };
+        ToolPermission {
+            tool_name: "http".into(),
+            effect: PolicyEffect::Allow,
+            parameters_constraint: Some(map),
+        }
+    }
+
+    #[test]
+    fn content_hash_covers_constraints() {
+        let internal = policy(vec![http(json!({ "host": "*.internal" }))]);
+        let anywhere = policy(vec![http(json!({ "host": "*" }))]);
+        let unconstrained = policy(vec![ToolPermission {
+            parameters_constraint: None,
+            ..http(json!({}))
+        }]);
+        assert_ne!(internal.content_hash(), anywhere.content_hash());
+        assert_ne!(internal.content_hash(), unconstrained.content_hash());
+    }
+
+    #[test]
+    fn content_hash_ignores_key_order() {
+        let a = policy(vec![http(
+            json!({ "host": "a", "opts": { "x": 1, "y": [1, 2] } }),
+        )]);
+        let b = policy(vec![http(
+            json!({ "opts": { "y": [1, 2], "x": 1 }, "host": "a" }),
+        )]);
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn denied_decision_carries_tool_and_policy() {
+        let decision = PolicyDecision {
+            allowed: false,
+            reason: "explicitly denied".into(),
+            policy_id: Some("policy-1".into()),
+            warnings: Vec::new(),
+            shadow_would_deny: false,
+            cache_ttl: None,
+            matched_tool: Some("shell".into()),
+            effect: Some(PolicyEffect::Deny),
+            applied_constraints: None,
+        };
+        let err = decision.require_allowed("shell").unwrap_err();
+        let SdkError::PolicyDenied {
+            tool,
+            reason,
+            policy_id,
+        } = err
+        else {
+            panic!("expected PolicyDenied, got {err:?}");
+        };
+        assert_eq!(tool.as_deref(), Some("shell"));
+        assert_eq!(reason, "explicitly denied");
+        assert_eq!(policy_id.as_deref(), Some("policy-1"));
+    }
+
+    #[test]
+    fn exhausted_check_carries_tokens_remaining() {
+        let check = BudgetCheck {
+            allowed: false,
+            tokens_remaining: 40,
+            reason: "insufficient budget".into(),
+            reservation_id: None,
+            utilization: 1.2,
+            warning: None,
+        };
+        let err = check.require_allowed().unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                SdkError::BudgetExhausted { tokens_remaining: Some(40), reason }
+                    if reason == "insufficient budget"
+            ),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn known_roles_and_effects_use_their_variants() {
+        for role in [Role::Executor, Role::Planner, Role::Reviewer, Role::Admin] {
+            assert_eq!(Role::from(role.as_str()), role);
+            assert_eq!(Role::from(String::from(role.clone())), role);
+        }
+        assert_eq!(Role::from("planner"), Role::Planner);
+        assert_eq!(Role::default(), Role::Executor);
+        for effect in [PolicyEffect::Allow, PolicyEffect::Deny] {
+            assert_eq!(PolicyEffect::from(effect.as_str()), effect);
+        }
+        assert_eq!(PolicyEffect::from("deny".to_string()), PolicyEffect::Deny);
+    }
+
+    #[test]
+    fn unknown_roles_and_effects_round_trip_through_other() {
+        let role = Role::from("triager".to_string());
+        assert_eq!(role, Role::Other("triager".into()));
+        assert_eq!(role.to_string(), "triager");
+        assert_eq!(String::from(role), "triager");
+
+        let effect = PolicyEffect::from("audit");
+        assert_eq!(effect, PolicyEffect::Other("audit".into()));
+        assert_eq!(String::from(effect), "audit");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn agent_role_deserializes_into_the_enum() {
+        let agent = |role: &str| -> Agent {
+            serde_json::from_value(json!({
+                "agent_id": "agent-1",
+                "org_id": "org-1",
+                "name": "bot",
+                "role": role,
+                "active": true,
+                "delegated_user_id": null,
+                "deactivated_at": null,
+                "deleted_at": null,
+            }))
+            .unwrap()
+        };
+        assert_eq!(agent("reviewer").role, Role::Reviewer);
+        let custom = agent("triager");
+        assert_eq!(custom.role, Role::Other("triager".into()));
+        assert_eq!(serde_json::to_value(&custom).unwrap()["role"], "triager");
+    }
+}
@@ -0,0 +1,300 @@
+use crate::models::{
+    LintSeverity, Policy, PolicyEffect, PolicyLintFinding, PolicyMode, ToolPermission,
+};
+use std::collections::HashMap;
+
+/// A policy assembled step by step, for
+/// [`AgentPlatformClient::set_policy_from`](crate::AgentPlatformClient::set_policy_from).
+/// Preferred over the positional `set_policy`, and the only way to attach
+/// per-tool parameter constraints.
+///
+/// # Example
+/// ```
+/// use agent_platform_sdk::policy::PolicyBuilder;
+/// use serde_json::json;
+///
+/// let mut internal = serde_json::Map::new();
+/// internal.insert("host".into(), json!("*.internal"));
+///
+/// let policy = PolicyBuilder::new("org-1")
+///     .for_agent("agent-7")
+///     .allow("search")
+///     .allow_with("http", internal)
+///     .deny("shell")
+///     .token_limit(50_000)
+///     .timeout(30);
+/// ```
+#[derive(Debug, Clone)]
+pub struct PolicyBuilder {
+    pub(crate) org_id: String,
+    pub(crate) agent_id: Option<String>,
+    pub(crate) tools: Vec<ToolPermission>,
+    pub(crate) token_limit: i64,
+    pub(crate) timeout_seconds: i32,
+    pub(crate) mode: PolicyMode,
+}
+
+impl PolicyBuilder {
+    /// An org-wide policy with no tool rules, token limit, or timeout.
+    pub fn new(org_id: &str) -> Self {
+        Self {
+            org_id: org_id.to_string(),
+            agent_id: None,
+            tools: Vec::new(),
+            token_limit: 0,
+            timeout_seconds: 0,
+            mode: PolicyMode::Enforce,
+        }
+    }
+
+    /// Applies the policy to one agent instead of the whole org.
+    pub fn for_agent(mut self, agent_id: &str) -> Self {
+        self.agent_id = Some(agent_id.to_string());
+        self
+    }
+
+    pub fn allow(self, tool: &str) -> Self {
+        self.tool(tool, PolicyEffect::Allow, None)
+    }
+
+    /// Allows `tool` only for calls whose parameters satisfy `constraint`,
+    /// keyed by parameter name.
+    pub fn allow_with(
+        self,
+        tool: &str,
+        constraint: serde_json::Map<String, serde_json::Value>,
+    ) -> Self {
+        self.tool(tool, PolicyEffect::Allow, Some(constraint))
+    }
+
+    pub fn deny(self, tool: &str) -> Self {
+        self.tool(tool, PolicyEffect::Deny, None)
+    }
+
+    /// A rule with an effect chosen at runtime, e.g. read from config.
+    /// A misspelt effect becomes [`PolicyEffect::Other`], which
+    /// [`lint_policies`] reports.
+    pub fn rule(self, tool: &str, effect: impl Into<PolicyEffect>) -> Self {
+        self.tool(tool, effect.into(), None)
+    }
+
+    pub fn token_limit(mut self, tokens: i64) -> Self {
+        self.token_limit = tokens;
+        self
+    }
+
+    /// Execution timeout in seconds.
+    pub fn timeout(mut self, seconds: i32) -> Self {
+        self.timeout_seconds = seconds;
+        self
+    }
+
+    pub fn mode(mut self, mode: PolicyMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    fn tool(
+        mut self,
+        tool: &str,
+        effect: PolicyEffect,
+        constraint: Option<serde_json::Map<String, serde_json::Value>>,
+    ) -> Self {
+        self.tools.push(ToolPermission {
+            tool_name: tool.to_string(),
+            effect,
+            parameters_constraint: constraint,
+        });
+        self
+    }
+}
+
+/// Checks `policies`, all from one org, for rules that conflict under the
+/// control plane's evaluation order: within a policy an explicit deny beats an
+/// explicit allow, which beats a `*` allow; across levels an agent rule
+/// replaces the org rule for that tool, except that org denies always win.
+///
+/// Findings are sorted most severe first.
+pub fn lint_policies(policies: &[Policy]) -> Vec<PolicyLintFinding> {
+    let mut findings = Vec::new();
+    for policy in policies {
+        lint_one(policy, &mut findings);
+    }
+    if let Some(org) = policies.iter().find(|p| p.agent_id.is_none()) {
+        for agent in policies.iter().filter(|p| p.agent_id.is_some()) {
+            lint_against_org(org, agent, &mut findings);
+        }
+    }
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    findings
+}
+
+fn lint_one(policy: &Policy, findings: &mut Vec<PolicyLintFinding>) {
+    let mut rules: HashMap<&str, Vec<&ToolPermission>> = HashMap::new();
+    for tool in &policy.tools {
+        rules.entry(tool.tool_name.as_str()).or_default().push(tool);
+    }
+    let finding = |severity, tool: &str, message: String| PolicyLintFinding {
+        severity,
+        message,
+        policy_ids: vec![policy.policy_id.clone()],
+        tool_name: Some(tool.to_string()),
+    };
+    let wildcard = rules
+        .get("*")
+        .is_some_and(|r| r.iter().any(|t| t.effect == PolicyEffect::Allow));
+    let mut tools: Vec<_> = rules.into_iter().collect();
+    tools.sort_unstable_by_key(|(name, _)| *name);
+    for (name, rules) in tools {
+        let allows = rules
+            .iter()
+            .filter(|t| t.effect == PolicyEffect::Allow)
+            .count();
+        let denies = rules
+            .iter()
+            .filter(|t| t.effect == PolicyEffect::Deny)
+            .count();
+        for effect in rules.iter().filter_map(|t| match &t.effect {
+            PolicyEffect::Other(effect) => Some(effect),
+            _ => None,
+        }) {
+            findings.push(finding(
+                LintSeverity::Error,
+                name,
+                format!("'{name}' has unknown effect '{effect}', which matches no call"),
+            ));
+        }
+        if allows > 0 && denies > 0 {
+            findings.push(finding(
+                LintSeverity::Error,
+                name,
+                format!("'{name}' is both allowed and denied; the deny always wins"),
+            ));
+        } else if allows > 1 || denies > 1 {
+            let constrained = rules.iter().any(|t| t.parameters_constraint.is_some());
+            let detail = if constrained {
+                "with overlapping constraints"
+            } else {
+                "more than once"
+            };
+            findings.push(finding(
+                LintSeverity::Warning,
+                name,
+                format!("'{name}' has the same effect {detail}"),
+            ));
+        }
+        if name == "*" && denies > 0 {
+            findings.push(finding(
+                LintSeverity::Error,
+                name,
+                "'*' is a wildcard only for allow; this deny matches no real tool".into(),
+            ));
+        } else if name != "*" && wildcard && allows > 0 && denies == 0 {
+            let unconstrained = rules.iter().all(|t| t.parameters_constraint.is_none());
+            if unconstrained {
+                findings.push(finding(
+                    LintSeverity::Info,
+                    name,
+                    format!("allowing '{name}' is redundant with the '*' allow"),
+                ));
+            }
+        }
+    }
+}
+
+fn lint_against_org(org: &Policy, agent: &Policy, findings: &mut Vec<PolicyLintFinding>) {
+    let agent_id = agent.agent_id.as_deref().unwrap_or_default();
+    let finding = |severity, tool: Option<&str>, message: String| PolicyLintFinding {
+        severity,
+        message,
+        policy_ids: vec![org.policy_id.clone(), agent.policy_id.clone()],
+        tool_name: tool.map(str::to_string),
+    };
+    for tool in &agent.tools {
+        let name = tool.tool_name.as_str();
+        let org_effect = |effect: PolicyEffect| {
+            org.tools
+                .iter()
+                .any(|t| t.tool_name == name && t.effect == effect)
+        };
+        if tool.effect == PolicyEffect::Allow && org_effect(PolicyEffect::Deny) {
+            findings.push(finding(
+                LintSeverity::Error,
+                Some(name),
+                format!(
+                    "agent {agent_id} allows '{name}', but the org denies it and org denies win"
+                ),
+            ));
+        } else if tool.effect == PolicyEffect::Deny && org_effect(PolicyEffect::Allow) {
+            findings.push(finding(
+                LintSeverity::Info,
+                Some(name),
+                format!("agent {agent_id} denies '{name}', which the org allows"),
+            ));
+        }
+    }
+    if org.token_limit > 0 && agent.token_limit > org.token_limit {
+        findings.push(finding(
+            LintSeverity::Warning,
+            None,
+            format!(
+                "agent {agent_id} token limit {} exceeds the org's {}; the lower limit applies",
+                agent.token_limit, org.token_limit
+            ),
+        ));
+    }
+    if org.execution_timeout_seconds > 0
+        && agent.execution_timeout_seconds > org.execution_timeout_seconds
+    {
+        findings.push(finding(
+            LintSeverity::Warning,
+            None,
+            format!(
+                "agent {agent_id} timeout {}s exceeds the org's {}s; the shorter timeout applies",
+                agent.execution_timeout_seconds, org.execution_timeout_seconds
+            ),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn allow_with_keeps_the_constraint() {
+        let mut internal = serde_json::Map::new();
+        internal.insert("host".into(), json!("*.internal"));
+        let policy = PolicyBuilder::new("org-1").allow_with("http", internal.clone());
+        assert_eq!(policy.tools.len(), 1);
+        assert_eq!(policy.tools[0].effect, PolicyEffect::Allow);
+        assert_eq!(policy.tools[0].parameters_constraint, Some(internal));
+    }
+
+    fn built(builder: PolicyBuilder) -> Policy {
+        Policy {
+            policy_id: "policy-1".into(),
+            org_id: builder.org_id,
+            agent_id: builder.agent_id,
+            tools: builder.tools,
+            token_limit: builder.token_limit,
+            execution_timeout_seconds: builder.timeout_seconds,
+            mode: builder.mode,
+        }
+    }
+
+    #[test]
+    fn lint_flags_unknown_effects() {
+        let policy = built(
+            PolicyBuilder::new("org-1")
+                .allow("search")
+                .rule("x", "alow"),
+        );
+        let findings = lint_policies(&[policy]);
+        assert_eq!(findings.len(), 1, "{findings:?}");
+        assert_eq!(findings[0].severity, LintSeverity::Error);
+        assert_eq!(findings[0].tool_name.as_deref(), Some("x"));
+        assert!(findings[0].message.contains("'alow'"), "{findings:?}");
+    }
+}
@@ -0,0 +1,63 @@
+use crate::proto::control_plane_client::ControlPlaneClient;
+use tonic::transport::{Channel, Endpoint};
+
+/// Spreads calls over several endpoints in proportion to their weights,
+/// using smooth weighted round-robin: picks interleave rather than running
+/// in bursts per endpoint.
+#[derive(Clone)]
+pub(crate) struct Pool {
+    members: Vec<Member>,
+    total_weight: i64,
+}
+
+#[derive(Clone)]
+struct Member {
+    uri: String,
+    channel: Channel,
+    weight: i64,
+    current: i64,
+}
+
+impl Pool {
+    /// Builds lazily connecting channels for each endpoint. Endpoints with
+    /// zero weight are dropped; the caller ensures at least one remains.
+    pub(crate) fn new(endpoints: Vec<(Endpoint, u32)>) -> Self {
+        let members: Vec<Member> = endpoints
+            .into_iter()
+            .filter(|(_, weight)| *weight > 0)
+            .map(|(endpoint, weight)| Member {
+                uri: endpoint.uri().to_string(),
+                channel: endpoint.connect_lazy(),
+                weight: weight.into(),
+                current: 0,
+            })
+            .collect();
+        let total_weight = members.iter().map(|m| m.weight).sum();
+        Self {
+            members,
+            total_weight,
+        }
+    }
+
+    /// Every endpoint's URI with its channel.
+    pub(crate) fn members(&self) -> Vec<(String, Channel)> {
+        self.members
+            .iter()
+            .map(|m| (m.uri.clone(), m.channel.clone()))
+            .collect()
+    }
+
+    /// Client to use for the next call.
+    pub(crate) fn select(&mut self) -> ControlPlaneClient<Channel> {
+        for m in &mut self.members {
+            m.current += m.weight;
+        }
+        let chosen = self
+            .members
+            .iter_mut()
+            .max_by_key(|m| m.current)
+            .expect("pool has at least one member");
+        chosen.current -= self.total_weight;
+        ControlPlaneClient::new(chosen.channel.clone())
+    }
+}
@@ -0,0 +1,14 @@
+//! The common imports in one place:
+//!
+//! ```
+//! use agent_platform_sdk::prelude::*;
+//! ```
+
+pub use crate::builder::ClientBuilder;
+pub use crate::client::AgentPlatformClient;
+pub use crate::control_plane::ControlPlane;
+pub use crate::error::{Result, SdkError};
+pub use crate::models::*;
+pub use crate::policy::PolicyBuilder;
+pub use crate::retry::RetryPolicy;
+pub use crate::stream::{EventStream, StreamConfig};
@@ -0,0 +1,78 @@
+use crate::error::Result;
+#[cfg(feature = "serde")]
+use crate::error::SdkError;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::path::Path;
+use std::time::Duration;
+use tonic::transport::Endpoint;
+
+/// Connection settings for one environment, loaded with
+/// [`ClientProfile::load`] or passed to
+/// [`AgentPlatformClient::connect_profile`](crate::AgentPlatformClient::connect_profile).
+///
+/// A profile file is a JSON object keyed by profile name:
+///
+/// ```json
+/// {
+///   "dev":  { "endpoint": "http://localhost:50051" },
+///   "prod": {
+///     "endpoint": "http://cp.us-east.internal:50051",
+///     "secondaries": ["http://cp.us-west.internal:50051"],
+///     "connect_timeout_secs": 5,
+///     "request_timeout_secs": 30,
+///     "policy_cache_ttl_secs": 60
+///   }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClientProfile {
+    pub endpoint: String,
+    /// Failover endpoints, tried in order when `endpoint` is unavailable.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub secondaries: Vec<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub connect_timeout_secs: Option<u64>,
+    /// Default deadline for every call; see `AgentPlatformClient::with_timeout`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub request_timeout_secs: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub read_only: bool,
+    /// Enables the policy decision cache with this default TTL.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub policy_cache_ttl_secs: Option<u64>,
+}
+
+impl ClientProfile {
+    /// Reads the profile named `name` from the JSON file at `path`.
+    #[cfg(feature = "serde")]
+    pub fn load(path: impl AsRef<Path>, name: &str) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| SdkError::Config(format!("{}: {e}", path.display())))?;
+        let mut profiles: HashMap<String, ClientProfile> = serde_json::from_str(&text)
+            .map_err(|e| SdkError::Config(format!("{}: {e}", path.display())))?;
+        profiles
+            .remove(name)
+            .ok_or_else(|| SdkError::Config(format!("{}: no profile {name:?}", path.display())))
+    }
+
+    /// Primary endpoint followed by the secondaries, with the connect timeout
+    /// applied. The request timeout is applied by the client.
+    pub(crate) fn endpoints(&self) -> Result<Vec<Endpoint>> {
+        std::iter::once(&self.endpoint)
+            .chain(&self.secondaries)
+            .map(|addr| {
+                let mut endpoint = Endpoint::from_shared(addr.clone())?;
+                if let Some(secs) = self.connect_timeout_secs {
+                    endpoint = endpoint.connect_timeout(Duration::from_secs(secs));
+                }
+                Ok(endpoint)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::error::{Result, SdkError};
+use prost::Message;
+use prost_types::FileDescriptorProto;
+use tonic::metadata::MetadataMap;
+use tonic::transport::Channel;
+use tonic::Extensions;
+
+#[allow(clippy::enum_variant_names)]
+pub(crate) mod proto {
+    tonic::include_proto!("grpc.reflection.v1alpha");
+}
+
+use proto::server_reflection_client::ServerReflectionClient;
+use proto::server_reflection_request::MessageRequest;
+use proto::server_reflection_response::MessageResponse;
+use proto::{ServerReflectionRequest, ServerReflectionResponse};
+
+/// Lists every method the server exposes through gRPC reflection, as
+/// fully qualified names like `agent_platform.ControlPlane.GetAgent`.
+/// `metadata` (the client's headers) is sent on each reflection stream.
+pub(crate) async fn list_methods(channel: Channel, metadata: MetadataMap) -> Result<Vec<String>> {
+    let mut client = ServerReflectionClient::new(channel);
+
+    let listed = exchange(
+        &mut client,
+        &metadata,
+        vec![MessageRequest::ListServices(String::new())],
+    )
+    .await?;
+    let services: Vec<String> = match listed.into_iter().next() {
+        Some(MessageResponse::ListServicesResponse(r)) => {
+            r.service.into_iter().map(|s| s.name).collect()
+        }
+        other => return Err(unexpected(other)),
+    };
+
+    let lookups = services
+        .iter()
+        .map(|s| MessageRequest::FileContainingSymbol(s.clone()))
+        .collect();
+    let files = exchange(&mut client, &metadata, lookups).await?;
+
+    let mut methods = Vec::new();
+    for (service, response) in services.iter().zip(files) {
+        let descriptors = match response {
+            MessageResponse::FileDescriptorResponse(r) => r.file_descriptor_proto,
+            other => return Err(unexpected(Some(other))),
+        };
+        for bytes in descriptors {
+            let file = FileDescriptorProto::decode(bytes.as_slice())
+                .map_err(|e| SdkError::InvalidResponse(format!("bad file descriptor: {e}")))?;
+            let package = file.package();
+            for svc in &file.service {
+                let full = if package.is_empty() {
+                    svc.name().to_string()
+                } else {
+                    format!("{package}.{}", svc.name())
+                };
+                if &full == service {
+                    methods.extend(svc.method.iter().map(|m| format!("{full}.{}", m.name())));
+                }
+            }
+        }
+    }
+    methods.sort();
+    methods.dedup();
+    Ok(methods)
+}
+
+/// Sends `requests` on one reflection stream and returns one response per
+/// request, in order, turning a reflection `ErrorResponse` into an error.
+async fn exchange(
+    client: &mut ServerReflectionClient<Channel>,
+    metadata: &MetadataMap,
+    requests: Vec<MessageRequest>,
+) -> Result<Vec<MessageResponse>> {
+    let expected = requests.len();
+    let outbound = tokio_stream::iter(requests.into_iter().map(|r| ServerReflectionRequest {
+        host: String::new(),
+        message_request: Some(r),
+    }));
+    let request = tonic::Request::from_parts(metadata.clone(), Extensions::default(), outbound);
+    let mut inbound = client.server_reflection_info(request).await?.into_inner();
+    let mut responses = Vec::with_capacity(expected);
+    while responses.len() < expected {
+        let Some(ServerReflectionResponse {
+            message_response, ..
+        }) = inbound.message().await?
+        else {
+            break;
+        };
+        match message_response {
+            Some(MessageResponse::ErrorResponse(e)) => {
+                return Err(tonic::Status::new(e.error_code.into(), e.error_message).into())
+            }
+            Some(r) => responses.push(r),
+            None => return Err(unexpected(None)),
+        }
+    }
+    if responses.len() < expected {
+        return Err(SdkError::InvalidResponse(format!(
+            "reflection stream ended after {} of {expected} responses",
+            responses.len()
+        )));
+    }
+    Ok(responses)
+}
+
+fn unexpected(response: Option<MessageResponse>) -> SdkError {
+    SdkError::InvalidResponse(format!("unexpected reflection response: {response:?}"))
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tonic::Code;
+
+/// When and how often the client retries a failed call.
+///
+/// Only idempotent reads (`get_*`, `list_*`, `check_budget`,
+/// `evaluate_policy`, ...) are retried unless `retry_mutations` is set, and
+/// only for transient failures: `Unavailable`, timeouts, transport errors and
+/// rate limiting (see [`SdkError::retriable`](crate::error::SdkError::retriable)).
+/// Anything else, such as `InvalidArgument`, `NotFound` or an exhausted
+/// budget, is returned immediately.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts including the first; 1 disables retries.
+    pub max_attempts: u32,
+    /// How long to wait before each retry. Defaults to [`Exponential`].
+    pub backoff: Arc<dyn BackoffStrategy>,
+    /// Also retry mutating calls such as `report_usage`. A call that timed
+    /// out may have been applied, so this relies on the server deduplicating
+    /// by the [idempotency key](crate::idempotency) each resend carries.
+    pub retry_mutations: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Arc::new(Exponential::default()),
+            retry_mutations: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Whether a failure with `code` that the SDK could not classify further
+    /// may succeed if tried again. `ResourceExhausted` is not: it is either
+    /// an exhausted budget or a message over the size limit, and neither
+    /// changes on resend.
+    pub fn is_retryable(code: Code) -> bool {
+        matches!(code, Code::Unavailable)
+    }
+
+    /// Replaces the backoff strategy, e.g. with [`DecorrelatedJitter`].
+    pub fn with_backoff(mut self, strategy: Box<dyn BackoffStrategy>) -> Self {
+        self.backoff = Arc::from(strategy);
+        self
+    }
+
+    /// Delay before retry number `attempt`, counting from 1, given the
+    /// delay before the previous retry of the same call.
+    pub(crate) fn delay(&self, attempt: u32, previous: Duration) -> Duration {
+        self.backoff.next_delay(attempt, previous)
+    }
+}
+
+/// Computes the wait before each retry of a call.
+///
+/// One strategy is shared by every clone of a client and every call in
+/// flight, so anything that depends on earlier retries is passed in rather
+/// than kept in the strategy.
+pub trait BackoffStrategy: Send + Sync + fmt::Debug {
+    /// Delay before retry number `attempt`, counting from 1. `previous` is
+    /// the delay this call waited before its last retry, zero before the
+    /// first.
+    fn next_delay(&self, attempt: u32, previous: Duration) -> Duration;
+}
+
+/// The same delay before every retry.
+#[derive(Debug, Clone, Copy)]
+pub struct Constant(pub Duration);
+
+impl BackoffStrategy for Constant {
+    fn next_delay(&self, _attempt: u32, _previous: Duration) -> Duration {
+        self.0
+    }
+}
+
+/// `base`, then `base + step`, `base + 2 * step`, ..., up to `max`.
+#[derive(Debug, Clone, Copy)]
+pub struct Linear {
+    pub base: Duration,
+    pub step: Duration,
+    pub max: Duration,
+}
+
+impl BackoffStrategy for Linear {
+    fn next_delay(&self, attempt: u32, _previous: Duration) -> Duration {
+        let extra = self.step.saturating_mul(attempt.saturating_sub(1));
+        self.base.saturating_add(extra).min(self.max)
+    }
+}
+
+/// `base` doubled for each retry after the first, up to `max`, with part of
+/// each delay randomized away.
+#[derive(Debug, Clone, Copy)]
+pub struct Exponential {
+    pub base: Duration,
+    pub max: Duration,
+    /// Fraction of each delay, from 0.0 to 1.0, that is randomized away so
+    /// that many clients failing together don't retry in lockstep.
+    pub jitter: f64,
+}
+
+impl Default for Exponential {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_millis(100),
+            max: Duration::from_secs(2),
+            jitter: 0.2,
+        }
+    }
+}
+
+impl BackoffStrategy for Exponential {
+    fn next_delay(&self, attempt: u32, _previous: Duration) -> Duration {
+        let exp = self
+            .base
+            .saturating_mul(1 << attempt.saturating_sub(1).min(20));
+        let capped = exp.min(self.max);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        capped.mul_f64(1.0 - jitter * unit_random())
+    }
+}
+
+/// AWS-style decorrelated jitter: each delay is drawn uniformly between
+/// `base` and three times the previous delay, capped at `max`. Spreads
+/// retries out better than jittered exponential backoff under contention.
+#[derive(Debug, Clone, Copy)]
+pub struct DecorrelatedJitter {
+    base: Duration,
+    max: Duration,
+}
+
+impl DecorrelatedJitter {
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self { base, max }
+    }
+}
+
+impl BackoffStrategy for DecorrelatedJitter {
+    fn next_delay(&self, _attempt: u32, previous: Duration) -> Duration {
+        // The first retry of a call treats `base` as the previous delay.
+        let upper = previous.max(self.base).saturating_mul(3);
+        (self.base + (upper - self.base).mul_f64(unit_random())).min(self.max)
+    }
+}
+
+/// Uniform value in `[0, 1)`.
+fn unit_random() -> f64 {
+    rand::random::<f64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decorrelated_jitter_state_is_per_call() {
+        let base = Duration::from_millis(10);
+        let strategy = DecorrelatedJitter::new(base, Duration::from_secs(10));
+        let shared = strategy;
+
+        // A long retry chain on one call ...
+        let mut previous = Duration::ZERO;
+        for attempt in 1..10 {
+            previous = strategy.next_delay(attempt, previous);
+        }
+        // ... does not affect the first retry of another.
+        for _ in 0..100 {
+            let first = shared.next_delay(1, Duration::ZERO);
+            assert!(first >= base && first <= base * 3, "{first:?}");
+        }
+    }
+
+    #[test]
+    fn decorrelated_jitter_stays_in_bounds() {
+        let base = Duration::from_millis(10);
+        let max = Duration::from_millis(200);
+        let strategy = DecorrelatedJitter::new(base, max);
+        let mut previous = Duration::ZERO;
+        for attempt in 1..50 {
+            let delay = strategy.next_delay(attempt, previous);
+            assert!(delay >= base && delay <= max);
+            assert!(delay <= previous.max(base) * 3);
+            previous = delay;
+        }
+    }
+
+    #[test]
+    fn linear_and_exponential_are_capped() {
+        let linear = Linear {
+            base: Duration::from_millis(100),
+            step: Duration::from_millis(50),
+            max: Duration::from_millis(180),
+        };
+        assert_eq!(
+            linear.next_delay(1, Duration::ZERO),
+            Duration::from_millis(100)
+        );
+        assert_eq!(
+            linear.next_delay(2, Duration::ZERO),
+            Duration::from_millis(150)
+        );
+        assert_eq!(
+            linear.next_delay(3, Duration::ZERO),
+            Duration::from_millis(180)
+        );
+
+        let exp = Exponential {
+            base: Duration::from_millis(100),
+            max: Duration::from_secs(1),
+            jitter: 0.0,
+        };
+        assert_eq!(
+            exp.next_delay(3, Duration::ZERO),
+            Duration::from_millis(400)
+        );
+        assert_eq!(exp.next_delay(30, Duration::ZERO), Duration::from_secs(1));
+    }
+}
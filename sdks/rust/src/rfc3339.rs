@@ -0,0 +1,225 @@
+//! Serde helpers that encode `SystemTime` as an RFC 3339 UTC string, e.g.
+//! `"2026-02-21T09:30:00.250Z"`, instead of serde's default
+//! `{ "secs_since_epoch": .., "nanos_since_epoch": .. }` object.
+//!
+//! With the `rfc3339` feature enabled, every timestamp field on the models
+//! uses [`option`]. Deserialization accepts any RFC 3339 offset (`Z`,
+//! `+02:00`, ...) and normalizes to UTC.
+
+use serde::{de, Deserialize, Deserializer, Serializer};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub fn serialize<S: Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format(*time))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse(&s).ok_or_else(|| de::Error::custom(format!("invalid RFC 3339 timestamp: {s}")))
+}
+
+/// The same encoding for `Option<SystemTime>`, with `None` as `null`.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match time {
+            Some(t) => super::serialize(t, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(s) => parse(&s)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom(format!("invalid RFC 3339 timestamp: {s}"))),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Formats `time` in UTC, with fractional seconds only when non-zero.
+pub fn format(time: SystemTime) -> String {
+    let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => {
+            let d = e.duration();
+            let nanos = d.subsec_nanos();
+            if nanos == 0 {
+                (-(d.as_secs() as i64), 0)
+            } else {
+                (-(d.as_secs() as i64) - 1, 1_000_000_000 - nanos)
+            }
+        }
+    };
+    let days = secs.div_euclid(86_400);
+    let tod = secs.rem_euclid(86_400);
+    let (y, m, d) = civil_from_days(days);
+    let mut out = format!(
+        "{y:04}-{m:02}-{d:02}T{:02}:{:02}:{:02}",
+        tod / 3600,
+        tod / 60 % 60,
+        tod % 60
+    );
+    if nanos != 0 {
+        let frac = format!("{nanos:09}");
+        out.push('.');
+        out.push_str(frac.trim_end_matches('0'));
+    }
+    out.push('Z');
+    out
+}
+
+/// Parses an RFC 3339 timestamp. Returns `None` if it is malformed,
+/// names a day its month doesn't have, or has an offset beyond ±23:59.
+///
+/// ```
+/// use agent_platform_sdk::rfc3339;
+///
+/// let t = rfc3339::parse("2024-02-29T23:30:00.5-01:00").unwrap();
+/// assert_eq!(rfc3339::format(t), "2024-03-01T00:30:00.5Z");
+/// assert!(rfc3339::parse("2024-13-01T00:00:00Z").is_none());
+/// assert!(rfc3339::parse("2023-02-29T00:00:00Z").is_none());
+/// ```
+pub fn parse(s: &str) -> Option<SystemTime> {
+    let b = s.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    if b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let digits = |part: Option<&str>| -> Option<i64> {
+        let part = part?;
+        part.bytes()
+            .all(|c| c.is_ascii_digit())
+            .then(|| part.parse().ok())?
+    };
+    let num = |r: std::ops::Range<usize>| digits(s.get(r));
+    let (y, mo, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (h, mi, sec) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if !(1..=12).contains(&mo) || h > 23 || mi > 59 || sec > 60 {
+        return None;
+    }
+    if !(1..=days_in_month(y, mo)).contains(&d) {
+        return None;
+    }
+
+    let mut rest = &s[19..];
+    let mut nanos: u32 = 0;
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        let kept = &frac[..digits.min(9)];
+        nanos = kept.parse::<u32>().ok()? * 10u32.pow(9 - kept.len() as u32);
+        rest = &frac[digits..];
+    }
+
+    let offset = match rest {
+        "Z" | "z" => 0,
+        _ => {
+            let rb = rest.as_bytes();
+            if rb.len() != 6 || rb[3] != b':' || !matches!(rb[0], b'+' | b'-') {
+                return None;
+            }
+            let (oh, om) = (digits(rest.get(1..3))?, digits(rest.get(4..6))?);
+            if oh > 23 || om > 59 {
+                return None;
+            }
+            let sign = if rb[0] == b'-' { -1 } else { 1 };
+            sign * (oh * 3600 + om * 60)
+        }
+    };
+
+    let secs = days_from_civil(y, mo, d) * 86_400 + h * 3600 + mi * 60 + sec - offset;
+    let since_epoch = Duration::new(secs.unsigned_abs(), 0);
+    let whole = if secs >= 0 {
+        UNIX_EPOCH + since_epoch
+    } else {
+        UNIX_EPOCH - since_epoch
+    };
+    Some(whole + Duration::from_nanos(u64::from(nanos)))
+}
+
+// Proleptic Gregorian calendar conversions, after Howard Hinnant's
+// `days_from_civil` / `civil_from_days`.
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(m <= 2), m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_valid_days_and_offsets() {
+        for s in [
+            "2024-02-29T00:00:00Z",
+            "2000-02-29T00:00:00Z",
+            "2023-04-30T00:00:00Z",
+            "2023-12-31T23:59:59Z",
+            "2023-06-01T12:00:00+23:59",
+            "2023-06-01T12:00:00.125-23:59",
+        ] {
+            assert!(parse(s).is_some(), "{s}");
+        }
+        assert_eq!(
+            parse("2023-06-01T12:00:00+23:59"),
+            parse("2023-05-31T12:01:00Z")
+        );
+    }
+
+    #[test]
+    fn rejects_days_the_month_lacks_and_out_of_range_offsets() {
+        for s in [
+            "2023-02-29T00:00:00Z",
+            "1900-02-29T00:00:00Z",
+            "2024-02-30T00:00:00Z",
+            "2023-04-31T00:00:00Z",
+            "2023-11-31T00:00:00Z",
+            "2023-01-32T00:00:00Z",
+            "2023-01-00T00:00:00Z",
+            "2023-06-01T12:00:00+24:00",
+            "2023-06-01T12:00:00-00:60",
+            "2023-06-01T12:00:00.5+99:99",
+            "2023-06-01T12:00:00+-1:00",
+        ] {
+            assert!(parse(s).is_none(), "{s}");
+        }
+    }
+}
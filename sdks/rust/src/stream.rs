@@ -0,0 +1,219 @@
+use crate::error::{Result, SdkError};
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+
+/// What a watch stream does when its buffer is full because the consumer is
+/// falling behind. Only events are ever dropped or held back: the error that
+/// ends a stream is queued even past the buffer size, and the end of the
+/// stream is always seen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overflow {
+    /// Stop reading from the server until the consumer catches up. HTTP/2 flow
+    /// control then applies backpressure to the server; nothing is lost.
+    #[default]
+    Block,
+    /// Discard the oldest buffered event to make room for the new one.
+    DropOldest,
+    /// Discard the incoming event and keep what is already buffered.
+    DropNewest,
+}
+
+/// Buffering behavior for server-streaming watch methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamConfig {
+    pub buffer: usize,
+    pub overflow: Overflow,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            buffer: 256,
+            overflow: Overflow::Block,
+        }
+    }
+}
+
+struct State<T> {
+    queue: VecDeque<Result<T>>,
+    done: bool,
+    waker: Option<Waker>,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    space: Notify,
+}
+
+/// A stream of events from the control plane, buffered according to a
+/// [`StreamConfig`]. Dropping it cancels the underlying RPC.
+pub struct EventStream<T> {
+    shared: Arc<Shared<T>>,
+    task: JoinHandle<()>,
+}
+
+impl<T: Send + 'static> EventStream<T> {
+    pub(crate) fn spawn<P, F>(mut source: tonic::Streaming<P>, config: StreamConfig, map: F) -> Self
+    where
+        P: Send + 'static,
+        F: Fn(P) -> T + Send + 'static,
+    {
+        let capacity = config.buffer.max(1);
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::with_capacity(capacity),
+                done: false,
+                waker: None,
+            }),
+            space: Notify::new(),
+        });
+        let producer = Arc::clone(&shared);
+        let task = tokio::spawn(async move {
+            while let Some(item) = source.next().await {
+                let item = item.map(&map).map_err(SdkError::from_status);
+                let failed = item.is_err();
+                producer.push(item, capacity, config.overflow).await;
+                if failed {
+                    break;
+                }
+            }
+            producer.finish();
+        });
+        Self { shared, task }
+    }
+}
+
+impl<T> Shared<T> {
+    async fn push(&self, item: Result<T>, capacity: usize, overflow: Overflow) {
+        // An error ends the stream, so it must reach the consumer.
+        let terminal = item.is_err();
+        let mut item = Some(item);
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if terminal || state.queue.len() < capacity {
+                    state.queue.extend(item.take());
+                } else {
+                    match overflow {
+                        Overflow::Block => {}
+                        Overflow::DropOldest => {
+                            state.queue.pop_front();
+                            state.queue.extend(item.take());
+                        }
+                        Overflow::DropNewest => {
+                            item.take();
+                        }
+                    }
+                }
+                if item.is_none() {
+                    if let Some(waker) = state.waker.take() {
+                        waker.wake();
+                    }
+                    return;
+                }
+            }
+            self.space.notified().await;
+        }
+    }
+
+    fn finish(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.done = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<T> Stream for EventStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut state = self.shared.state.lock().unwrap();
+        if let Some(item) = state.queue.pop_front() {
+            drop(state);
+            self.shared.space.notify_one();
+            return Poll::Ready(Some(item));
+        }
+        if state.done {
+            return Poll::Ready(None);
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+impl<T> Drop for EventStream<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shared() -> Shared<u32> {
+        Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                done: false,
+                waker: None,
+            }),
+            space: Notify::new(),
+        }
+    }
+
+    fn drain(shared: &Shared<u32>) -> Vec<std::result::Result<u32, String>> {
+        let mut state = shared.state.lock().unwrap();
+        state
+            .queue
+            .drain(..)
+            .map(|item| item.map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn drop_newest_keeps_the_terminal_error() {
+        let shared = shared();
+        for i in 0..3 {
+            shared.push(Ok(i), 2, Overflow::DropNewest).await;
+        }
+        let err = SdkError::Timeout("gone".into());
+        shared.push(Err(err), 2, Overflow::DropNewest).await;
+        let items = drain(&shared);
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[..2], [Ok(0), Ok(1)]);
+        assert!(items[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_keeps_the_terminal_error_and_the_events() {
+        let shared = shared();
+        for i in 0..3 {
+            shared.push(Ok(i), 2, Overflow::DropOldest).await;
+        }
+        let err = SdkError::Timeout("gone".into());
+        shared.push(Err(err), 2, Overflow::DropOldest).await;
+        let items = drain(&shared);
+        assert_eq!(items[..2], [Ok(1), Ok(2)]);
+        assert!(items[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn block_does_not_hold_back_the_terminal_error() {
+        let shared = shared();
+        shared.push(Ok(0), 1, Overflow::Block).await;
+        let err = SdkError::Timeout("gone".into());
+        // Would wait for space forever if the error were held back.
+        shared.push(Err(err), 1, Overflow::Block).await;
+        shared.finish();
+        assert_eq!(drain(&shared).len(), 2);
+        assert!(shared.state.lock().unwrap().done);
+    }
+}
@@ -0,0 +1,259 @@
+//! An in-process gRPC server for the client's unit tests. It records every
+//! call it receives, headers included, and answers with whatever the test's
+//! handler returns, so tests can check what the client puts on the wire.
+
+use crate::AgentPlatformClient;
+use prost::bytes::{Buf, BufMut};
+use prost::Message;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::codec::{Codec, DecodeBuf, Decoder, EncodeBuf, Encoder};
+use tonic::codegen::{http, Body, BoxFuture, Service, StdError};
+use tonic::metadata::MetadataMap;
+use tonic::server::{Grpc, NamedService, StreamingService};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Services the server answers for, by their index in the `Named` type.
+const SERVICES: [&str; 3] = [
+    "agent_platform.ControlPlane",
+    "grpc.health.v1.Health",
+    "grpc.reflection.v1alpha.ServerReflection",
+];
+
+/// One call as the server saw it.
+#[derive(Debug, Clone)]
+pub(crate) struct Call {
+    /// `/package.Service/Method`.
+    pub path: String,
+    pub metadata: MetadataMap,
+    /// Encoded request messages; one for unary calls.
+    pub messages: Vec<Vec<u8>>,
+}
+
+impl Call {
+    /// The RPC name, e.g. `GetOrganization`.
+    pub fn method(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or_default()
+    }
+
+    /// Every value sent for header `key`.
+    pub fn headers(&self, key: &str) -> Vec<String> {
+        self.metadata
+            .get_all(key)
+            .iter()
+            .map(|v| v.to_str().unwrap().to_string())
+            .collect()
+    }
+
+    /// The first request message.
+    pub fn request<M: Message + Default>(&self) -> M {
+        M::decode(self.messages[0].as_slice()).expect("request decodes")
+    }
+}
+
+/// Encoded reply messages, or the status to fail the call with.
+pub(crate) type Reply = Result<Vec<Vec<u8>>, Box<Status>>;
+
+type Handler = Arc<dyn Fn(&Call) -> Reply + Send + Sync>;
+
+/// Encodes a single reply message.
+pub(crate) fn reply<M: Message>(message: &M) -> Reply {
+    Ok(vec![message.encode_to_vec()])
+}
+
+pub(crate) fn fail(status: Status) -> Reply {
+    Err(Box::new(status))
+}
+
+pub(crate) struct TestServer {
+    addr: String,
+    calls: Arc<Mutex<Vec<Call>>>,
+    shutdown: oneshot::Sender<()>,
+    serving: JoinHandle<()>,
+}
+
+impl TestServer {
+    /// Starts a server on a free local port that answers every call with
+    /// `handler`. It runs until [`stop`](Self::stop) or the end of the
+    /// test's runtime.
+    pub async fn start(handler: impl Fn(&Call) -> Reply + Send + Sync + 'static) -> Self {
+        Self::start_at("127.0.0.1:0", handler).await
+    }
+
+    /// [`start`](Self::start) on `addr`, e.g. that of a stopped server.
+    pub async fn start_at(
+        addr: &str,
+        handler: impl Fn(&Call) -> Reply + Send + Sync + 'static,
+    ) -> Self {
+        let listener = TcpListener::bind(addr).await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let shared = Shared {
+            handler: Arc::new(handler),
+            calls: calls.clone(),
+        };
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = Server::builder()
+            .add_service(Named::<0>(shared.clone()))
+            .add_service(Named::<1>(shared.clone()))
+            .add_service(Named::<2>(shared))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                let _ = stopped.await;
+            });
+        let serving = tokio::spawn(async {
+            server.await.unwrap();
+        });
+        Self {
+            addr,
+            calls,
+            shutdown,
+            serving,
+        }
+    }
+
+    /// Shuts the server down, closing its connections, and returns the
+    /// `host:port` it listened on.
+    pub async fn stop(self) -> String {
+        let _ = self.shutdown.send(());
+        self.serving.await.unwrap();
+        self.addr.trim_start_matches("http://").to_string()
+    }
+
+    /// `http://host:port`, for connecting other than through
+    /// [`client`](Self::client).
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+
+    pub async fn client(&self) -> AgentPlatformClient {
+        AgentPlatformClient::connect(&self.addr).await.unwrap()
+    }
+
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// RPC names received so far, in order.
+    pub fn methods(&self) -> Vec<String> {
+        self.calls()
+            .iter()
+            .map(|c| c.method().to_string())
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+struct Shared {
+    handler: Handler,
+    calls: Arc<Mutex<Vec<Call>>>,
+}
+
+#[derive(Clone)]
+struct Named<const N: usize>(Shared);
+
+impl<const N: usize> NamedService for Named<N> {
+    const NAME: &'static str = SERVICES[N];
+}
+
+impl<const N: usize, B> Service<http::Request<B>> for Named<N>
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<tonic::body::BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Infallible>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Infallible>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: http::Request<B>) -> Self::Future {
+        let handle = Handle {
+            path: req.uri().path().to_string(),
+            shared: self.0.clone(),
+        };
+        Box::pin(async move { Ok(Grpc::new(RawCodec).streaming(handle, req).await) })
+    }
+}
+
+/// Serves one call. Every RPC is handled as bidirectional streaming, which
+/// is the same on the wire as the narrower kinds.
+struct Handle {
+    path: String,
+    shared: Shared,
+}
+
+impl StreamingService<Vec<u8>> for Handle {
+    type Response = Vec<u8>;
+    type ResponseStream = tokio_stream::Iter<std::vec::IntoIter<Result<Vec<u8>, Status>>>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, req: Request<Streaming<Vec<u8>>>) -> Self::Future {
+        let path = std::mem::take(&mut self.path);
+        let shared = self.shared.clone();
+        Box::pin(async move {
+            let metadata = req.metadata().clone();
+            let mut stream = req.into_inner();
+            let mut messages = Vec::new();
+            while let Some(message) = stream.message().await? {
+                messages.push(message);
+            }
+            let call = Call {
+                path,
+                metadata,
+                messages,
+            };
+            let result = (shared.handler)(&call);
+            shared.calls.lock().unwrap().push(call);
+            let replies = result.map_err(|s| *s)?.into_iter().map(Ok);
+            let replies = replies.collect::<Vec<_>>();
+            Ok(Response::new(tokio_stream::iter(replies)))
+        })
+    }
+}
+
+/// Passes message bytes through undecoded.
+struct RawCodec;
+
+impl Codec for RawCodec {
+    type Encode = Vec<u8>;
+    type Decode = Vec<u8>;
+    type Encoder = RawCodec;
+    type Decoder = RawCodec;
+
+    fn encoder(&mut self) -> RawCodec {
+        RawCodec
+    }
+
+    fn decoder(&mut self) -> RawCodec {
+        RawCodec
+    }
+}
+
+impl Encoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn encode(&mut self, item: Vec<u8>, dst: &mut EncodeBuf<'_>) -> Result<(), Status> {
+        dst.put_slice(&item);
+        Ok(())
+    }
+}
+
+impl Decoder for RawCodec {
+    type Item = Vec<u8>;
+    type Error = Status;
+
+    fn decode(&mut self, src: &mut DecodeBuf<'_>) -> Result<Option<Vec<u8>>, Status> {
+        let mut message = vec![0; src.remaining()];
+        src.copy_to_slice(&mut message);
+        Ok(Some(message))
+    }
+}
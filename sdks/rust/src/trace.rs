@@ -0,0 +1,213 @@
+//! Per-call `tracing` spans, compiled in with the `tracing` feature.
+//!
+//! Each span is named `agent_platform.rpc` and carries `rpc`, `org_id` and
+//! `agent_id` when the request has them, and, once the call finishes,
+//! `outcome` (`ok` or an error code from [`SdkError::to_serializable`]) and
+//! `elapsed_ms`. Only ids are recorded, never request payloads, so claims
+//! and tokens stay out of logs.
+
+use crate::error::Result;
+#[cfg(feature = "tracing")]
+use crate::error::SdkError;
+use std::future::Future;
+
+/// Ids a request is scoped to, for span fields.
+pub(crate) trait Scoped {
+    fn org_id(&self) -> Option<&str> {
+        None
+    }
+
+    fn agent_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+impl<T> Scoped for T {}
+
+#[cfg(feature = "tracing")]
+pub(crate) struct CallSpan {
+    span: tracing::Span,
+    started: std::time::Instant,
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct CallSpan;
+
+impl CallSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(method: &'static str, org_id: Option<&str>, agent_id: Option<&str>) -> Self {
+        let span = tracing::info_span!(
+            "agent_platform.rpc",
+            rpc = method,
+            org_id = tracing::field::Empty,
+            agent_id = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+            elapsed_ms = tracing::field::Empty,
+        );
+        if let Some(org_id) = org_id {
+            span.record("org_id", org_id);
+        }
+        if let Some(agent_id) = agent_id {
+            span.record("agent_id", agent_id);
+        }
+        Self {
+            span,
+            started: std::time::Instant::now(),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline]
+    pub(crate) fn new(
+        _method: &'static str,
+        _org_id: Option<&str>,
+        _agent_id: Option<&str>,
+    ) -> Self {
+        Self
+    }
+
+    /// Runs `call` inside the span and records how it ended.
+    #[cfg(feature = "tracing")]
+    pub(crate) async fn run<T>(self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        use tracing::Instrument;
+        let result = call.instrument(self.span.clone()).await;
+        let outcome = match &result {
+            Ok(_) => "ok".to_string(),
+            Err(e) => SdkError::to_serializable(e).code,
+        };
+        self.span.record("outcome", outcome.as_str());
+        self.span
+            .record("elapsed_ms", self.started.elapsed().as_millis() as u64);
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline]
+    pub(crate) async fn run<T>(self, call: impl Future<Output = Result<T>>) -> Result<T> {
+        call.await
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod scopes {
+    use super::Scoped;
+    use crate::proto::*;
+
+    fn non_empty(s: &str) -> Option<&str> {
+        (!s.is_empty()).then_some(s)
+    }
+
+    macro_rules! org_and_agent {
+        ($($t:ty),* $(,)?) => {$(
+            impl Scoped for $t {
+                fn org_id(&self) -> Option<&str> {
+                    non_empty(&self.org_id)
+                }
+
+                fn agent_id(&self) -> Option<&str> {
+                    non_empty(&self.agent_id)
+                }
+            }
+        )*};
+    }
+
+    macro_rules! org_only {
+        ($($t:ty),* $(,)?) => {$(
+            impl Scoped for $t {
+                fn org_id(&self) -> Option<&str> {
+                    non_empty(&self.org_id)
+                }
+            }
+        )*};
+    }
+
+    macro_rules! unscoped {
+        ($($t:ty),* $(,)?) => {$(impl Scoped for $t {})*};
+    }
+
+    org_and_agent!(
+        GetAgentRequest,
+        GetAgentCardRequest,
+        DeactivateAgentRequest,
+        ReactivateAgentRequest,
+        UpdateAgentRequest,
+        GetAgentConfigRequest,
+        SetPolicyRequest,
+        PatchPolicyRequest,
+        GetPolicyRequest,
+        GetPolicyAtRequest,
+        EvaluatePolicyRequest,
+        EvaluatePoliciesRequest,
+        SetRateLimitRequest,
+        CheckRateLimitRequest,
+        BacktestPolicyRequest,
+        GetEffectivePolicyRequest,
+        SchedulePolicyRequest,
+        ListScheduledChangesRequest,
+        SetBudgetRequest,
+        GetBudgetRequest,
+        ResetBudgetRequest,
+        CheckBudgetRequest,
+        ConsumeBudgetRequest,
+        RecommendBudgetRequest,
+        ListOpenReservationsRequest,
+        ReportUsageRequest,
+        ReportExecutionRequest,
+        GetUsageRequest,
+        ListUsageReportsRequest,
+        GetUsageByModelRequest,
+        GetAuditLogRequest,
+        WatchActivityRequest,
+        GetAgentHealthRequest,
+        ExecuteTaskRequest,
+    );
+
+    org_only!(
+        GetOrgRequest,
+        UpdateOrgRequest,
+        DeleteOrgRequest,
+        PreviewDeleteOrgRequest,
+        RegisterAgentRequest,
+        RegisterAgentsRequest,
+        ListAgentsRequest,
+        ListAgentsWithToolRequest,
+        OffboardUserRequest,
+        GetSchedulingInfoRequest,
+        ListPoliciesRequest,
+        DeletePolicyRequest,
+        SetDefaultAgentPolicyRequest,
+        GetDefaultAgentPolicyRequest,
+        EvaluateMatrixRequest,
+        FindPoliciesByToolRequest,
+        GetEffectivePoliciesRequest,
+        DeprecateToolRequest,
+        CancelScheduledChangeRequest,
+        ReleaseReservationRequest,
+        WatchBudgetResetsRequest,
+        TransferBudgetRequest,
+        CheckBudgetAllocationRequest,
+        ListExhaustedAgentsRequest,
+        SetBudgetsRequest,
+        GetUsageByUserRequest,
+        ListUsedToolsRequest,
+        TailAuditLogRequest,
+        ExportAuditLogSignedRequest,
+        SamplePolicyDecisionsRequest,
+        WatchChangesRequest,
+    );
+
+    unscoped!(
+        CreateOrgRequest,
+        CreateOrgsRequest,
+        CreateOrgWithAdminRequest,
+        ListOrgsRequest,
+        GetServerTimeRequest,
+    );
+
+    impl Scoped for ValidateCredentialRequest {
+        fn agent_id(&self) -> Option<&str> {
+            non_empty(&self.agent_id)
+        }
+    }
+}